@@ -0,0 +1,161 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+use std::path::PathBuf;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use langtools::{
+    domain::{source_info::SourceInfo, token::TokenKind},
+    lexing::lexer::Lexer,
+    messaging::message_context::MessageContext,
+    sourcing::{read_source::ReadSource, source_reader::SourceReader, source_string::SourceString},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+enum TokenKindBench {
+    Keyword,
+    Identifier,
+    Number,
+}
+
+impl TokenKind for TokenKindBench {}
+
+const KEYWORDS: [&str; 6] = ["fn", "let", "if", "else", "while", "return"];
+
+fn eat_identifier_rest(source_reader: &mut dyn ReadSource) {
+    while source_reader
+        .peek_next()
+        .is_ok_and(|value| value.is_ascii_alphanumeric() || value == '_')
+    {
+        let _ = source_reader.eat_next();
+    }
+}
+
+// A lexer for keywords, identifiers and numbers, separated by whitespace. Keywords are triggers of their own, so a
+// keyword that turns out to be the start of a longer identifier is lexed as an identifier by its callback.
+fn representative_lexer() -> Lexer<TokenKindBench> {
+    let mut lexer = Lexer::<TokenKindBench>::new();
+
+    for value in ('a'..='z').chain('A'..='Z').chain(['_']) {
+        lexer
+            .add_trigger(&value.to_string(), |source_reader, _| {
+                eat_identifier_rest(source_reader);
+                Some(TokenKindBench::Identifier)
+            })
+            .unwrap();
+    }
+
+    for keyword in KEYWORDS {
+        lexer
+            .add_trigger(keyword, |source_reader, _| {
+                if source_reader
+                    .peek_next()
+                    .is_ok_and(|value| value.is_ascii_alphanumeric() || value == '_')
+                {
+                    eat_identifier_rest(source_reader);
+                    Some(TokenKindBench::Identifier)
+                } else {
+                    Some(TokenKindBench::Keyword)
+                }
+            })
+            .unwrap();
+    }
+
+    for value in '0'..='9' {
+        lexer
+            .add_trigger(&value.to_string(), |source_reader, _| {
+                while source_reader
+                    .peek_next()
+                    .is_ok_and(|value| value.is_ascii_digit())
+                {
+                    let _ = source_reader.eat_next();
+                }
+
+                Some(TokenKindBench::Number)
+            })
+            .unwrap();
+    }
+
+    for value in [" ", "\n"] {
+        lexer.add_trigger(value, |_, _| None).unwrap();
+    }
+
+    lexer
+}
+
+// Generates `token_count` words separated by whitespace. A fixed seed keeps the input the same from run to run so that
+// results can be compared.
+fn generate_input(token_count: usize) -> String {
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = || {
+        seed = seed
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (seed >> 33) as usize
+    };
+
+    let mut result = String::new();
+
+    for index in 0..token_count {
+        match next() % 3 {
+            0 => result.push_str(KEYWORDS[next() % KEYWORDS.len()]),
+            1 => {
+                result.push_str("name_");
+                result.push_str(&(next() % 1000).to_string());
+            }
+            _ => result.push_str(&(next() % 100_000).to_string()),
+        }
+
+        result.push(if index % 12 == 11 { '\n' } else { ' ' });
+    }
+
+    result
+}
+
+fn lex_count(lexer: &Lexer<TokenKindBench>, input: &str) -> usize {
+    let mut source = SourceString::new(SourceInfo::new(PathBuf::from("bench")), input);
+    let mut source_reader = SourceReader::new(&mut source);
+    source_reader
+        .enable_buffering()
+        .expect("buffering should not be enabled on a new source reader");
+
+    let mut message_context = MessageContext::new();
+
+    lexer.lex(&mut source_reader, &mut message_context).count()
+}
+
+fn bench_lexer_throughput(c: &mut Criterion) {
+    let lexer = representative_lexer();
+    let input = generate_input(10_000);
+    let token_count = lex_count(&lexer, &input);
+
+    assert_eq!(token_count, 10_000);
+
+    let mut group = c.benchmark_group("lexer");
+    group.throughput(Throughput::Elements(token_count as u64));
+    group.bench_function("keywords_identifiers_numbers", |b| {
+        b.iter(|| lex_count(&lexer, &input))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_lexer_throughput);
+criterion_main!(benches);
@@ -20,6 +20,8 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+pub mod line_index;
 pub mod source_info;
 pub mod source_location;
+pub mod source_span;
 pub mod token;
@@ -0,0 +1,108 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+use super::source_location::{Line, Offset, LINE_INITIAL, OFFSET_INITIAL};
+
+// The offset that each line starts at, for turning offsets back into lines and columns. Offsets count characters the
+// same way that `SourceString` does, and newlines are folded the same way as `SourceReader` so that `\r\n` and `\r`
+// each end a single line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineIndex {
+    line_starts: Vec<Offset>,
+}
+
+impl LineIndex {
+    pub fn new(data: &str) -> Self {
+        let mut line_starts = Vec::from([OFFSET_INITIAL]);
+        let mut iter = data.chars().peekable();
+        let mut offset = OFFSET_INITIAL;
+
+        while let Some(value) = iter.next() {
+            offset += 1;
+
+            match value {
+                '\r' => {
+                    if let Some('\r') | Some('\n') = iter.peek() {
+                        iter.next();
+                        offset += 1;
+                    }
+
+                    line_starts.push(offset);
+                }
+                '\n' => line_starts.push(offset),
+                _ => {}
+            }
+        }
+
+        Self { line_starts }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    pub fn try_get_line_start(&self, line: Line) -> Option<Offset> {
+        let index = line.checked_sub(LINE_INITIAL)? as usize;
+
+        self.line_starts.get(index).copied()
+    }
+
+    // The line that contains `offset`. Offsets past the end of the source are on the last line.
+    pub fn line_of(&self, offset: Offset) -> Line {
+        let index = self
+            .line_starts
+            .partition_point(|line_start| *line_start <= offset);
+
+        index as Line - 1 + LINE_INITIAL
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_index() {
+        let index = LineIndex::new("ab\ncd\r\nef\rgh");
+
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(index.try_get_line_start(0), None);
+        assert_eq!(index.try_get_line_start(1), Some(0));
+        assert_eq!(index.try_get_line_start(2), Some(3));
+        assert_eq!(index.try_get_line_start(3), Some(7));
+        assert_eq!(index.try_get_line_start(4), Some(10));
+        assert_eq!(index.try_get_line_start(5), None);
+
+        assert_eq!(index.line_of(0), 1);
+        assert_eq!(index.line_of(2), 1);
+        assert_eq!(index.line_of(3), 2);
+        assert_eq!(index.line_of(9), 3);
+        assert_eq!(index.line_of(100), 4);
+    }
+
+    #[test]
+    fn test_line_index_empty() {
+        let index = LineIndex::new("");
+
+        assert_eq!(index.line_count(), 1);
+        assert_eq!(index.line_of(0), 1);
+    }
+}
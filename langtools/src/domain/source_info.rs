@@ -20,15 +20,57 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use std::path::PathBuf;
+use std::{cmp::Ordering, path::PathBuf};
 
-#[derive(Clone, Debug, PartialEq, PartialOrd, Eq)]
+// A cheap handle for a source, for grouping and keying by source without comparing paths. Ids are assigned by
+// `SourceMap::add` in the order sources are added to it, so they are only meaningful with the map that assigned them.
+pub type SourceId = u32;
+
+// Comparisons are implemented by hand so that a source is identified by its path alone. The id depends on the map the
+// source was added to and `had_bom` on how it was read, so neither takes part.
+#[derive(Clone, Debug)]
 pub struct SourceInfo {
     pub path: PathBuf,
+    // Set when a leading byte order mark was skipped while reading, so that it can be written back out
+    pub had_bom: bool,
+    id: Option<SourceId>,
 }
 
 impl SourceInfo {
     pub fn new(path: PathBuf) -> Self {
-        SourceInfo { path }
+        SourceInfo {
+            path,
+            had_bom: false,
+            id: None,
+        }
+    }
+
+    // The id assigned when this source was added to a `SourceMap`, or `None` if it hasn't been
+    pub fn id(&self) -> Option<SourceId> {
+        self.id
+    }
+
+    pub fn set_id(&mut self, id: SourceId) {
+        self.id = Some(id);
+    }
+}
+
+impl PartialEq for SourceInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+    }
+}
+
+impl Eq for SourceInfo {}
+
+impl PartialOrd for SourceInfo {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SourceInfo {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.path.cmp(&other.path)
     }
 }
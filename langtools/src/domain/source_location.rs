@@ -20,7 +20,10 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use super::source_info::SourceInfo;
+use super::{
+    line_index::LineIndex,
+    source_info::{SourceId, SourceInfo},
+};
 use std::path::PathBuf;
 
 pub type Offset = usize;
@@ -31,7 +34,16 @@ pub const OFFSET_INITIAL: Offset = 0;
 pub const LINE_INITIAL: Line = 1;
 pub const COLUMN_INITIAL: Column = 1;
 
-#[derive(Clone, Debug, PartialEq, PartialOrd, Eq)]
+// The difference from one location to another. Each field is the other location's value minus this one's, so the
+// column delta is relative even across lines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LocationDelta {
+    pub offset: isize,
+    pub lines: i64,
+    pub columns: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord)]
 pub struct SourceLocation {
     pub info: SourceInfo,
     pub offset: Offset,
@@ -52,4 +64,115 @@ impl SourceLocation {
             column,
         }
     }
+
+    // Computes the line and column of `offset` from the line starts in `index`, which must have been built from the
+    // same source as `info`
+    pub fn from_offset(info: SourceInfo, offset: Offset, index: &LineIndex) -> Self {
+        let line = index.line_of(offset);
+        let line_start = index
+            .try_get_line_start(line)
+            .expect("line from line index should have a start");
+
+        Self::new_from_info(
+            info,
+            offset,
+            line,
+            (offset - line_start) as Column + COLUMN_INITIAL,
+        )
+    }
+
+    pub fn source_id(&self) -> Option<SourceId> {
+        self.info.id()
+    }
+
+    // Returns `None` if the locations are in different sources
+    pub fn distance_to(&self, other: &SourceLocation) -> Option<LocationDelta> {
+        if self.info != other.info {
+            return None;
+        }
+
+        Some(LocationDelta {
+            offset: other.offset as isize - self.offset as isize,
+            lines: other.line as i64 - self.line as i64,
+            columns: other.column as i64 - self.column as i64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_location_distance_to_same_line() {
+        let start = SourceLocation::new(PathBuf::from("--"), 2, 1, 3);
+        let end = SourceLocation::new(PathBuf::from("--"), 7, 1, 8);
+
+        assert_eq!(
+            start.distance_to(&end),
+            Some(LocationDelta {
+                offset: 5,
+                lines: 0,
+                columns: 5
+            })
+        );
+        assert_eq!(
+            end.distance_to(&start),
+            Some(LocationDelta {
+                offset: -5,
+                lines: 0,
+                columns: -5
+            })
+        );
+    }
+
+    #[test]
+    fn test_source_location_distance_to_across_lines() {
+        let start = SourceLocation::new(PathBuf::from("--"), 4, 1, 5);
+        let end = SourceLocation::new(PathBuf::from("--"), 10, 3, 2);
+
+        assert_eq!(
+            start.distance_to(&end),
+            Some(LocationDelta {
+                offset: 6,
+                lines: 2,
+                columns: -3
+            })
+        );
+    }
+
+    #[test]
+    fn test_source_location_distance_to_other_source() {
+        let a = SourceLocation::new(PathBuf::from("a"), 0, 1, 1);
+        let b = SourceLocation::new(PathBuf::from("b"), 0, 1, 1);
+
+        assert_eq!(a.distance_to(&b), None);
+    }
+
+    #[test]
+    fn test_source_location_from_offset() {
+        let info = SourceInfo::new(PathBuf::from("--"));
+        let index = LineIndex::new("ab\ncd\r\nef");
+
+        assert_eq!(
+            SourceLocation::from_offset(info.clone(), 0, &index),
+            SourceLocation::new(PathBuf::from("--"), 0, 1, 1)
+        );
+        assert_eq!(
+            SourceLocation::from_offset(info.clone(), 1, &index),
+            SourceLocation::new(PathBuf::from("--"), 1, 1, 2)
+        );
+        assert_eq!(
+            SourceLocation::from_offset(info.clone(), 3, &index),
+            SourceLocation::new(PathBuf::from("--"), 3, 2, 1)
+        );
+        assert_eq!(
+            SourceLocation::from_offset(info.clone(), 7, &index),
+            SourceLocation::new(PathBuf::from("--"), 7, 3, 1)
+        );
+        assert_eq!(
+            SourceLocation::from_offset(info, 8, &index),
+            SourceLocation::new(PathBuf::from("--"), 8, 3, 2)
+        );
+    }
 }
@@ -0,0 +1,110 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use super::source_location::SourceLocation;
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord)]
+pub struct SourceSpan {
+    pub start: SourceLocation,
+    pub end: SourceLocation,
+}
+
+// Spans are half-open: `end` is the location just after the last character
+impl SourceSpan {
+    pub fn new(start: SourceLocation, end: SourceLocation) -> Self {
+        Self { start, end }
+    }
+
+    // Returns `None` unless the spans are in the same source and share at least one character, so adjacent spans
+    // don't intersect
+    pub fn intersection(&self, other: &SourceSpan) -> Option<SourceSpan> {
+        if self.start.info != other.start.info {
+            return None;
+        }
+
+        let start = (&self.start).max(&other.start);
+        let end = (&self.end).min(&other.end);
+
+        if start < end {
+            Some(SourceSpan::new(start.clone(), end.clone()))
+        } else {
+            None
+        }
+    }
+
+    // The smallest span covering both spans, including anything between them
+    pub fn union(&self, other: &SourceSpan) -> SourceSpan {
+        assert!(
+            self.start.info == other.start.info,
+            "cannot take the union of spans in different sources"
+        );
+
+        SourceSpan::new(
+            (&self.start).min(&other.start).clone(),
+            (&self.end).max(&other.end).clone(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn span(start: usize, end: usize) -> SourceSpan {
+        SourceSpan::new(
+            SourceLocation::new(PathBuf::from("--"), start, 1, start as u32 + 1),
+            SourceLocation::new(PathBuf::from("--"), end, 1, end as u32 + 1),
+        )
+    }
+
+    #[test]
+    fn test_source_span_overlapping() {
+        assert_eq!(span(0, 4).intersection(&span(2, 6)), Some(span(2, 4)));
+        assert_eq!(span(2, 6).intersection(&span(0, 4)), Some(span(2, 4)));
+        assert_eq!(span(0, 6).intersection(&span(2, 4)), Some(span(2, 4)));
+        assert_eq!(span(0, 4).union(&span(2, 6)), span(0, 6));
+    }
+
+    #[test]
+    fn test_source_span_adjacent() {
+        assert_eq!(span(0, 2).intersection(&span(2, 4)), None);
+        assert_eq!(span(0, 2).union(&span(2, 4)), span(0, 4));
+    }
+
+    #[test]
+    fn test_source_span_disjoint() {
+        assert_eq!(span(0, 1).intersection(&span(3, 4)), None);
+        assert_eq!(span(3, 4).union(&span(0, 1)), span(0, 4));
+    }
+
+    #[test]
+    fn test_source_span_different_sources() {
+        let other = SourceSpan::new(
+            SourceLocation::new(PathBuf::from("other"), 0, 1, 1),
+            SourceLocation::new(PathBuf::from("other"), 4, 1, 5),
+        );
+
+        assert_eq!(span(0, 4).intersection(&other), None);
+    }
+}
@@ -20,16 +20,33 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use super::source_location::{Column, Line, Offset, SourceLocation};
-use std::{fmt::Debug, hash::Hash, path::PathBuf};
+use super::{
+    source_info::SourceId,
+    source_location::{Column, Line, Offset, SourceLocation, COLUMN_INITIAL},
+    source_span::SourceSpan,
+};
+use std::{collections::HashSet, fmt::Debug, hash::Hash, path::PathBuf};
 
-pub trait TokenKind: Debug + Clone + Copy + Eq + PartialOrd + Hash {}
+pub trait TokenKind: Debug + Clone + Copy + Eq + PartialOrd + Hash {
+    // The name used for this kind in error messages. Override it to show something like "`+`" or "a number" instead of
+    // the variant name.
+    fn display_name(&self) -> String {
+        format!("{self:?}")
+    }
+}
 
 #[derive(Clone, Debug, PartialEq, PartialOrd, Eq)]
 pub struct Token<TKind: TokenKind> {
     pub location: SourceLocation,
     pub text: String,
     pub kind: TKind,
+    pub trigger_length: Option<usize>,
+    pub trigger_prefix: Option<String>,
+    // How many characters of the source the text was read from, set by the lexer only when it differs from the length
+    // of the text. It does when `SourceReader` folds a "\r\n" or "\r\r" newline into a single '\n'.
+    pub source_length: Option<usize>,
+    pub leading_trivia: Vec<Token<()>>,
+    pub trailing_trivia: Vec<Token<()>>,
 }
 
 impl TokenKind for () {}
@@ -51,23 +68,354 @@ impl<TKind: TokenKind> Token<TKind> {
             location,
             text,
             kind,
+            trigger_length: None,
+            trigger_prefix: None,
+            source_length: None,
+            leading_trivia: Vec::new(),
+            trailing_trivia: Vec::new(),
         }
     }
 
     pub fn to_kindless(&self) -> Token<()> {
-        Token::new_from_location(self.location.clone(), self.text.clone(), ())
+        Token {
+            location: self.location.clone(),
+            text: self.text.clone(),
+            kind: (),
+            trigger_length: self.trigger_length,
+            trigger_prefix: self.trigger_prefix.clone(),
+            source_length: self.source_length,
+            leading_trivia: self.leading_trivia.clone(),
+            trailing_trivia: self.trailing_trivia.clone(),
+        }
+    }
+
+    pub fn map_kind<TKindTo: TokenKind>(self, mapping: fn(TKind) -> TKindTo) -> Token<TKindTo> {
+        Token {
+            location: self.location,
+            text: self.text,
+            kind: mapping(self.kind),
+            trigger_length: self.trigger_length,
+            trigger_prefix: self.trigger_prefix,
+            source_length: self.source_length,
+            leading_trivia: self.leading_trivia,
+            trailing_trivia: self.trailing_trivia,
+        }
+    }
+
+    pub fn end_location(&self) -> SourceLocation {
+        let mut result = self.location.clone();
+
+        for value in self.text.chars() {
+            if value == '\n' {
+                result.line += 1;
+                result.column = COLUMN_INITIAL;
+            } else {
+                result.column += 1;
+            }
+        }
+
+        result.offset += self.source_len();
+
+        result
+    }
+
+    // The number of source characters the token covers, which is the length of its text unless newlines were folded
+    pub fn source_len(&self) -> usize {
+        self.source_length
+            .unwrap_or_else(|| self.text.chars().count())
+    }
+
+    // Sets `source_length` from the offset that the token's source ends at, leaving it unset if it matches the text
+    pub fn set_source_end(&mut self, end_offset: Offset) {
+        let length = end_offset - self.location.offset;
+
+        self.source_length = if length == self.text.chars().count() {
+            None
+        } else {
+            Some(length)
+        };
+    }
+
+    pub fn source_id(&self) -> Option<SourceId> {
+        self.location.source_id()
+    }
+
+    pub fn span(&self) -> SourceSpan {
+        SourceSpan::new(self.location.clone(), self.end_location())
+    }
+
+    // Appends `other`, which must start where this token ends with no trivia between them. The result keeps this
+    // token's location, kind, trigger length and leading trivia, and takes the trailing trivia of `other`.
+    pub fn merge(mut self, other: Token<TKind>) -> Token<TKind> {
+        assert!(
+            self.end_location() == other.location
+                && self.trailing_trivia.is_empty()
+                && other.leading_trivia.is_empty(),
+            "cannot merge tokens that aren't adjacent"
+        );
+
+        let end_offset = other.end_location().offset;
+
+        self.text.push_str(&other.text);
+        self.set_source_end(end_offset);
+        self.trailing_trivia = other.trailing_trivia;
+
+        self
+    }
+}
+
+// The kinds in `kinds` in a stable order, for output that shouldn't depend on hash set iteration order. Kinds are
+// sorted by their `PartialOrd` order, falling back to their debug representations for kinds that can't be compared.
+pub fn sorted_kinds<TKind: TokenKind>(kinds: &HashSet<TKind>) -> Vec<TKind> {
+    let mut result: Vec<TKind> = kinds.iter().copied().collect();
+
+    result.sort_by(|a, b| {
+        a.partial_cmp(b)
+            .unwrap_or_else(|| format!("{a:?}").cmp(&format!("{b:?}")))
+    });
+
+    result
+}
+
+// The standard phrasing for an expected set of token kinds, like "expected `+`, `-`, or a number", for any error that
+// reports one. Display names are sorted so that messages don't depend on hash set iteration order.
+pub fn format_expected<TKind: TokenKind>(kinds: &HashSet<TKind>) -> String {
+    let mut names: Vec<String> = kinds.iter().map(TokenKind::display_name).collect();
+    names.sort();
+
+    match names.as_slice() {
+        [] => String::from("expected nothing"),
+        [name] => format!("expected {name}"),
+        [first, second] => format!("expected {first} or {second}"),
+        [rest @ .., last] => format!("expected {}, or {last}", rest.join(", ")),
+    }
+}
+
+// Reconstructs source text from tokens, including any trivia attached to them. This only round-trips when every
+// character was lexed into a token or trivia, for example with `Lexer::set_whitespace_kind`. Newlines come back
+// normalized to `\n` since `SourceReader` folds `\r\n` and `\r` while reading. A byte order mark recorded in
+// `SourceInfo::had_bom` is written back out at the start.
+pub fn tokens_to_source<TKind: TokenKind>(tokens: &[Token<TKind>]) -> String {
+    let mut result = String::new();
+
+    if tokens
+        .first()
+        .is_some_and(|token| token.location.info.had_bom)
+    {
+        result.push('\u{feff}');
+    }
+
+    for token in tokens {
+        for trivia in token.leading_trivia.iter() {
+            result.push_str(&trivia.text);
+        }
+
+        result.push_str(&token.text);
+
+        for trivia in token.trailing_trivia.iter() {
+            result.push_str(&trivia.text);
+        }
+    }
+
+    result
+}
+
+// Finds the ranges of the source that no token or trivia covers, as half-open `(start, end)` offset pairs. These are
+// the regions that the lexer skipped, including any before the first token and after the last one up to
+// `source_len`. Tokens must be in source order.
+pub fn coverage<TKind: TokenKind>(
+    tokens: &[Token<TKind>],
+    source_len: Offset,
+) -> Vec<(Offset, Offset)> {
+    let mut result = Vec::new();
+    let mut covered_to = 0;
+
+    let spans = tokens.iter().flat_map(|token| {
+        token
+            .leading_trivia
+            .iter()
+            .map(Token::span)
+            .chain([token.span()])
+            .chain(token.trailing_trivia.iter().map(Token::span))
+    });
+
+    for span in spans {
+        if span.start.offset > covered_to {
+            result.push((covered_to, span.start.offset));
+        }
+
+        covered_to = covered_to.max(span.end.offset);
+    }
+
+    if source_len > covered_to {
+        result.push((covered_to, source_len));
+    }
+
+    result
+}
+
+// Groups tokens by the line they're on, in the order they appear. A token that spans several lines, like a block
+// comment or a multiline string, belongs to the line it starts on. Lines without tokens are left out. Tokens must be
+// in source order.
+pub fn group_by_line<TKind: TokenKind>(tokens: &[Token<TKind>]) -> Vec<(Line, Vec<&Token<TKind>>)> {
+    let mut result: Vec<(Line, Vec<&Token<TKind>>)> = Vec::new();
+
+    for token in tokens {
+        match result.last_mut() {
+            Some((line, group)) if *line == token.location.line => group.push(token),
+            _ => result.push((token.location.line, Vec::from([token]))),
+        }
+    }
+
+    result
+}
+
+// Patches the locations of tokens, and of their trivia, after an edit at `from_offset` on `edited_line` that changed
+// the length of the source by `delta` characters and its line count by `line_delta`. Locations before `from_offset` are
+// left alone and the rest have their offsets and lines shifted, so unchanged tokens after an edit don't need to be
+// lexed again.
+//
+// Only the edited line has its columns changed. If `line_delta` is 0 then the edit stayed on that line, so locations
+// after it on the line are shifted by `delta` columns. Otherwise their columns depend on the text of the edit and are
+// left as they were, which means the edited line should be lexed again. Columns on later lines never change.
+pub fn shift_tokens<TKind: TokenKind>(
+    tokens: &mut [Token<TKind>],
+    from_offset: Offset,
+    edited_line: Line,
+    delta: isize,
+    line_delta: i32,
+) {
+    let shift = |location: &mut SourceLocation| {
+        if location.offset < from_offset {
+            return;
+        }
+
+        if line_delta == 0 && location.line == edited_line {
+            location.column = location
+                .column
+                .checked_add_signed(delta as i32)
+                .expect("column should not be shifted before the start of the line");
+        }
+
+        location.offset = location
+            .offset
+            .checked_add_signed(delta)
+            .expect("offset should not be shifted before the start of the source");
+        location.line = location
+            .line
+            .checked_add_signed(line_delta)
+            .expect("line should not be shifted before the start of the source");
+    };
+
+    for token in tokens.iter_mut() {
+        for trivia in token.leading_trivia.iter_mut() {
+            shift(&mut trivia.location);
+        }
+
+        shift(&mut token.location);
+
+        for trivia in token.trailing_trivia.iter_mut() {
+            shift(&mut trivia.location);
+        }
     }
 }
 
+// Compares two token streams by kind and text, ignoring locations and trivia, and returns the length of their common
+// prefix, the length of their common suffix, and the number of tokens in `new` between the two. The prefix and suffix
+// never overlap, so the tokens in `old` between them number `old.len() - prefix - suffix`. This can check that
+// relexing after an edit only changed the tokens around it.
+pub fn token_diff<TKind: TokenKind>(
+    old: &[Token<TKind>],
+    new: &[Token<TKind>],
+) -> (usize, usize, usize) {
+    let is_same = |a: &Token<TKind>, b: &Token<TKind>| a.kind == b.kind && a.text == b.text;
+
+    let prefix = old
+        .iter()
+        .zip(new)
+        .take_while(|(a, b)| is_same(a, b))
+        .count();
+
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| is_same(a, b))
+        .count();
+
+    (prefix, suffix, new.len() - prefix - suffix)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::source_info::SourceInfo;
 
     #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
     struct TokenKindTest {}
 
     impl TokenKind for TokenKindTest {}
 
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindNamed {
+        Plus,
+        Minus,
+        Number,
+    }
+
+    impl TokenKind for TokenKindNamed {
+        fn display_name(&self) -> String {
+            String::from(match self {
+                TokenKindNamed::Plus => "`+`",
+                TokenKindNamed::Minus => "`-`",
+                TokenKindNamed::Number => "a number",
+            })
+        }
+    }
+
+    #[test]
+    fn test_format_expected() {
+        assert_eq!(
+            format_expected(&HashSet::from([TokenKindNamed::Number])),
+            "expected a number"
+        );
+        assert_eq!(
+            format_expected(&HashSet::from([
+                TokenKindNamed::Number,
+                TokenKindNamed::Plus
+            ])),
+            "expected `+` or a number"
+        );
+        assert_eq!(
+            format_expected(&HashSet::from([
+                TokenKindNamed::Number,
+                TokenKindNamed::Minus,
+                TokenKindNamed::Plus
+            ])),
+            "expected `+`, `-`, or a number"
+        );
+    }
+
+    #[test]
+    fn test_format_expected_debug_names() {
+        #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+        enum TokenKindUnnamed {
+            B,
+            A,
+        }
+
+        impl TokenKind for TokenKindUnnamed {}
+
+        assert_eq!(
+            format_expected(&HashSet::from([TokenKindUnnamed::B, TokenKindUnnamed::A])),
+            "expected A or B"
+        );
+        assert_eq!(
+            format_expected(&HashSet::<TokenKindUnnamed>::new()),
+            "expected nothing"
+        );
+    }
+
     #[test]
     fn test_token_to_kindless() {
         let token_with_kind = Token::<TokenKindTest>::new(
@@ -83,4 +431,274 @@ mod tests {
         assert_eq!(token_without_kind.location, token_with_kind.location);
         assert_eq!(token_without_kind.text, token_with_kind.text);
     }
+
+    #[test]
+    fn test_token_end_location() {
+        let token = Token::<TokenKindTest>::new(
+            PathBuf::from("--"),
+            2,
+            1,
+            3,
+            String::from("a\nbc"),
+            TokenKindTest {},
+        );
+
+        assert_eq!(
+            token.end_location(),
+            SourceLocation::new(PathBuf::from("--"), 6, 2, 3)
+        );
+    }
+
+    #[test]
+    fn test_token_source_id() {
+        let mut info_a = SourceInfo::new(PathBuf::from("a.txt"));
+        info_a.set_id(0);
+        let mut info_b = SourceInfo::new(PathBuf::from("b.txt"));
+        info_b.set_id(1);
+
+        let token = |info: &SourceInfo, offset, line, text: &str| {
+            Token::new_from_location(
+                SourceLocation::new_from_info(info.clone(), offset, line, 1),
+                String::from(text),
+                (),
+            )
+        };
+        let a = token(&info_a, 0, 1, "x");
+        let a_later = token(&info_a, 3, 3, "y");
+        let b = token(&info_b, 0, 1, "x");
+
+        assert!(a.source_id().is_some());
+        assert_eq!(a.source_id(), a_later.source_id());
+        assert_ne!(a.source_id(), b.source_id());
+        assert_eq!(
+            Token::new(PathBuf::from("a.txt"), 0, 1, 1, String::from("x"), ()).source_id(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_token_merge() {
+        let token = Token::<TokenKindTest>::new(
+            PathBuf::from("--"),
+            0,
+            1,
+            1,
+            String::from("a\n"),
+            TokenKindTest {},
+        )
+        .merge(Token::new(
+            PathBuf::from("--"),
+            2,
+            2,
+            1,
+            String::from("b"),
+            TokenKindTest {},
+        ));
+
+        assert_eq!(
+            token.location,
+            SourceLocation::new(PathBuf::from("--"), 0, 1, 1)
+        );
+        assert_eq!(token.text, "a\nb");
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot merge tokens that aren't adjacent")]
+    fn test_token_merge_not_adjacent() {
+        Token::<TokenKindTest>::new(
+            PathBuf::from("--"),
+            0,
+            1,
+            1,
+            String::from("a"),
+            TokenKindTest {},
+        )
+        .merge(Token::new(
+            PathBuf::from("--"),
+            2,
+            1,
+            3,
+            String::from("b"),
+            TokenKindTest {},
+        ));
+    }
+
+    fn tokens_ab_cd_ef() -> Vec<Token<TokenKindTest>> {
+        Vec::from([
+            Token::new(
+                PathBuf::from("--"),
+                0,
+                1,
+                1,
+                String::from("ab"),
+                TokenKindTest {},
+            ),
+            Token::new(
+                PathBuf::from("--"),
+                3,
+                1,
+                4,
+                String::from("cd"),
+                TokenKindTest {},
+            ),
+            Token::new(
+                PathBuf::from("--"),
+                6,
+                2,
+                1,
+                String::from("ef"),
+                TokenKindTest {},
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_shift_tokens_insert_within_line() {
+        // "ab cd\nef" -> "ab xxcd\nef"
+        let mut tokens = tokens_ab_cd_ef();
+
+        shift_tokens(&mut tokens, 3, 1, 2, 0);
+
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| token.location.clone())
+                .collect::<Vec<_>>(),
+            Vec::from([
+                SourceLocation::new(PathBuf::from("--"), 0, 1, 1),
+                SourceLocation::new(PathBuf::from("--"), 5, 1, 6),
+                SourceLocation::new(PathBuf::from("--"), 8, 2, 1),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_shift_tokens_insert_end_of_line() {
+        // "ab cd\nef" -> "ab cdxx\nef"
+        let mut tokens = tokens_ab_cd_ef();
+
+        shift_tokens(&mut tokens, 5, 1, 2, 0);
+
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| token.location.clone())
+                .collect::<Vec<_>>(),
+            Vec::from([
+                SourceLocation::new(PathBuf::from("--"), 0, 1, 1),
+                SourceLocation::new(PathBuf::from("--"), 3, 1, 4),
+                SourceLocation::new(PathBuf::from("--"), 8, 2, 1),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_shift_tokens_insert_line() {
+        // "ab cd\nef" -> "ab cd\nxx\nef"
+        let mut tokens = tokens_ab_cd_ef();
+
+        shift_tokens(&mut tokens, 6, 2, 3, 1);
+
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| token.location.clone())
+                .collect::<Vec<_>>(),
+            Vec::from([
+                SourceLocation::new(PathBuf::from("--"), 0, 1, 1),
+                SourceLocation::new(PathBuf::from("--"), 3, 1, 4),
+                SourceLocation::new(PathBuf::from("--"), 9, 3, 1),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_shift_tokens_trivia() {
+        let mut tokens = tokens_ab_cd_ef();
+        tokens[1].leading_trivia = Vec::from([Token::new(
+            PathBuf::from("--"),
+            2,
+            1,
+            3,
+            String::from(" "),
+            (),
+        )]);
+
+        shift_tokens(&mut tokens, 2, 1, 1, 0);
+
+        assert_eq!(
+            tokens[1].leading_trivia[0].location,
+            SourceLocation::new(PathBuf::from("--"), 3, 1, 4)
+        );
+        assert_eq!(
+            tokens[1].location,
+            SourceLocation::new(PathBuf::from("--"), 4, 1, 5)
+        );
+    }
+
+    #[test]
+    fn test_token_diff() {
+        let old = tokens_ab_cd_ef();
+
+        // "cd" edited to "cxd", which moves "ef" along
+        let mut new = tokens_ab_cd_ef();
+        new[1].text = String::from("cxd");
+        shift_tokens(&mut new[2..], 5, 1, 1, 0);
+
+        assert_eq!(token_diff(&old, &new), (1, 1, 1));
+        assert_eq!(token_diff(&old, &old), (3, 0, 0));
+        assert_eq!(token_diff(&old, &new[..1]), (1, 0, 0));
+        assert_eq!(token_diff(&old[..1], &old), (1, 0, 2));
+    }
+
+    #[test]
+    fn test_coverage() {
+        // "  ab  c d  " with only the words lexed
+        let tokens = Vec::from([
+            Token::new(PathBuf::from("--"), 2, 1, 3, String::from("ab"), ()),
+            Token::new(PathBuf::from("--"), 6, 1, 7, String::from("c"), ()),
+            Token::new(PathBuf::from("--"), 8, 1, 9, String::from("d"), ()),
+        ]);
+
+        assert_eq!(
+            coverage(&tokens, 11),
+            Vec::from([(0, 2), (4, 6), (7, 8), (9, 11)])
+        );
+        assert_eq!(coverage(&tokens[..1], 4), Vec::from([(0, 2)]));
+        assert_eq!(coverage::<()>(&[], 3), Vec::from([(0, 3)]));
+    }
+
+    #[test]
+    fn test_coverage_trivia() {
+        let mut token = Token::new(PathBuf::from("--"), 1, 1, 2, String::from("a"), ());
+        token.leading_trivia.push(Token::new(
+            PathBuf::from("--"),
+            0,
+            1,
+            1,
+            String::from(" "),
+            (),
+        ));
+
+        assert_eq!(coverage(&[token], 2), Vec::new());
+    }
+
+    #[test]
+    fn test_group_by_line() {
+        // "a b\nc\nd" with a multiline token starting on the second line
+        let tokens = Vec::from([
+            Token::new(PathBuf::from("--"), 0, 1, 1, String::from("a"), ()),
+            Token::new(PathBuf::from("--"), 2, 1, 3, String::from("b"), ()),
+            Token::new(PathBuf::from("--"), 4, 2, 1, String::from("c\nd"), ()),
+        ]);
+
+        assert_eq!(
+            group_by_line(&tokens),
+            Vec::from([
+                (1, Vec::from([&tokens[0], &tokens[1]])),
+                (2, Vec::from([&tokens[2]]))
+            ])
+        );
+        assert_eq!(group_by_line::<()>(&[]), Vec::new());
+    }
 }
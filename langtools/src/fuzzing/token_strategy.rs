@@ -0,0 +1,170 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::path::PathBuf;
+
+use proptest::{collection, prelude::*};
+
+use crate::domain::{
+    source_location::{SourceLocation, COLUMN_INITIAL, LINE_INITIAL, OFFSET_INITIAL},
+    token::{Token, TokenKind},
+};
+
+pub const TOKEN_TEXT_PATTERN: &str = "[a-z0-9\n]{1,4}";
+pub const TOKEN_GAP_MAX: usize = 3;
+
+pub fn token_stream<TTokenKind: TokenKind + 'static>(
+    kind: impl Strategy<Value = TTokenKind>,
+    max_length: usize,
+) -> impl Strategy<Value = Vec<Token<TTokenKind>>> {
+    collection::vec(
+        (kind, TOKEN_TEXT_PATTERN, 0..=TOKEN_GAP_MAX),
+        0..=max_length,
+    )
+    .prop_map(|parts| {
+        // Each token starts where the previous one ended, shifted right by a gap of skipped columns, so that offsets
+        // and lines are monotonic just like they would be coming out of the lexer
+        let mut location = SourceLocation::new(
+            PathBuf::from("--"),
+            OFFSET_INITIAL,
+            LINE_INITIAL,
+            COLUMN_INITIAL,
+        );
+        let mut result = Vec::with_capacity(parts.len());
+
+        for (kind, text, gap) in parts {
+            location.offset += gap;
+            location.column += gap as u32;
+
+            let token = Token::new_from_location(location, text, kind);
+            location = token.end_location();
+            result.push(token);
+        }
+
+        result
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lexing::token_reader::TokenReader,
+        parsing::{
+            parse::Parse, parser_choice::ParserChoiceBuilder,
+            parser_sequential::ParserSequentialBuilder, parser_token::ParserTokenBuilder,
+        },
+        tree::{visit::Visit, Tree},
+    };
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        A,
+        B,
+        C,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    enum TreeTest {
+        Leaf(Token<()>),
+        Node(Token<()>, Vec<TreeTest>),
+    }
+
+    impl Tree for TreeTest {
+        fn token(&self) -> &Token<()> {
+            match &self {
+                TreeTest::Leaf(token) => token,
+                TreeTest::Node(token, _) => token,
+            }
+        }
+    }
+
+    impl Visit for TreeTest {
+        fn visit(&self, _: crate::tree::visit::VisitCallback<Self>) {}
+
+        fn visit_mut(&self, _: crate::tree::visit::VisitCallbackMut<Self>) {}
+    }
+
+    fn token_kind_test() -> impl Strategy<Value = TokenKindTest> {
+        prop_oneof![
+            Just(TokenKindTest::A),
+            Just(TokenKindTest::B),
+            Just(TokenKindTest::C),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn test_token_stream_monotonic(tokens in token_stream(token_kind_test(), 16)) {
+            for pair in tokens.windows(2) {
+                prop_assert!(pair[0].end_location().offset <= pair[1].location.offset);
+                prop_assert!(pair[0].end_location().line <= pair[1].location.line);
+            }
+        }
+
+        #[test]
+        fn test_token_stream_parse_does_not_panic(tokens in token_stream(token_kind_test(), 16)) {
+            let parser = ParserChoiceBuilder::<TokenKindTest, TreeTest>::default()
+                .choices(Box::new(
+                    ParserSequentialBuilder::<TokenKindTest, TreeTest>::default()
+                        .sequence(Box::new(
+                            ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                                .token_kind(TokenKindTest::A)
+                                .action(TreeTest::Leaf)
+                                .build()
+                                .unwrap(),
+                        ))
+                        .sequence(Box::new(
+                            ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                                .token_kind(TokenKindTest::B)
+                                .action(TreeTest::Leaf)
+                                .build()
+                                .unwrap(),
+                        ))
+                        .action(TreeTest::Node)
+                        .build()
+                        .unwrap(),
+                ))
+                .choices(Box::new(
+                    ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                        .token_kind(TokenKindTest::C)
+                        .action(TreeTest::Leaf)
+                        .build()
+                        .unwrap(),
+                ))
+                .build()
+                .unwrap();
+
+            let mut tokens_iter = tokens.iter();
+            let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+            while token_reader.has_more() {
+                if parser.parse(&mut token_reader).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -21,12 +21,17 @@
 // SOFTWARE.
 
 pub mod dfsa;
-pub mod fsa_error;
 pub mod dfsa_executor;
+pub mod fsa_error;
 pub mod fsa_types;
 pub mod lexer;
 pub mod lexer_context;
 pub mod lexer_trigger_action;
 pub mod lexing_error;
-pub mod token_reader;
 pub mod nfsa;
+pub mod token_canonicalize;
+pub mod token_coalesce;
+pub mod token_filter;
+pub mod token_reader;
+pub mod token_remap;
+pub mod token_soft_keywords;
@@ -22,7 +22,7 @@
 
 use super::fsa_error::{FSAError, Result};
 use super::fsa_types::FSAId;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::hash::Hash;
 use std::option::Option;
 use std::vec::Vec;
@@ -31,12 +31,16 @@ use std::vec::Vec;
 struct DFSAState<TElement: Eq + Hash, TAction> {
     action: Option<TAction>,
     transitions: HashMap<TElement, FSAId>,
+    except_transition: Option<(HashSet<TElement>, FSAId)>,
 }
 
+// Metadata is bookkeeping attached to states separately from their actions, such as a priority or a name for
+// diagnostics, so that the action type doesn't need to carry it
 #[derive(Debug)]
-pub struct DFSA<TElement: Eq + Hash, TAction> {
+pub struct DFSA<TElement: Eq + Hash, TAction, TMeta = ()> {
     states: Vec<DFSAState<TElement, TAction>>,
     start_id: Option<FSAId>,
+    meta: HashMap<FSAId, TMeta>,
 }
 
 impl<TElement: Eq + Hash, TAction> DFSAState<TElement, TAction> {
@@ -44,6 +48,7 @@ impl<TElement: Eq + Hash, TAction> DFSAState<TElement, TAction> {
         Self {
             action: None,
             transitions: HashMap::new(),
+            except_transition: None,
         }
     }
 
@@ -51,15 +56,17 @@ impl<TElement: Eq + Hash, TAction> DFSAState<TElement, TAction> {
         Self {
             action: Some(action),
             transitions: HashMap::new(),
+            except_transition: None,
         }
     }
 }
 
-impl<TElement: Eq + Hash, TAction> DFSA<TElement, TAction> {
+impl<TElement: Eq + Hash, TAction, TMeta> DFSA<TElement, TAction, TMeta> {
     pub fn new() -> Self {
         Self {
             states: Vec::new(),
             start_id: None,
+            meta: HashMap::new(),
         }
     }
 
@@ -97,6 +104,31 @@ impl<TElement: Eq + Hash, TAction> DFSA<TElement, TAction> {
         }
     }
 
+    // Adds a transition taken on any element that isn't in `excluded` and doesn't have an exact transition from this
+    // state, such as the body of a string literal being anything except `"` and `\`. Exact transitions always take
+    // precedence, so excluded elements can still have their own transitions. Each state can have at most one of these
+    // since two would overlap on elements excluded by neither. `sample_accepted` and `accepting_paths` don't follow them
+    // since they stand for elements that aren't known.
+    pub fn add_transition_except(
+        &mut self,
+        from_id: FSAId,
+        excluded: HashSet<TElement>,
+        to_id: FSAId,
+    ) -> Result<()> {
+        if !self.is_id_in_bounds(to_id) {
+            return Err(FSAError::OutOfRangeId(to_id));
+        }
+
+        let state = self.try_get_state_mut(from_id)?;
+
+        if state.except_transition.is_some() {
+            Err(FSAError::TransitionAlreadyExists)
+        } else {
+            state.except_transition = Some((excluded, to_id));
+            Ok(())
+        }
+    }
+
     pub fn try_get_start_id(&self) -> Result<FSAId> {
         if let Some(result) = self.start_id {
             Ok(result)
@@ -121,20 +153,52 @@ impl<TElement: Eq + Hash, TAction> DFSA<TElement, TAction> {
             .ok_or(FSAError::StateHasNoAction(id))
     }
 
+    pub fn try_get_state_action_mut(&mut self, id: FSAId) -> Result<&mut TAction> {
+        self.try_get_state_mut(id)?
+            .action
+            .as_mut()
+            .ok_or(FSAError::StateHasNoAction(id))
+    }
+
     pub fn set_state_action(&mut self, id: FSAId, action: Option<TAction>) -> Result<()> {
         self.try_get_state_mut(id)?.action = action;
         Ok(())
     }
 
+    pub fn try_get_state_meta(&self, id: FSAId) -> Result<&TMeta> {
+        self.try_get_state(id)?;
+        self.meta.get(&id).ok_or(FSAError::StateHasNoMeta(id))
+    }
+
+    pub fn set_state_meta(&mut self, id: FSAId, meta: Option<TMeta>) -> Result<()> {
+        self.try_get_state(id)?;
+
+        match meta {
+            Some(meta) => {
+                self.meta.insert(id, meta);
+            }
+            None => {
+                self.meta.remove(&id);
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn try_get_transition(&self, from_id: FSAId, on_element: TElement) -> Result<FSAId> {
-        self.try_get_state(from_id)?
-            .transitions
-            .get(&on_element)
-            .ok_or(FSAError::NoSuchTransition)
-            .copied()
+        let state = self.try_get_state(from_id)?;
+
+        if let Some(to_id) = state.transitions.get(&on_element) {
+            return Ok(*to_id);
+        }
+
+        match &state.except_transition {
+            Some((excluded, to_id)) if !excluded.contains(&on_element) => Ok(*to_id),
+            _ => Err(FSAError::NoSuchTransition),
+        }
     }
 
-    fn is_id_in_bounds(&self, id: FSAId) -> bool {
+    pub fn is_id_in_bounds(&self, id: FSAId) -> bool {
         id < self.states.len()
     }
 
@@ -147,7 +211,79 @@ impl<TElement: Eq + Hash, TAction> DFSA<TElement, TAction> {
     }
 }
 
-impl<T: Eq + Hash, U> Default for DFSA<T, U> {
+impl<TElement: Eq + Hash + Clone, TAction, TMeta> DFSA<TElement, TAction, TMeta> {
+    // The elements with an exact transition out of a state. Elements covered only by an except transition aren't
+    // included since they aren't known.
+    pub fn transition_elements(&self, from_id: FSAId) -> Result<HashSet<TElement>> {
+        Ok(self
+            .try_get_state(from_id)?
+            .transitions
+            .keys()
+            .cloned()
+            .collect())
+    }
+
+    pub fn sample_accepted(&self, max: usize) -> Result<Vec<Vec<TElement>>> {
+        // Breadth first search from the start state so that the first path found to each state is the shortest one
+        let start_id = self.try_get_start_id()?;
+
+        let mut result = Vec::new();
+        let mut visited = HashSet::from([start_id]);
+        let mut queue = VecDeque::from([(start_id, Vec::new())]);
+
+        while let Some((id, path)) = queue.pop_front() {
+            if result.len() >= max {
+                break;
+            }
+
+            let state = self.try_get_state(id)?;
+
+            for (element, next_id) in state.transitions.iter() {
+                if visited.insert(*next_id) {
+                    let mut next_path = path.clone();
+                    next_path.push(element.clone());
+                    queue.push_back((*next_id, next_path));
+                }
+            }
+
+            if state.action.is_some() {
+                result.push(path);
+            }
+        }
+
+        Ok(result)
+    }
+
+    // Every element sequence of at most `max_len` elements that ends in a state with an action, paired with that
+    // action. Unlike `sample_accepted`, paths through the same state are all listed and cycles are followed until the
+    // length runs out, so the result can grow exponentially with `max_len`. The order of the result is unspecified.
+    pub fn accepting_paths(&self, max_len: usize) -> Result<Vec<(Vec<TElement>, &TAction)>> {
+        let start_id = self.try_get_start_id()?;
+
+        let mut result = Vec::new();
+        let mut stack = Vec::from([(start_id, Vec::new())]);
+
+        while let Some((id, path)) = stack.pop() {
+            let state = self.try_get_state(id)?;
+
+            if path.len() < max_len {
+                for (element, next_id) in state.transitions.iter() {
+                    let mut next_path = path.clone();
+                    next_path.push(element.clone());
+                    stack.push((*next_id, next_path));
+                }
+            }
+
+            if let Some(action) = &state.action {
+                result.push((path, action));
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl<T: Eq + Hash, U, V> Default for DFSA<T, U, V> {
     fn default() -> Self {
         Self::new()
     }
@@ -215,6 +351,25 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_dfsa_transition_elements() -> Result<()> {
+        let mut dfsa = DFSA::<char, String>::new();
+        let start = dfsa.add_state();
+        let a = dfsa.add_state();
+        dfsa.add_transition(start, 'a', a)?;
+        dfsa.add_transition(start, 'b', a)?;
+        dfsa.add_transition_except(start, HashSet::from(['c']), a)?;
+
+        assert_eq!(
+            dfsa.transition_elements(start),
+            Ok(HashSet::from(['a', 'b']))
+        );
+        assert_eq!(dfsa.transition_elements(a), Ok(HashSet::new()));
+        assert_eq!(dfsa.transition_elements(2), Err(FSAError::OutOfRangeId(2)));
+
+        Ok(())
+    }
+
     #[test]
     fn test_dfsa_try_get_transition_bad_element() -> Result<()> {
         let mut dfsa = DFSA::<char, String>::new();
@@ -302,4 +457,122 @@ mod tests {
             Err(FSAError::OutOfRangeId(100))
         );
     }
+
+    #[test]
+    fn test_dfsa_state_meta() -> Result<()> {
+        let mut dfsa = DFSA::<char, String, u32>::new();
+        let start = dfsa.add_state();
+        let accepting = dfsa.add_state_with_action(String::from("a"));
+        dfsa.add_transition(start, 'a', accepting)?;
+
+        assert_eq!(
+            dfsa.try_get_state_meta(accepting),
+            Err(FSAError::StateHasNoMeta(accepting))
+        );
+
+        dfsa.set_state_meta(accepting, Some(10))?;
+
+        assert_eq!(dfsa.try_get_state_meta(accepting), Ok(&10));
+        assert_eq!(dfsa.try_get_state_action(accepting), Ok(&String::from("a")));
+
+        dfsa.set_state_meta(accepting, None)?;
+
+        assert_eq!(
+            dfsa.try_get_state_meta(accepting),
+            Err(FSAError::StateHasNoMeta(accepting))
+        );
+        assert_eq!(
+            dfsa.set_state_meta(100, Some(1)),
+            Err(FSAError::OutOfRangeId(100))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dfsa_sample_accepted() -> Result<()> {
+        let mut dfsa = DFSA::<char, String>::new();
+        let start = dfsa.add_state();
+        let a = dfsa.add_state();
+        let ab = dfsa.add_state_with_action(String::from("ab"));
+        let c = dfsa.add_state_with_action(String::from("c"));
+        dfsa.add_transition(start, 'a', a)?;
+        dfsa.add_transition(a, 'b', ab)?;
+        dfsa.add_transition(start, 'c', c)?;
+        dfsa.set_start_id(start)?;
+
+        assert_eq!(
+            dfsa.sample_accepted(10)?,
+            Vec::from([Vec::from(['c']), Vec::from(['a', 'b'])])
+        );
+        assert_eq!(dfsa.sample_accepted(1)?, Vec::from([Vec::from(['c'])]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dfsa_sample_accepted_no_start_id() {
+        let dfsa = DFSA::<char, String>::new();
+
+        assert_eq!(dfsa.sample_accepted(10), Err(FSAError::NoStartId));
+    }
+
+    #[test]
+    fn test_dfsa_accepting_paths() -> Result<()> {
+        let mut dfsa = DFSA::<char, String>::new();
+        let start = dfsa.add_state();
+        let a = dfsa.add_state_with_action(String::from("a+"));
+        let ab = dfsa.add_state_with_action(String::from("a+b"));
+        dfsa.add_transition(start, 'a', a)?;
+        dfsa.add_transition(a, 'a', a)?;
+        dfsa.add_transition(a, 'b', ab)?;
+        dfsa.set_start_id(start)?;
+
+        let mut paths = dfsa.accepting_paths(3)?;
+        paths.sort();
+
+        assert_eq!(
+            paths,
+            Vec::from([
+                (Vec::from(['a']), &String::from("a+")),
+                (Vec::from(['a', 'a']), &String::from("a+")),
+                (Vec::from(['a', 'a', 'a']), &String::from("a+")),
+                (Vec::from(['a', 'a', 'b']), &String::from("a+b")),
+                (Vec::from(['a', 'b']), &String::from("a+b")),
+            ])
+        );
+        assert_eq!(dfsa.accepting_paths(0)?, Vec::new());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dfsa_add_transition_except() -> Result<()> {
+        let mut dfsa = DFSA::<char, String>::new();
+        let start = dfsa.add_state();
+        let body = dfsa.add_state();
+        let escape = dfsa.add_state();
+        let end = dfsa.add_state_with_action(String::from("string"));
+        dfsa.add_transition(start, '"', body)?;
+        dfsa.add_transition_except(body, HashSet::from(['"', '\\']), body)?;
+        dfsa.add_transition(body, '\\', escape)?;
+        dfsa.add_transition_except(escape, HashSet::new(), body)?;
+        dfsa.add_transition(body, '"', end)?;
+        dfsa.set_start_id(start)?;
+
+        assert_eq!(dfsa.try_get_transition(body, 'a'), Ok(body));
+        assert_eq!(dfsa.try_get_transition(body, '\\'), Ok(escape));
+        assert_eq!(dfsa.try_get_transition(escape, '"'), Ok(body));
+        assert_eq!(dfsa.try_get_transition(body, '"'), Ok(end));
+        assert_eq!(
+            dfsa.try_get_transition(start, 'a'),
+            Err(FSAError::NoSuchTransition)
+        );
+        assert_eq!(
+            dfsa.add_transition_except(body, HashSet::new(), end),
+            Err(FSAError::TransitionAlreadyExists)
+        );
+
+        Ok(())
+    }
 }
@@ -21,19 +21,19 @@
 // SOFTWARE.
 
 use super::dfsa::DFSA;
-use super::fsa_error::Result;
+use super::fsa_error::{FSAError, Result};
 use super::fsa_types::FSAId;
 use std::hash::Hash;
 
 #[derive(Debug)]
-pub struct DFSAExecutor<'dfsa, TElement: Eq + Hash, TAction> {
-    dfsa: &'dfsa DFSA<TElement, TAction>,
+pub struct DFSAExecutor<'dfsa, TElement: Eq + Hash, TAction, TMeta = ()> {
+    dfsa: &'dfsa DFSA<TElement, TAction, TMeta>,
     start_id: FSAId,
     current_id: FSAId,
 }
 
-impl<'dfsa, TElement: Eq + Hash, TAction> DFSAExecutor<'dfsa, TElement, TAction> {
-    pub fn new(dfsa: &'dfsa DFSA<TElement, TAction>) -> Result<Self> {
+impl<'dfsa, TElement: Eq + Hash, TAction, TMeta> DFSAExecutor<'dfsa, TElement, TAction, TMeta> {
+    pub fn new(dfsa: &'dfsa DFSA<TElement, TAction, TMeta>) -> Result<Self> {
         let start_id = dfsa.try_get_start_id()?;
 
         Ok(Self {
@@ -43,6 +43,27 @@ impl<'dfsa, TElement: Eq + Hash, TAction> DFSAExecutor<'dfsa, TElement, TAction>
         })
     }
 
+    // Starts at `state_id` instead of the start state, to resume a match that was partially consumed before. Resetting
+    // still goes back to the start state.
+    pub fn new_at(dfsa: &'dfsa DFSA<TElement, TAction, TMeta>, state_id: FSAId) -> Result<Self> {
+        let mut result = Self::new(dfsa)?;
+        result.set_state(state_id)?;
+        Ok(result)
+    }
+
+    pub fn current_id(&self) -> FSAId {
+        self.current_id
+    }
+
+    pub fn set_state(&mut self, id: FSAId) -> Result<()> {
+        if self.dfsa.is_id_in_bounds(id) {
+            self.current_id = id;
+            Ok(())
+        } else {
+            Err(FSAError::OutOfRangeId(id))
+        }
+    }
+
     pub fn reset(&mut self) {
         self.current_id = self.start_id
     }
@@ -68,7 +89,6 @@ impl<'dfsa, TElement: Eq + Hash, TAction> DFSAExecutor<'dfsa, TElement, TAction>
 
 #[cfg(test)]
 mod tests {
-    use super::super::fsa_error::FSAError;
     use super::*;
 
     #[test]
@@ -115,4 +135,43 @@ mod tests {
             Err(FSAError::NoStartId)
         );
     }
+
+    #[test]
+    fn test_dfsa_executor_new_at() -> Result<()> {
+        let mut dfsa = DFSA::<char, String>::new();
+        let start = dfsa.add_state();
+        let a = dfsa.add_state();
+        let ab = dfsa.add_state_with_action(String::from("ab"));
+        dfsa.add_transition(start, 'a', a)?;
+        dfsa.add_transition(a, 'b', ab)?;
+        dfsa.set_start_id(start)?;
+
+        let mut dfsa_executor = DFSAExecutor::new(&dfsa)?;
+        dfsa_executor.step('a')?;
+        let snapshot = dfsa_executor.current_id();
+
+        let mut resumed = DFSAExecutor::new_at(&dfsa, snapshot)?;
+
+        assert!(!resumed.is_at_start());
+        assert_eq!(resumed.step('b'), Ok(()));
+        assert_eq!(resumed.current_action().cloned(), Some(String::from("ab")));
+
+        resumed.reset();
+
+        assert!(resumed.is_at_start());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dfsa_executor_new_at_out_of_range() {
+        let mut dfsa = DFSA::<char, String>::new();
+        let start = dfsa.add_state();
+        dfsa.set_start_id(start).unwrap();
+
+        assert_eq!(
+            DFSAExecutor::new_at(&dfsa, 100).map(|_| ()),
+            Err(FSAError::OutOfRangeId(100))
+        );
+    }
 }
@@ -32,6 +32,7 @@ pub enum FSAError {
     NoStartId,
     OutOfRangeId(FSAId),
     StateHasNoAction(FSAId),
+    StateHasNoMeta(FSAId),
     TransitionAlreadyExists,
     NoSuchTransition,
 }
@@ -44,6 +45,7 @@ impl Display for FSAError {
             FSAError::NoStartId => write!(f, "no start id specified"),
             FSAError::OutOfRangeId(id) => write!(f, "out of range id: {}", id),
             FSAError::StateHasNoAction(id) => write!(f, "state {} has no action", id),
+            FSAError::StateHasNoMeta(id) => write!(f, "state {} has no metadata", id),
             FSAError::TransitionAlreadyExists => write!(f, "transition already exists"),
             FSAError::NoSuchTransition => write!(f, "no such transition exists on element"),
         }
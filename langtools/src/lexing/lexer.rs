@@ -20,29 +20,71 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+use std::collections::HashSet;
+
 use crate::{
-    domain::token::TokenKind,
+    domain::{
+        source_info::SourceInfo,
+        token::{Token, TokenKind},
+    },
     lexing::lexing_error::LexingError,
     messaging::message_context::MessageContext,
-    sourcing::{read_source::ReadSource, source_reader::SourceReader},
+    sourcing::{read_source::ReadSource, source_reader::SourceReader, source_string::SourceString},
 };
 
 use super::{
     dfsa::DFSA,
+    fsa_types::FSAId,
     lexer_context::LexerContext,
-    lexer_trigger_action::{LexerTriggerAction, LexerTriggerActionCallback},
+    lexer_trigger_action::{
+        LexerTriggerAction, LexerTriggerActionCallback, LexerTriggerActionStatefulCallback,
+        LexerTriggerCallbackKind, LexerTriggerPredicate,
+    },
     lexing_error::Result,
 };
 
 pub type LexerErrorHandler = fn(&mut dyn ReadSource);
 
+// Called once when the lexer reaches the end of the source. If it returns a token kind, an empty token of that kind is
+// emitted at the end location before lexing stops.
+pub type LexerEofHandler<TTokenKind> = fn() -> Option<TTokenKind>;
+
+// Called with each token as it is returned from `LexerContext::lex_next`, after any trailing trivia has been attached.
+// It isn't called for trivia. Tokens lexed again after `LexerContext::restore` are passed to it again.
+pub type LexerTokenHook<TTokenKind> = fn(&Token<TTokenKind>);
+
+// Trivia is the text of triggers whose callbacks return no token kind, such as whitespace and comments. Without a
+// policy it is discarded.
+//
+// With `LeadingAndTrailing`, trivia that starts on the same line that the preceding token ends on is attached to that
+// token as trailing trivia. This includes the newline that ends the line if it is trivia. Everything after the first
+// trivia that doesn't is leading trivia of the next token. A token whose text ends with a newline has no trailing
+// trivia since anything after it is on a later line. Trivia after the last token is discarded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LexerTriviaPolicy {
+    Leading,
+    LeadingAndTrailing,
+}
+
+pub const DEFAULT_LOOKBEHIND_LENGTH: usize = 10;
+
+// `TState` is user state that lives in each `LexerContext` and is passed to triggers added with
+// `add_trigger_with_state`, since trigger callbacks are function pointers and can't capture anything. It can track
+// things like paren depth or a stack of string interpolations.
 #[readonly::make]
-pub struct Lexer<TTokenKind> {
-    pub trigger_dfsa: DFSA<char, LexerTriggerAction<TTokenKind>>,
+pub struct Lexer<TTokenKind: TokenKind, TState = ()> {
+    pub trigger_dfsa: DFSA<char, LexerTriggerAction<TTokenKind, TState>>,
     error_handler: Option<LexerErrorHandler>,
+    record_trigger_length: bool,
+    record_trigger_prefix: bool,
+    trivia_policy: Option<LexerTriviaPolicy>,
+    whitespace_kind: Option<TTokenKind>,
+    eof_handler: Option<LexerEofHandler<TTokenKind>>,
+    on_token: Option<LexerTokenHook<TTokenKind>>,
+    lookbehind_length: usize,
 }
 
-impl<TTokenKind: TokenKind> Lexer<TTokenKind> {
+impl<TTokenKind: TokenKind, TState> Lexer<TTokenKind, TState> {
     pub fn new() -> Self {
         let mut trigger_dfsa = DFSA::new();
         let start_id = trigger_dfsa.add_state();
@@ -53,6 +95,13 @@ impl<TTokenKind: TokenKind> Lexer<TTokenKind> {
         Self {
             trigger_dfsa,
             error_handler: None,
+            record_trigger_length: false,
+            record_trigger_prefix: false,
+            trivia_policy: None,
+            whitespace_kind: None,
+            eof_handler: None,
+            on_token: None,
+            lookbehind_length: DEFAULT_LOOKBEHIND_LENGTH,
         }
     }
 
@@ -61,6 +110,114 @@ impl<TTokenKind: TokenKind> Lexer<TTokenKind> {
         prefix: &str,
         callback: LexerTriggerActionCallback<TTokenKind>,
     ) -> Result<()> {
+        self.add_trigger_callback(prefix, LexerTriggerCallbackKind::Plain(callback))
+    }
+
+    pub fn add_trigger_with_state(
+        &mut self,
+        prefix: &str,
+        callback: LexerTriggerActionStatefulCallback<TTokenKind, TState>,
+    ) -> Result<()> {
+        self.add_trigger_callback(prefix, LexerTriggerCallbackKind::Stateful(callback))
+    }
+
+    fn add_trigger_callback(
+        &mut self,
+        prefix: &str,
+        callback: LexerTriggerCallbackKind<TTokenKind, TState>,
+    ) -> Result<()> {
+        let id = self.add_trigger_prefix(prefix)?;
+
+        match self.trigger_dfsa.try_get_state_action_mut(id) {
+            Ok(action) if action.callback.is_some() => {
+                Err(LexingError::DuplicateTrigger(prefix.to_owned()))
+            }
+            Ok(action) => {
+                action.set_callback(callback);
+                Ok(())
+            }
+            Err(_) => {
+                self.trigger_dfsa.set_state_action(
+                    id,
+                    Some(LexerTriggerAction::new(prefix.to_owned(), callback)),
+                )?;
+                Ok(())
+            }
+        }
+    }
+
+    // Adds a trigger that only fires when `predicate` accepts the last token lexed before it, such as `/` starting a
+    // regular expression after an operator but meaning division after an identifier. Any number of contextual triggers
+    // can share a prefix with each other and with one unconditional trigger, which is used when none of them are
+    // active.
+    pub fn add_contextual_trigger(
+        &mut self,
+        prefix: &str,
+        predicate: LexerTriggerPredicate<TTokenKind>,
+        callback: LexerTriggerActionCallback<TTokenKind>,
+    ) -> Result<()> {
+        let id = self.add_trigger_prefix(prefix)?;
+
+        let callback = LexerTriggerCallbackKind::Plain(callback);
+
+        match self.trigger_dfsa.try_get_state_action_mut(id) {
+            Ok(action) => {
+                action.add_contextual(predicate, callback);
+                Ok(())
+            }
+            Err(_) => {
+                self.trigger_dfsa.set_state_action(
+                    id,
+                    Some(LexerTriggerAction::new_contextual(
+                        prefix.to_owned(),
+                        predicate,
+                        callback,
+                    )),
+                )?;
+                Ok(())
+            }
+        }
+    }
+
+    // The trigger DFSA state reached by reading `prefix` from the start state, if there is one. Every prefix of a trigger
+    // has a state but only whole triggers have actions.
+    pub fn state_for_prefix(&self, prefix: &str) -> Option<FSAId> {
+        let mut current_id = self.trigger_dfsa.try_get_start_id().ok()?;
+
+        for element in prefix.chars() {
+            current_id = self
+                .trigger_dfsa
+                .try_get_transition(current_id, element)
+                .ok()?;
+        }
+
+        Some(current_id)
+    }
+
+    // The characters that some trigger starts with, to check against the characters expected in the source. Whitespace
+    // lexed with `set_whitespace_kind` isn't included since it doesn't go through triggers.
+    pub fn starting_characters(&self) -> HashSet<char> {
+        self.trigger_dfsa
+            .try_get_start_id()
+            .and_then(|start_id| self.trigger_dfsa.transition_elements(start_id))
+            .expect("the start state is set when the lexer is created")
+    }
+
+    pub fn get_trigger_action(&self, id: FSAId) -> Option<&LexerTriggerAction<TTokenKind, TState>> {
+        self.trigger_dfsa.try_get_state_action(id).ok()
+    }
+
+    // Replaces the action of a state found with `state_for_prefix`, for example to swap the callback of a keyword in a
+    // lexer built elsewhere. Setting `None` removes the trigger.
+    pub fn set_trigger_action(
+        &mut self,
+        id: FSAId,
+        action: Option<LexerTriggerAction<TTokenKind, TState>>,
+    ) -> Result<()> {
+        Ok(self.trigger_dfsa.set_state_action(id, action)?)
+    }
+
+    fn add_trigger_prefix(&mut self, prefix: &str) -> Result<FSAId> {
         assert!(!prefix.is_empty(), "cannot add trigger with empty prefix");
 
         let mut current_id = self.trigger_dfsa.try_get_start_id()?;
@@ -76,22 +233,52 @@ impl<TTokenKind: TokenKind> Lexer<TTokenKind> {
             }
         }
 
-        if self.trigger_dfsa.try_get_state_action(current_id).is_ok() {
-            Err(LexingError::DuplicateTrigger(prefix.to_owned()))
-        } else {
-            self.trigger_dfsa
-                .set_state_action(current_id, Some(LexerTriggerAction::new(callback)))?;
-
-            Ok(())
-        }
+        Ok(current_id)
     }
 
     pub fn lex<'self_>(
         &'self_ self,
         source_reader: &'self_ mut SourceReader<'self_>,
         message_context: &'self_ mut MessageContext,
-    ) -> LexerContext<'self_, TTokenKind> {
-        LexerContext::new(self, source_reader, message_context)
+    ) -> LexerContext<'self_, TTokenKind, TState>
+    where
+        TState: Default + Clone,
+    {
+        self.lex_with_state(source_reader, message_context, TState::default())
+    }
+
+    pub fn lex_with_state<'self_>(
+        &'self_ self,
+        source_reader: &'self_ mut SourceReader<'self_>,
+        message_context: &'self_ mut MessageContext,
+        state: TState,
+    ) -> LexerContext<'self_, TTokenKind, TState>
+    where
+        TState: Clone,
+    {
+        LexerContext::new_with_state(self, source_reader, message_context, state)
+    }
+
+    // Lexes all of `data` with a fresh source and reader, for re-processing new input against the same lexer such as
+    // in a REPL. Errors are emitted to `message_context` as usual, so call `MessageContext::clear` first to only see
+    // the ones for this input.
+    pub fn lex_string(
+        &self,
+        info: SourceInfo,
+        data: &str,
+        message_context: &mut MessageContext,
+    ) -> Vec<Token<TTokenKind>>
+    where
+        TState: Default + Clone,
+    {
+        let mut source = SourceString::new(info, data);
+        let mut source_reader = SourceReader::new(&mut source);
+
+        source_reader
+            .enable_buffering()
+            .expect("buffering should be disabled on a new reader");
+
+        self.lex(&mut source_reader, message_context).collect()
     }
 
     pub fn set_error_handler(&mut self, error_handler: LexerErrorHandler) {
@@ -103,9 +290,71 @@ impl<TTokenKind: TokenKind> Lexer<TTokenKind> {
             let _ = read_source.eat_next();
         })
     }
+
+    pub fn set_eof_handler(&mut self, eof_handler: LexerEofHandler<TTokenKind>) {
+        self.eof_handler = Some(eof_handler)
+    }
+
+    pub fn get_eof_handler(&self) -> Option<LexerEofHandler<TTokenKind>> {
+        self.eof_handler
+    }
+
+    pub fn set_on_token(&mut self, on_token: Option<LexerTokenHook<TTokenKind>>) {
+        self.on_token = on_token
+    }
+
+    pub fn get_on_token(&self) -> Option<LexerTokenHook<TTokenKind>> {
+        self.on_token
+    }
+
+    pub fn set_record_trigger_length(&mut self, value: bool) {
+        self.record_trigger_length = value
+    }
+
+    pub fn is_recording_trigger_length(&self) -> bool {
+        self.record_trigger_length
+    }
+
+    // When set, each token records the trigger prefix that produced it, which is off by default so that tokens don't
+    // each carry an extra string
+    pub fn set_record_trigger_prefix(&mut self, value: bool) {
+        self.record_trigger_prefix = value
+    }
+
+    pub fn is_recording_trigger_prefix(&self) -> bool {
+        self.record_trigger_prefix
+    }
+
+    pub fn set_trivia_policy(&mut self, value: Option<LexerTriviaPolicy>) {
+        self.trivia_policy = value
+    }
+
+    pub fn get_trivia_policy(&self) -> Option<LexerTriviaPolicy> {
+        self.trivia_policy
+    }
+
+    // When set, runs of whitespace are emitted as tokens of this kind before triggers are checked, so that every
+    // character of the source ends up in a token
+    pub fn set_whitespace_kind(&mut self, value: Option<TTokenKind>) {
+        self.whitespace_kind = value
+    }
+
+    pub fn get_whitespace_kind(&self) -> Option<TTokenKind> {
+        self.whitespace_kind
+    }
+
+    // The number of most recently consumed characters quoted in unexpected character messages. Zero turns the context
+    // off.
+    pub fn set_lookbehind_length(&mut self, value: usize) {
+        self.lookbehind_length = value
+    }
+
+    pub fn get_lookbehind_length(&self) -> usize {
+        self.lookbehind_length
+    }
 }
 
-impl<TTokenKind: TokenKind> Default for Lexer<TTokenKind> {
+impl<TTokenKind: TokenKind, TState> Default for Lexer<TTokenKind, TState> {
     fn default() -> Self {
         Self::new()
     }
@@ -113,11 +362,14 @@ impl<TTokenKind: TokenKind> Default for Lexer<TTokenKind> {
 
 #[cfg(test)]
 mod tests {
-    use crate::lexing::lexing_error::LexingError;
+    use std::path::PathBuf;
+
+    use crate::{lexing::lexing_error::LexingError, messaging::message::Severity};
 
     use super::*;
 
     #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    #[allow(clippy::upper_case_acronyms)]
     enum TokenKindTest {
         AB,
         AC,
@@ -130,15 +382,171 @@ mod tests {
     fn test_lexer_add_trigger() {
         let mut lexer = Lexer::<TokenKindTest>::new();
 
-        assert_eq!(lexer.add_trigger("ab", |_| Some(TokenKindTest::AB)), Ok(()));
-        assert_eq!(lexer.add_trigger("ac", |_| Some(TokenKindTest::AC)), Ok(()));
         assert_eq!(
-            lexer.add_trigger("abc", |_| Some(TokenKindTest::ABC)),
+            lexer.add_trigger("ab", |_, _| Some(TokenKindTest::AB)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("ac", |_, _| Some(TokenKindTest::AC)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("abc", |_, _| Some(TokenKindTest::ABC)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("ab", |_, _| Some(TokenKindTest::AB)),
+            Err(LexingError::DuplicateTrigger(String::from("ab")))
+        );
+    }
+
+    #[test]
+    fn test_lexer_add_contextual_trigger() {
+        let mut lexer = Lexer::<TokenKindTest>::new();
+
+        assert_eq!(
+            lexer.add_contextual_trigger(
+                "ab",
+                |last_token| last_token.is_none(),
+                |_, _| Some(TokenKindTest::AB)
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_contextual_trigger(
+                "ab",
+                |last_token| last_token.is_some(),
+                |_, _| Some(TokenKindTest::ABC)
+            ),
             Ok(())
         );
         assert_eq!(
-            lexer.add_trigger("ab", |_| Some(TokenKindTest::AB)),
+            lexer.add_trigger("ab", |_, _| Some(TokenKindTest::AC)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("ab", |_, _| Some(TokenKindTest::AC)),
             Err(LexingError::DuplicateTrigger(String::from("ab")))
         );
     }
+
+    #[test]
+    fn test_lexer_starting_characters() {
+        let mut lexer = Lexer::<TokenKindTest>::new();
+
+        assert_eq!(lexer.starting_characters(), HashSet::new());
+        assert_eq!(
+            lexer.add_trigger("ab", |_, _| Some(TokenKindTest::AB)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("ac", |_, _| Some(TokenKindTest::AC)),
+            Ok(())
+        );
+
+        assert_eq!(lexer.starting_characters(), HashSet::from(['a']));
+    }
+
+    #[test]
+    fn test_lexer_lex_string_repl() {
+        let mut lexer = Lexer::<TokenKindTest>::new();
+
+        assert_eq!(
+            lexer.add_trigger("ab", |_, _| Some(TokenKindTest::AB)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("ac", |_, _| Some(TokenKindTest::AC)),
+            Ok(())
+        );
+
+        let mut message_context = MessageContext::new();
+
+        let tokens = lexer.lex_string(
+            SourceInfo::new(PathBuf::from("<repl>")),
+            "abxac",
+            &mut message_context,
+        );
+
+        assert_eq!(
+            tokens.iter().map(|token| token.kind).collect::<Vec<_>>(),
+            vec![TokenKindTest::AB, TokenKindTest::AC]
+        );
+        assert_eq!(message_context.count_with_severity(Severity::Error), 1);
+
+        message_context.clear();
+
+        let tokens = lexer.lex_string(
+            SourceInfo::new(PathBuf::from("<repl>")),
+            "acab",
+            &mut message_context,
+        );
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(
+                    PathBuf::from("<repl>"),
+                    0,
+                    1,
+                    1,
+                    String::from("ac"),
+                    TokenKindTest::AC
+                ),
+                Token::new(
+                    PathBuf::from("<repl>"),
+                    2,
+                    1,
+                    3,
+                    String::from("ab"),
+                    TokenKindTest::AB
+                ),
+            ]
+        );
+        assert!(message_context.messages.is_empty());
+        assert_eq!(message_context.count_with_severity(Severity::Error), 0);
+    }
+
+    #[test]
+    fn test_lexer_state_for_prefix() {
+        let mut lexer = Lexer::<TokenKindTest>::new();
+
+        assert_eq!(
+            lexer.add_trigger("abc", |_, _| Some(TokenKindTest::ABC)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("ab", |_, _| Some(TokenKindTest::AB)),
+            Ok(())
+        );
+
+        let ab = lexer.state_for_prefix("ab").unwrap();
+        let a = lexer.state_for_prefix("a").unwrap();
+
+        assert!(lexer.get_trigger_action(ab).is_some());
+        assert!(lexer.get_trigger_action(a).is_none());
+        assert_eq!(lexer.state_for_prefix("ac"), None);
+
+        assert_eq!(
+            lexer.set_trigger_action(
+                ab,
+                Some(LexerTriggerAction::new(
+                    String::from("ab"),
+                    LexerTriggerCallbackKind::Plain(|_, _| Some(TokenKindTest::AC))
+                ))
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            lexer
+                .get_trigger_action(ab)
+                .and_then(|action| action.select(None))
+                .and_then(|callback| callback.call(
+                    &mut crate::sourcing::source_new_string(""),
+                    "ab",
+                    &mut ()
+                )),
+            Some(TokenKindTest::AC)
+        );
+    }
 }
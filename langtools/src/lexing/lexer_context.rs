@@ -20,44 +20,305 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+use std::{collections::VecDeque, mem};
+
 use crate::{
-    domain::token::{Token, TokenKind},
+    domain::{
+        source_location::SourceLocation,
+        token::{Token, TokenKind},
+    },
     messaging::{
         message::{Message, Severity},
         message_context::MessageContext,
     },
     sourcing::{
-        read_source::ReadSource, source_reader::SourceReader, sourcing_error::SourcingError,
+        read_source::ReadSource,
+        source_reader::{SourceReader, SourceReaderMark},
+        sourcing_error::SourcingError,
     },
 };
 
 use super::{
     dfsa_executor::DFSAExecutor,
-    lexer::Lexer,
-    lexer_trigger_action::LexerTriggerAction,
+    fsa_types::FSAId,
+    lexer::{Lexer, LexerTriviaPolicy},
+    lexer_trigger_action::{LexerTriggerAction, LexerTriggerCallbackKind},
     lexing_error::{LexingError, Result},
 };
 
-pub struct LexerContext<'lexer, TTokenKind: TokenKind> {
-    lexer: &'lexer Lexer<TTokenKind>,
+pub struct LexerContext<'lexer, TTokenKind: TokenKind, TState = ()> {
+    lexer: &'lexer Lexer<TTokenKind, TState>,
     source_reader: &'lexer mut SourceReader<'lexer>,
     message_context: &'lexer mut MessageContext,
+    leading_trivia: Vec<Token<()>>,
+    // Collecting trailing trivia requires lexing past the end of it, so whatever was lexed next is held here
+    pending: Option<LexerContextPending<TTokenKind, TState>>,
+    is_eof_handled: bool,
+    // The last token lexed, for contextual triggers
+    last_token: Option<Token<TTokenKind>>,
+    state: TState,
+    trace: Option<Vec<LexTrace>>,
+    // The last characters consumed, quoted as context in unexpected character messages
+    lookbehind: VecDeque<char>,
+}
+
+// A record of how one trigger was matched, for debugging surprising tokens. `peeked` has every character looked at,
+// including the last one when it had no transition. `states` starts at the start state and has each state stepped to.
+// `trigger_state` is the state whose trigger won, or `None` if no trigger matched, and `buffer` is the text that was
+// eaten while matching.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LexTrace {
+    pub peeked: Vec<char>,
+    pub states: Vec<FSAId>,
+    pub trigger_state: Option<FSAId>,
+    pub buffer: String,
+}
+
+// `state` is from before the pending token was lexed, since its callbacks have already run on the context's state
+struct LexerContextPending<TTokenKind: TokenKind, TState> {
+    location: SourceLocation,
+    mark: Option<SourceReaderMark>,
+    last_token: Option<Token<TTokenKind>>,
+    lookbehind: VecDeque<char>,
+    state: TState,
+    result: Result<Token<TTokenKind>>,
+}
+
+// A position in the source between tokens that the lexer can be rewound to. The trigger DFSA executor is created fresh
+// for every trigger, so only the source reader position, the trivia collected so far and the state need to be saved.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LexerContextMark<TTokenKind: TokenKind, TState = ()> {
+    source_reader: SourceReaderMark,
+    leading_trivia: Vec<Token<()>>,
+    is_eof_handled: bool,
+    last_token: Option<Token<TTokenKind>>,
+    lookbehind: VecDeque<char>,
+    state: TState,
 }
 
-impl<'lexer, TTokenKind: TokenKind> LexerContext<'lexer, TTokenKind> {
+enum LexedItem<TTokenKind: TokenKind> {
+    Token(Token<TTokenKind>),
+    Trivia(Token<()>),
+}
+
+impl<'lexer, TTokenKind: TokenKind, TState: Clone> LexerContext<'lexer, TTokenKind, TState> {
     pub fn new(
-        lexer: &'lexer Lexer<TTokenKind>,
+        lexer: &'lexer Lexer<TTokenKind, TState>,
+        source_reader: &'lexer mut SourceReader<'lexer>,
+        message_context: &'lexer mut MessageContext,
+    ) -> Self
+    where
+        TState: Default,
+    {
+        Self::new_with_state(lexer, source_reader, message_context, TState::default())
+    }
+
+    pub fn new_with_state(
+        lexer: &'lexer Lexer<TTokenKind, TState>,
         source_reader: &'lexer mut SourceReader<'lexer>,
         message_context: &'lexer mut MessageContext,
+        state: TState,
     ) -> Self {
         Self {
             lexer,
             source_reader,
             message_context,
+            leading_trivia: Vec::new(),
+            pending: None,
+            is_eof_handled: false,
+            last_token: None,
+            state,
+            trace: None,
+            lookbehind: VecDeque::new(),
+        }
+    }
+
+    // Tracing is off by default so that lexing doesn't pay for recording the states it visits
+    pub fn enable_tracing(&mut self) {
+        if self.trace.is_none() {
+            self.trace = Some(Vec::new());
         }
     }
 
+    pub fn is_tracing_enabled(&self) -> bool {
+        self.trace.is_some()
+    }
+
+    // Returns the traces recorded since tracing was enabled or since the last call, in the order that triggers were
+    // matched. This includes triggers for trivia and skipped text, not just tokens.
+    pub fn take_trace(&mut self) -> Vec<LexTrace> {
+        self.trace.as_mut().map(mem::take).unwrap_or_default()
+    }
+
+    // The state is changed by stateful trigger callbacks as they run. With `LexerTriviaPolicy::LeadingAndTrailing` the
+    // callbacks for the next token run before the current one is returned. `mark` saves the state from before them, so
+    // `restore` rewinds it along with the source.
+    pub fn state(&self) -> &TState {
+        &self.state
+    }
+
+    pub fn state_mut(&mut self) -> &mut TState {
+        &mut self.state
+    }
+
+    pub fn into_state(self) -> TState {
+        self.state
+    }
+
     pub fn lex_next(&mut self) -> Result<Token<TTokenKind>> {
+        let mut token = match self.pending.take() {
+            Some(pending) => pending.result?,
+            None => self.lex_next_with_leading_trivia()?,
+        };
+
+        if self.lexer.get_trivia_policy() == Some(LexerTriviaPolicy::LeadingAndTrailing) {
+            self.collect_trailing_trivia(&mut token);
+        }
+
+        if let Some(on_token) = self.lexer.get_on_token() {
+            on_token(&token);
+        }
+
+        Ok(token)
+    }
+
+    // Unlike iterating, this doesn't recover from errors or emit messages. Reaching the end of the source between
+    // tokens ends lexing, but reaching it partway through a trigger is an error.
+    pub fn try_lex_all(&mut self) -> Result<Vec<Token<TTokenKind>>> {
+        let mut result = Vec::new();
+
+        loop {
+            match self.lex_next() {
+                Ok(token) => result.push(token),
+                Err(LexingError::UnexpectedEndOfSource)
+                | Err(LexingError::SourcingError(SourcingError::NoMoreChars))
+                    if self.source_reader.is_buffer_empty() =>
+                {
+                    return Ok(result)
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    pub fn mark(&self) -> Result<LexerContextMark<TTokenKind, TState>> {
+        match &self.pending {
+            Some(pending) => Ok(LexerContextMark {
+                source_reader: pending
+                    .mark
+                    .clone()
+                    .ok_or(SourcingError::MarkingNotSupported)?,
+                leading_trivia: match &pending.result {
+                    Ok(token) => token.leading_trivia.clone(),
+                    Err(_) => self.leading_trivia.clone(),
+                },
+                is_eof_handled: self.is_eof_handled,
+                last_token: pending.last_token.clone(),
+                lookbehind: pending.lookbehind.clone(),
+                state: pending.state.clone(),
+            }),
+            None => Ok(LexerContextMark {
+                source_reader: self.source_reader.mark_position()?,
+                leading_trivia: self.leading_trivia.clone(),
+                is_eof_handled: self.is_eof_handled,
+                last_token: self.last_token.clone(),
+                lookbehind: self.lookbehind.clone(),
+                state: self.state.clone(),
+            }),
+        }
+    }
+
+    pub fn restore(&mut self, mark: &LexerContextMark<TTokenKind, TState>) -> Result<()> {
+        self.source_reader.restore_position(&mark.source_reader)?;
+        self.leading_trivia = mark.leading_trivia.clone();
+        self.pending = None;
+        self.is_eof_handled = mark.is_eof_handled;
+        self.last_token = mark.last_token.clone();
+        self.lookbehind = mark.lookbehind.clone();
+        self.state = mark.state.clone();
+
+        Ok(())
+    }
+
+    fn next_location(&self) -> SourceLocation {
+        match &self.pending {
+            Some(pending) => pending.location.clone(),
+            None => self.source_reader.location.clone(),
+        }
+    }
+
+    fn lex_next_with_leading_trivia(&mut self) -> Result<Token<TTokenKind>> {
+        loop {
+            match self.lex_next_item()? {
+                LexedItem::Token(mut token) => {
+                    token.leading_trivia = mem::take(&mut self.leading_trivia);
+                    return Ok(token);
+                }
+                LexedItem::Trivia(trivia) => self.leading_trivia.push(trivia),
+            }
+        }
+    }
+
+    fn collect_trailing_trivia(&mut self, token: &mut Token<TTokenKind>) {
+        let line = token.end_location().line;
+        let ends_with_newline = token.text.ends_with('\n');
+        let last_token = self.last_token.clone();
+
+        while self.source_reader.has_more() {
+            let location = self.source_reader.location.clone();
+            let mark = self.source_reader.mark_position().ok();
+            let lookbehind = self.lookbehind.clone();
+            let state = self.state.clone();
+
+            match self.lex_next_item() {
+                Ok(LexedItem::Trivia(trivia)) => {
+                    if self.leading_trivia.is_empty()
+                        && !ends_with_newline
+                        && trivia.location.line == line
+                    {
+                        token.trailing_trivia.push(trivia);
+                    } else {
+                        self.leading_trivia.push(trivia);
+                    }
+                }
+                Ok(LexedItem::Token(mut next)) => {
+                    next.leading_trivia = mem::take(&mut self.leading_trivia);
+                    self.pending = Some(LexerContextPending {
+                        location,
+                        mark,
+                        last_token,
+                        lookbehind,
+                        state,
+                        result: Ok(next),
+                    });
+                    break;
+                }
+                Err(err) => {
+                    self.pending = Some(LexerContextPending {
+                        location,
+                        mark,
+                        last_token,
+                        lookbehind,
+                        state,
+                        result: Err(err),
+                    });
+                    break;
+                }
+            }
+        }
+    }
+
+    fn lex_next_item(&mut self) -> Result<LexedItem<TTokenKind>> {
+        let item = self.lex_next_item_unrecorded()?;
+
+        if let LexedItem::Token(token) = &item {
+            self.last_token = Some(token.clone());
+        }
+
+        Ok(item)
+    }
+
+    fn lex_next_item_unrecorded(&mut self) -> Result<LexedItem<TTokenKind>> {
         // Check preconditions
         assert!(self.source_reader.is_buffering_enabled());
         assert!(self.source_reader.is_buffer_empty());
@@ -65,27 +326,99 @@ impl<'lexer, TTokenKind: TokenKind> LexerContext<'lexer, TTokenKind> {
         // Save location of first character of token
         let location_first = self.source_reader.location.clone();
 
+        // Give the EOF handler a chance to emit a final token
+        if !self.source_reader.has_more() && !self.is_eof_handled {
+            self.is_eof_handled = true;
+
+            if let Some(token_kind) = self.lexer.get_eof_handler().and_then(|handler| handler()) {
+                return Ok(LexedItem::Token(Token::new_from_location(
+                    location_first,
+                    String::new(),
+                    token_kind,
+                )));
+            }
+        }
+
+        // Lex a run of whitespace if whitespace is being kept
+        if let Some(whitespace_kind) = self.lexer.get_whitespace_kind() {
+            while self
+                .source_reader
+                .peek_next()
+                .is_ok_and(|value| value.is_whitespace())
+            {
+                self.source_reader.eat_next()?;
+            }
+
+            if !self.source_reader.is_buffer_empty() {
+                let text = self.source_reader.pop_buffer()?;
+                self.record_lookbehind(&text);
+
+                let mut token = Token::new_from_location(location_first, text, whitespace_kind);
+                token.set_source_end(self.source_reader.location.offset);
+
+                return Ok(LexedItem::Token(token));
+            }
+        }
+
         // Lex next trigger or return error
-        let trigger_action = self.lex_next_trigger_action()?;
+        let (callback, trigger_prefix) = self.lex_next_trigger_callback()?;
+
+        // Run the trigger action with the text that matched the trigger
+        let trigger_text = self.source_reader.peek_buffer()?.to_owned();
 
-        // Run the trigger action
-        if let Some(token_kind) = (trigger_action.callback)(self.source_reader) {
+        let token_kind = callback.call(self.source_reader, &trigger_text, &mut self.state);
+        let consumed = self.source_reader.peek_buffer()?.to_owned();
+        self.record_lookbehind(&consumed);
+
+        if let Some(token_kind) = token_kind {
             // If a token kind of specified, emit the token
-            Ok(Token::new_from_location(
+            let mut token = Token::new_from_location(
                 location_first,
                 self.source_reader.pop_buffer()?,
                 token_kind,
-            ))
+            );
+            token.set_source_end(self.source_reader.location.offset);
+
+            if self.lexer.is_recording_trigger_length() {
+                token.trigger_length = Some(trigger_prefix.chars().count());
+            }
+
+            if self.lexer.is_recording_trigger_prefix() {
+                token.trigger_prefix = Some(trigger_prefix.to_owned());
+            }
+
+            Ok(LexedItem::Token(token))
+        } else if self.lexer.get_trivia_policy().is_some() {
+            // If no token kind is specified, the text is trivia
+            let mut trivia =
+                Token::new_from_location(location_first, self.source_reader.pop_buffer()?, ());
+            trivia.set_source_end(self.source_reader.location.offset);
+
+            Ok(LexedItem::Trivia(trivia))
         } else {
-            // If no token kind is specified, skip the token and try to lex another
+            // If trivia isn't being kept, skip the token and try to lex another
             self.source_reader.clear_buffer()?;
-            self.lex_next()
+            self.lex_next_item()
+        }
+    }
+
+    fn record_lookbehind(&mut self, text: &str) {
+        let length = self.lexer.get_lookbehind_length();
+
+        self.lookbehind.extend(text.chars());
+
+        while self.lookbehind.len() > length {
+            self.lookbehind.pop_front();
         }
     }
 
-    fn lex_next_trigger_action(&mut self) -> Result<&LexerTriggerAction<TTokenKind>> {
-        let mut trigger_dfsa_executor: DFSAExecutor<char, LexerTriggerAction<TTokenKind>> =
-            DFSAExecutor::new(&self.lexer.trigger_dfsa)?;
+    // Returns the callback of the longest active trigger along with the prefix it was added for
+    fn lex_next_trigger_callback(
+        &mut self,
+    ) -> Result<(LexerTriggerCallbackKind<TTokenKind, TState>, &'lexer str)> {
+        let lexer = self.lexer;
+        let mut trigger_dfsa_executor: DFSAExecutor<char, LexerTriggerAction<TTokenKind, TState>> =
+            DFSAExecutor::new(&lexer.trigger_dfsa)?;
 
         // We need to greedily eat the next trigger using the trigger DFSA. Let's say we have a string we're going
         // to lex "abbc" and two triggers in our DFSA: one for "ab" and one for "abb". Thus our DFSA would look like
@@ -130,7 +463,7 @@ impl<'lexer, TTokenKind: TokenKind> LexerContext<'lexer, TTokenKind> {
         // The algorithm is then:
         //
         // While true:
-        //   If the current state has a trigger, save it as the best trigger so far.
+        //   If the current state has a trigger that is active after the last token, save it as the best trigger so far.
         //   Peek the next character if there are more characters to be read.
         //   If there is a transition on the next character:
         //     Eat the next character and append it to the buffer.
@@ -141,18 +474,42 @@ impl<'lexer, TTokenKind: TokenKind> LexerContext<'lexer, TTokenKind> {
         // If a best trigger has been saved, return it with the buffer so far,
         // Else go into error recovery.
 
-        let mut last_trigger: Option<&LexerTriggerAction<TTokenKind>> = None;
+        let mut last_trigger: Option<(LexerTriggerCallbackKind<TTokenKind, TState>, &'lexer str)> =
+            None;
         let first_char = self.source_reader.peek_next().ok();
 
+        let mut trace = self.trace.is_some().then(|| LexTrace {
+            peeked: Vec::new(),
+            states: Vec::from([trigger_dfsa_executor.current_id()]),
+            trigger_state: None,
+            buffer: String::new(),
+        });
+
         loop {
-            let current_trigger = trigger_dfsa_executor.current_action();
+            let current_trigger = trigger_dfsa_executor.current_action().and_then(|action| {
+                action
+                    .select(self.last_token.as_ref())
+                    .map(|callback| (callback, action.prefix.as_str()))
+            });
             if current_trigger.is_some() {
                 last_trigger = current_trigger;
+
+                if let Some(trace) = &mut trace {
+                    trace.trigger_state = Some(trigger_dfsa_executor.current_id());
+                }
             }
 
             if let Ok(next_char) = self.source_reader.peek_next() {
+                if let Some(trace) = &mut trace {
+                    trace.peeked.push(next_char);
+                }
+
                 if trigger_dfsa_executor.step(next_char).is_ok() {
                     self.source_reader.eat_next()?;
+
+                    if let Some(trace) = &mut trace {
+                        trace.states.push(trigger_dfsa_executor.current_id());
+                    }
                 } else {
                     break;
                 }
@@ -161,6 +518,11 @@ impl<'lexer, TTokenKind: TokenKind> LexerContext<'lexer, TTokenKind> {
             }
         }
 
+        if let (Some(mut trace), Some(traces)) = (trace, &mut self.trace) {
+            trace.buffer = self.source_reader.peek_buffer()?.to_owned();
+            traces.push(trace);
+        }
+
         if let Some(last_trigger) = last_trigger {
             Ok(last_trigger)
         } else if let Some(first_char) = first_char {
@@ -175,24 +537,35 @@ impl<'lexer, TTokenKind: TokenKind> LexerContext<'lexer, TTokenKind> {
     }
 }
 
-impl<'lexer, TTokenKind: TokenKind> Iterator for LexerContext<'lexer, TTokenKind> {
+impl<'lexer, TTokenKind: TokenKind, TState: Clone> Iterator
+    for LexerContext<'lexer, TTokenKind, TState>
+{
     type Item = Token<TTokenKind>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let location_first = self.source_reader.location.clone();
+        let location_first = self.next_location();
 
         match self.lex_next() {
             Ok(token) => Some(token),
             Err(LexingError::UnexpectedEndOfSource)
             | Err(LexingError::SourcingError(SourcingError::NoMoreChars)) => None,
             Err(LexingError::UnexpectedCharacter(chr)) => {
+                let description = if self.lookbehind.is_empty() {
+                    format!("unexpected character {chr:?}")
+                } else {
+                    let context: String = self.lookbehind.iter().collect();
+                    format!("unexpected character {chr:?} after {context:?}")
+                };
+
                 self.message_context.emit(Message::new_location(
                     location_first,
                     Severity::Error,
-                    format!("unexpected character {chr:?}"),
+                    description,
                 ));
 
                 (self.lexer.get_error_handler())(self.source_reader);
+                let consumed = self.source_reader.peek_buffer().ok()?.to_owned();
+                self.record_lookbehind(&consumed);
                 self.source_reader.clear_buffer().ok()?;
 
                 self.next()
@@ -224,18 +597,27 @@ impl<'lexer, TTokenKind: TokenKind> Iterator for LexerContext<'lexer, TTokenKind
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::{cell::Cell, path::PathBuf};
 
-    use crate::{domain::source_info::SourceInfo, sourcing::source_string::SourceString};
+    use crate::{
+        domain::{
+            source_info::SourceInfo,
+            token::{coverage, tokens_to_source},
+        },
+        sourcing::source_string::SourceString,
+    };
 
     use super::*;
 
     #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    #[allow(clippy::upper_case_acronyms)]
     enum TokenKindTest {
         A,
         AB,
         AC,
         ABC,
+        Whitespace,
+        Eof,
     }
 
     impl TokenKind for TokenKindTest {}
@@ -244,10 +626,16 @@ mod tests {
     fn test_lexer_context_simple() {
         let mut lexer = Lexer::<TokenKindTest>::new();
 
-        assert_eq!(lexer.add_trigger("ab", |_| Some(TokenKindTest::AB)), Ok(()));
-        assert_eq!(lexer.add_trigger("ac", |_| Some(TokenKindTest::AC)), Ok(()));
         assert_eq!(
-            lexer.add_trigger("abc", |_| Some(TokenKindTest::ABC)),
+            lexer.add_trigger("ab", |_, _| Some(TokenKindTest::AB)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("ac", |_, _| Some(TokenKindTest::AC)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("abc", |_, _| Some(TokenKindTest::ABC)),
             Ok(())
         );
 
@@ -318,10 +706,16 @@ mod tests {
     fn test_lexer_context_unexpected_char() {
         let mut lexer = Lexer::<TokenKindTest>::new();
 
-        assert_eq!(lexer.add_trigger("ab", |_| Some(TokenKindTest::AB)), Ok(()));
-        assert_eq!(lexer.add_trigger("ac", |_| Some(TokenKindTest::AC)), Ok(()));
         assert_eq!(
-            lexer.add_trigger("abc", |_| Some(TokenKindTest::ABC)),
+            lexer.add_trigger("ab", |_, _| Some(TokenKindTest::AB)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("ac", |_, _| Some(TokenKindTest::AC)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("abc", |_, _| Some(TokenKindTest::ABC)),
             Ok(())
         );
 
@@ -344,10 +738,16 @@ mod tests {
     fn test_lexer_context_unexpected_end() {
         let mut lexer = Lexer::<TokenKindTest>::new();
 
-        assert_eq!(lexer.add_trigger("ab", |_| Some(TokenKindTest::AB)), Ok(()));
-        assert_eq!(lexer.add_trigger("ac", |_| Some(TokenKindTest::AC)), Ok(()));
         assert_eq!(
-            lexer.add_trigger("abc", |_| Some(TokenKindTest::ABC)),
+            lexer.add_trigger("ab", |_, _| Some(TokenKindTest::AB)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("ac", |_, _| Some(TokenKindTest::AC)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("abc", |_, _| Some(TokenKindTest::ABC)),
             Ok(())
         );
 
@@ -371,7 +771,7 @@ mod tests {
         let mut lexer = Lexer::<TokenKindTest>::new();
 
         assert_eq!(
-            lexer.add_trigger("ab", |source_reader| {
+            lexer.add_trigger("ab", |source_reader, _| {
                 while source_reader.peek_next() == Ok('b') {
                     let _ = source_reader.eat_next();
                 }
@@ -380,9 +780,12 @@ mod tests {
             }),
             Ok(())
         );
-        assert_eq!(lexer.add_trigger("ac", |_| Some(TokenKindTest::AC)), Ok(()));
         assert_eq!(
-            lexer.add_trigger("abc", |_| Some(TokenKindTest::ABC)),
+            lexer.add_trigger("ac", |_, _| Some(TokenKindTest::AC)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("abc", |_, _| Some(TokenKindTest::ABC)),
             Ok(())
         );
 
@@ -453,7 +856,10 @@ mod tests {
     fn test_lexer_context_error_recovery_default() {
         let mut lexer = Lexer::<TokenKindTest>::new();
 
-        assert_eq!(lexer.add_trigger("a", |_| Some(TokenKindTest::A)), Ok(()));
+        assert_eq!(
+            lexer.add_trigger("a", |_, _| Some(TokenKindTest::A)),
+            Ok(())
+        );
 
         let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "aba");
         let mut source_reader = SourceReader::new(&mut source);
@@ -490,7 +896,8 @@ mod tests {
 
         assert_eq!(token_source.next(), None);
 
-        assert!((&message_context.messages)
+        assert!(message_context
+            .messages
             .iter()
             .any(|i| (*i.description).contains("unexpected")));
     }
@@ -499,7 +906,10 @@ mod tests {
     fn test_lexer_context_error_recovery_simple() {
         let mut lexer = Lexer::<TokenKindTest>::new();
 
-        assert_eq!(lexer.add_trigger("a", |_| Some(TokenKindTest::A)), Ok(()));
+        assert_eq!(
+            lexer.add_trigger("a", |_, _| Some(TokenKindTest::A)),
+            Ok(())
+        );
 
         lexer.set_error_handler(|source| {
             let _ = source.eat_next();
@@ -541,8 +951,870 @@ mod tests {
 
         assert_eq!(token_source.next(), None);
 
-        assert!((&message_context.messages)
+        assert!(message_context
+            .messages
             .iter()
             .any(|i| (*i.description).contains("unexpected")));
     }
+
+    #[test]
+    fn test_lexer_context_error_lookbehind() {
+        let mut lexer = Lexer::<TokenKindTest>::new();
+
+        assert_eq!(
+            lexer.add_trigger("a", |_, _| Some(TokenKindTest::A)),
+            Ok(())
+        );
+
+        assert_eq!(lexer.add_trigger(" ", |_, _| None), Ok(()));
+
+        lexer.set_lookbehind_length(4);
+
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "aa a a@");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        assert_eq!(
+            lexer
+                .lex(&mut source_reader, &mut message_context)
+                .map(|token| token.kind)
+                .collect::<Vec<_>>(),
+            vec![TokenKindTest::A; 4]
+        );
+
+        assert_eq!(message_context.messages.len(), 1);
+        assert_eq!(
+            message_context.messages[0].description,
+            "unexpected character '@' after \" a a\""
+        );
+    }
+
+    #[test]
+    fn test_lexer_context_trigger_text() {
+        fn callback(_: &mut dyn ReadSource, trigger_text: &str) -> Option<TokenKindTest> {
+            match trigger_text {
+                "ab" => Some(TokenKindTest::AB),
+                "ac" => Some(TokenKindTest::AC),
+                _ => None,
+            }
+        }
+
+        let mut lexer = Lexer::<TokenKindTest>::new();
+
+        assert_eq!(lexer.add_trigger("ab", callback), Ok(()));
+        assert_eq!(lexer.add_trigger("ac", callback), Ok(()));
+
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "acab");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let mut token_source = lexer.lex(&mut source_reader, &mut message_context);
+
+        assert_eq!(
+            token_source.lex_next(),
+            Ok(Token::new(
+                PathBuf::from("--"),
+                0,
+                1,
+                1,
+                String::from("ac"),
+                TokenKindTest::AC
+            ))
+        );
+
+        assert_eq!(
+            token_source.lex_next(),
+            Ok(Token::new(
+                PathBuf::from("--"),
+                2,
+                1,
+                3,
+                String::from("ab"),
+                TokenKindTest::AB
+            ))
+        );
+    }
+
+    #[test]
+    fn test_lexer_context_record_trigger_length() {
+        let mut lexer = Lexer::<TokenKindTest>::new();
+
+        assert_eq!(
+            lexer.add_trigger("ab", |source_reader, _| {
+                while source_reader.peek_next() == Ok('b') {
+                    let _ = source_reader.eat_next();
+                }
+
+                Some(TokenKindTest::AB)
+            }),
+            Ok(())
+        );
+
+        lexer.set_record_trigger_length(true);
+
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "abbb");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let mut token_source = lexer.lex(&mut source_reader, &mut message_context);
+
+        let token = token_source.lex_next().unwrap();
+
+        assert_eq!(token.text, "abbb");
+        assert_eq!(token.trigger_length, Some(2));
+    }
+
+    #[test]
+    fn test_lexer_context_record_trigger_length_overshoot() {
+        let mut lexer = Lexer::<TokenKindTest>::new();
+
+        assert_eq!(
+            lexer.add_trigger("a", |_, _| Some(TokenKindTest::A)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("abc", |_, _| Some(TokenKindTest::ABC)),
+            Ok(())
+        );
+
+        lexer.set_record_trigger_length(true);
+
+        // The DFSA reads "ab" looking for "abc" before falling back to the "a" trigger
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "abd");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let mut token_source = lexer.lex(&mut source_reader, &mut message_context);
+
+        assert_eq!(
+            token_source
+                .lex_next()
+                .map(|token| (token.kind, token.trigger_length)),
+            Ok((TokenKindTest::A, Some(1)))
+        );
+    }
+
+    #[test]
+    fn test_lexer_context_record_trigger_prefix() {
+        let mut lexer = Lexer::<TokenKindTest>::new();
+
+        assert_eq!(
+            lexer.add_trigger("ab", |_, _| Some(TokenKindTest::AB)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("ac", |source_reader, _| {
+                while source_reader.peek_next() == Ok('c') {
+                    let _ = source_reader.eat_next();
+                }
+
+                Some(TokenKindTest::AC)
+            }),
+            Ok(())
+        );
+
+        lexer.set_record_trigger_prefix(true);
+
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "abaccab");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let tokens = lexer
+            .lex(&mut source_reader, &mut message_context)
+            .try_lex_all()
+            .unwrap();
+
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| (token.text.as_str(), token.trigger_prefix.as_deref()))
+                .collect::<Vec<_>>(),
+            Vec::from([("ab", Some("ab")), ("acc", Some("ac")), ("ab", Some("ab"))])
+        );
+    }
+
+    #[test]
+    fn test_lexer_context_record_trigger_prefix_overshoot() {
+        let mut lexer = Lexer::<TokenKindTest>::new();
+
+        assert_eq!(
+            lexer.add_trigger("a", |_, _| Some(TokenKindTest::A)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("abc", |_, _| Some(TokenKindTest::ABC)),
+            Ok(())
+        );
+
+        lexer.set_record_trigger_prefix(true);
+
+        // The DFSA reads "ab" looking for "abc" before falling back to the "a" trigger
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "abd");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let mut token_source = lexer.lex(&mut source_reader, &mut message_context);
+
+        assert_eq!(
+            token_source
+                .lex_next()
+                .map(|token| (token.kind, token.trigger_prefix)),
+            Ok((TokenKindTest::A, Some(String::from("a"))))
+        );
+    }
+
+    fn lexer_with_trivia(policy: LexerTriviaPolicy) -> Lexer<TokenKindTest> {
+        let mut lexer = Lexer::<TokenKindTest>::new();
+
+        assert_eq!(
+            lexer.add_trigger("a", |_, _| Some(TokenKindTest::A)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("ab", |_, _| Some(TokenKindTest::AB)),
+            Ok(())
+        );
+        assert_eq!(lexer.add_trigger(" ", |_, _| None), Ok(()));
+        assert_eq!(lexer.add_trigger("\n", |_, _| None), Ok(()));
+        assert_eq!(
+            lexer.add_trigger("#", |source_reader, _| {
+                while source_reader.peek_next().is_ok_and(|value| value != '\n') {
+                    let _ = source_reader.eat_next();
+                }
+
+                None
+            }),
+            Ok(())
+        );
+
+        lexer.set_trivia_policy(Some(policy));
+
+        lexer
+    }
+
+    fn trivia(offset: usize, line: u32, column: u32, text: &str) -> Token<()> {
+        Token::new(
+            PathBuf::from("--"),
+            offset,
+            line,
+            column,
+            String::from(text),
+            (),
+        )
+    }
+
+    #[test]
+    fn test_lexer_context_trivia_leading() {
+        let lexer = lexer_with_trivia(LexerTriviaPolicy::Leading);
+
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "a ab # c\nab");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let tokens: Vec<Token<TokenKindTest>> = lexer
+            .lex(&mut source_reader, &mut message_context)
+            .collect();
+
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[0].leading_trivia, Vec::new());
+        assert_eq!(tokens[1].leading_trivia, Vec::from([trivia(1, 1, 2, " ")]));
+        assert_eq!(
+            tokens[2].leading_trivia,
+            Vec::from([
+                trivia(4, 1, 5, " "),
+                trivia(5, 1, 6, "# c"),
+                trivia(8, 1, 9, "\n")
+            ])
+        );
+        assert!(tokens.iter().all(|token| token.trailing_trivia.is_empty()));
+    }
+
+    #[test]
+    fn test_lexer_context_trivia_trailing() {
+        let lexer = lexer_with_trivia(LexerTriviaPolicy::LeadingAndTrailing);
+
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "a ab # c\nab");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let tokens: Vec<Token<TokenKindTest>> = lexer
+            .lex(&mut source_reader, &mut message_context)
+            .collect();
+
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[0].text, "a");
+        assert_eq!(tokens[0].leading_trivia, Vec::new());
+        assert_eq!(tokens[0].trailing_trivia, Vec::from([trivia(1, 1, 2, " ")]));
+        assert_eq!(tokens[1].text, "ab");
+        assert_eq!(tokens[1].leading_trivia, Vec::new());
+        assert_eq!(
+            tokens[1].trailing_trivia,
+            Vec::from([
+                trivia(4, 1, 5, " "),
+                trivia(5, 1, 6, "# c"),
+                trivia(8, 1, 9, "\n")
+            ])
+        );
+        assert_eq!(tokens[2].text, "ab");
+        assert_eq!(tokens[2].location.line, 2);
+        assert_eq!(tokens[2].leading_trivia, Vec::new());
+        assert_eq!(tokens[2].trailing_trivia, Vec::new());
+    }
+
+    #[test]
+    fn test_lexer_context_mark_restore() {
+        let lexer = lexer_with_trivia(LexerTriviaPolicy::LeadingAndTrailing);
+
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "a ab # c\nab a");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let mut token_source = lexer.lex(&mut source_reader, &mut message_context);
+
+        assert_eq!(
+            token_source.lex_next().map(|token| token.text),
+            Ok(String::from("a"))
+        );
+
+        let mark = token_source.mark().unwrap();
+
+        let first = Vec::from([token_source.lex_next(), token_source.lex_next()]);
+
+        assert_eq!(token_source.restore(&mark), Ok(()));
+
+        let second = Vec::from([token_source.lex_next(), token_source.lex_next()]);
+
+        assert_eq!(first, second);
+        assert_eq!(
+            first
+                .into_iter()
+                .map(|token| token.unwrap().text)
+                .collect::<Vec<String>>(),
+            Vec::from([String::from("ab"), String::from("ab")])
+        );
+        assert_eq!(
+            token_source.lex_next().map(|token| token.text),
+            Ok(String::from("a"))
+        );
+    }
+
+    #[test]
+    fn test_lexer_context_new_default_state() {
+        let mut lexer = Lexer::<TokenKindTest, usize>::new();
+
+        assert_eq!(
+            lexer.add_trigger_with_state("a", |_, _, count| {
+                *count += 1;
+                Some(TokenKindTest::A)
+            }),
+            Ok(())
+        );
+
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "aa");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let mut token_source = LexerContext::new(&lexer, &mut source_reader, &mut message_context);
+
+        assert_eq!(*token_source.state(), 0);
+        assert_eq!(token_source.by_ref().count(), 2);
+        assert_eq!(token_source.into_state(), 2);
+    }
+
+    #[test]
+    fn test_lexer_context_mark_restore_state() {
+        let mut lexer = Lexer::<TokenKindTest, usize>::new();
+
+        assert_eq!(
+            lexer.add_trigger_with_state("(", |_, _, depth| {
+                *depth += 1;
+                Some(TokenKindTest::A)
+            }),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger_with_state(")", |_, _, depth| {
+                *depth -= 1;
+                Some(TokenKindTest::AB)
+            }),
+            Ok(())
+        );
+        assert_eq!(lexer.add_trigger(" ", |_, _| None), Ok(()));
+
+        lexer.set_trivia_policy(Some(LexerTriviaPolicy::LeadingAndTrailing));
+
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "( ( ) )");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let mut token_source = lexer.lex_with_state(&mut source_reader, &mut message_context, 0);
+
+        assert_eq!(
+            token_source.lex_next().map(|token| token.text),
+            Ok(String::from("("))
+        );
+
+        // The second `(` has already been lexed as the next token to find the trailing trivia of the first
+        assert_eq!(*token_source.state(), 2);
+
+        let mark = token_source.mark().unwrap();
+
+        assert_eq!(token_source.by_ref().count(), 3);
+        assert_eq!(*token_source.state(), 0);
+
+        assert_eq!(token_source.restore(&mark), Ok(()));
+        assert_eq!(*token_source.state(), 1);
+
+        assert_eq!(token_source.by_ref().count(), 3);
+        assert_eq!(token_source.into_state(), 0);
+    }
+
+    #[test]
+    fn test_lexer_context_whitespace_round_trip() {
+        let mut lexer = Lexer::<TokenKindTest>::new();
+
+        assert_eq!(
+            lexer.add_trigger("ab", |_, _| Some(TokenKindTest::AB)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("ac", |_, _| Some(TokenKindTest::AC)),
+            Ok(())
+        );
+
+        lexer.set_whitespace_kind(Some(TokenKindTest::Whitespace));
+
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "ab ac\nab");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let tokens: Vec<Token<TokenKindTest>> = lexer
+            .lex(&mut source_reader, &mut message_context)
+            .collect();
+
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| token.kind)
+                .collect::<Vec<TokenKindTest>>(),
+            Vec::from([
+                TokenKindTest::AB,
+                TokenKindTest::Whitespace,
+                TokenKindTest::AC,
+                TokenKindTest::Whitespace,
+                TokenKindTest::AB
+            ])
+        );
+        assert_eq!(tokens_to_source(&tokens), "ab ac\nab");
+    }
+
+    #[test]
+    fn test_lexer_context_whitespace_round_trip_newline_normalization() {
+        let mut lexer = Lexer::<TokenKindTest>::new();
+
+        assert_eq!(
+            lexer.add_trigger("ab", |_, _| Some(TokenKindTest::AB)),
+            Ok(())
+        );
+
+        lexer.set_whitespace_kind(Some(TokenKindTest::Whitespace));
+
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "ab\r\nab");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let tokens: Vec<Token<TokenKindTest>> = lexer
+            .lex(&mut source_reader, &mut message_context)
+            .collect();
+
+        assert_eq!(tokens_to_source(&tokens), "ab\nab");
+    }
+
+    #[test]
+    fn test_lexer_context_folded_newline_end_location() {
+        let mut lexer = Lexer::<TokenKindTest>::new();
+
+        assert_eq!(
+            lexer.add_trigger("ab", |_, _| Some(TokenKindTest::AB)),
+            Ok(())
+        );
+
+        lexer.set_whitespace_kind(Some(TokenKindTest::Whitespace));
+
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "ab\r\nab");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let tokens: Vec<Token<TokenKindTest>> = lexer
+            .lex(&mut source_reader, &mut message_context)
+            .collect();
+
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[1].text, "\n");
+        assert_eq!(tokens[1].source_length, Some(2));
+        assert_eq!(tokens[0].source_length, None);
+        assert_eq!(tokens[1].end_location(), tokens[2].location);
+        assert_eq!(coverage(&tokens, 6), Vec::new());
+
+        let merged = tokens[0]
+            .clone()
+            .merge(tokens[1].clone())
+            .merge(tokens[2].clone());
+
+        assert_eq!(merged.text, "ab\nab");
+        assert_eq!(merged.end_location().offset, 6);
+    }
+
+    #[test]
+    fn test_lexer_context_recording_bom() {
+        let mut lexer = Lexer::<TokenKindTest>::new();
+
+        assert_eq!(
+            lexer.add_trigger("ab", |_, _| Some(TokenKindTest::AB)),
+            Ok(())
+        );
+
+        lexer.set_whitespace_kind(Some(TokenKindTest::Whitespace));
+
+        let mut source =
+            SourceString::new_recording_bom(SourceInfo::new(PathBuf::from("--")), "\u{feff}ab ab");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let tokens: Vec<Token<TokenKindTest>> = lexer
+            .lex(&mut source_reader, &mut message_context)
+            .collect();
+
+        assert!(tokens[0].location.info.had_bom);
+        assert_eq!(
+            tokens[0].location,
+            SourceLocation::new(PathBuf::from("--"), 0, 1, 1)
+        );
+        assert_eq!(tokens_to_source(&tokens), "\u{feff}ab ab");
+    }
+
+    #[test]
+    fn test_lexer_context_contextual_trigger() {
+        #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+        enum TokenKindSlash {
+            Identifier,
+            Equals,
+            Divide,
+            Regex,
+        }
+
+        impl TokenKind for TokenKindSlash {}
+
+        let mut lexer = Lexer::<TokenKindSlash>::new();
+
+        assert_eq!(
+            lexer.add_trigger("x", |_, _| Some(TokenKindSlash::Identifier)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("=", |_, _| Some(TokenKindSlash::Equals)),
+            Ok(())
+        );
+        assert_eq!(lexer.add_trigger(" ", |_, _| None), Ok(()));
+        assert_eq!(
+            lexer.add_trigger("/", |_, _| Some(TokenKindSlash::Divide)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_contextual_trigger(
+                "/",
+                |last_token| {
+                    !matches!(
+                        last_token.map(|token| token.kind),
+                        Some(TokenKindSlash::Identifier)
+                    )
+                },
+                |source_reader, _| {
+                    while source_reader.eat_next().is_ok_and(|value| value != '/') {}
+
+                    Some(TokenKindSlash::Regex)
+                }
+            ),
+            Ok(())
+        );
+
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "x = /x/ x / x");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let tokens: Vec<(TokenKindSlash, String)> = lexer
+            .lex(&mut source_reader, &mut message_context)
+            .map(|token| (token.kind, token.text))
+            .collect();
+
+        assert_eq!(
+            tokens,
+            Vec::from([
+                (TokenKindSlash::Identifier, String::from("x")),
+                (TokenKindSlash::Equals, String::from("=")),
+                (TokenKindSlash::Regex, String::from("/x/")),
+                (TokenKindSlash::Identifier, String::from("x")),
+                (TokenKindSlash::Divide, String::from("/")),
+                (TokenKindSlash::Identifier, String::from("x")),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_lexer_context_state() {
+        let mut lexer = Lexer::<TokenKindTest, usize>::new();
+
+        assert_eq!(
+            lexer.add_trigger_with_state("a", |_, _, count| {
+                *count += 1;
+                Some(TokenKindTest::A)
+            }),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("ab", |_, _| Some(TokenKindTest::AB)),
+            Ok(())
+        );
+        assert_eq!(lexer.add_trigger(" ", |_, _| None), Ok(()));
+
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "a ab a a");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let mut token_source = lexer.lex_with_state(&mut source_reader, &mut message_context, 10);
+
+        assert_eq!(token_source.by_ref().count(), 4);
+        assert_eq!(token_source.into_state(), 13);
+    }
+
+    #[test]
+    fn test_lexer_context_trace() {
+        let mut lexer = Lexer::<TokenKindTest>::new();
+
+        assert_eq!(
+            lexer.add_trigger("abc", |_, _| Some(TokenKindTest::A)),
+            Ok(())
+        );
+
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "abc");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let mut token_source = lexer.lex(&mut source_reader, &mut message_context);
+
+        assert!(!token_source.is_tracing_enabled());
+
+        token_source.enable_tracing();
+
+        assert!(token_source.lex_next().is_ok());
+
+        let states = ["", "a", "ab", "abc"]
+            .iter()
+            .map(|prefix| lexer.state_for_prefix(prefix).unwrap())
+            .collect::<Vec<FSAId>>();
+
+        assert_eq!(
+            token_source.take_trace(),
+            Vec::from([LexTrace {
+                peeked: Vec::from(['a', 'b', 'c']),
+                states: states.clone(),
+                trigger_state: Some(states[3]),
+                buffer: String::from("abc"),
+            }])
+        );
+        assert_eq!(token_source.take_trace(), Vec::new());
+    }
+
+    #[test]
+    fn test_lexer_context_eof_handler() {
+        let mut lexer = Lexer::<TokenKindTest>::new();
+
+        assert_eq!(
+            lexer.add_trigger("ab", |_, _| Some(TokenKindTest::AB)),
+            Ok(())
+        );
+        assert_eq!(lexer.add_trigger(" ", |_, _| None), Ok(()));
+
+        lexer.set_eof_handler(|| Some(TokenKindTest::Eof));
+
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "ab ");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let mut token_source = lexer.lex(&mut source_reader, &mut message_context);
+
+        assert_eq!(
+            token_source.next().map(|token| token.kind),
+            Some(TokenKindTest::AB)
+        );
+        assert_eq!(
+            token_source.next(),
+            Some(Token::new(
+                PathBuf::from("--"),
+                3,
+                1,
+                4,
+                String::new(),
+                TokenKindTest::Eof
+            ))
+        );
+        assert_eq!(token_source.next(), None);
+        assert_eq!(token_source.next(), None);
+    }
+
+    #[test]
+    fn test_lexer_context_on_token() {
+        thread_local! {
+            static TOKEN_COUNT: Cell<usize> = const { Cell::new(0) };
+        }
+
+        let mut lexer = lexer_with_trivia(LexerTriviaPolicy::LeadingAndTrailing);
+
+        lexer.set_on_token(Some(|token| {
+            assert_ne!(token.text, " ");
+            TOKEN_COUNT.with(|count| count.set(count.get() + 1));
+        }));
+
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "a  ab # c\n a");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let tokens: Vec<Token<TokenKindTest>> = lexer
+            .lex(&mut source_reader, &mut message_context)
+            .collect();
+
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(TOKEN_COUNT.with(Cell::get), 3);
+    }
+
+    #[test]
+    fn test_lexer_context_try_lex_all() {
+        let mut lexer = Lexer::<TokenKindTest>::new();
+
+        assert_eq!(
+            lexer.add_trigger("ab", |_, _| Some(TokenKindTest::AB)),
+            Ok(())
+        );
+        assert_eq!(lexer.add_trigger(" ", |_, _| None), Ok(()));
+
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "ab ab ");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let mut token_source = lexer.lex(&mut source_reader, &mut message_context);
+
+        assert_eq!(token_source.try_lex_all().map(|tokens| tokens.len()), Ok(2));
+    }
+
+    #[test]
+    fn test_lexer_context_try_lex_all_unexpected_character() {
+        let mut lexer = Lexer::<TokenKindTest>::new();
+
+        assert_eq!(
+            lexer.add_trigger("ab", |_, _| Some(TokenKindTest::AB)),
+            Ok(())
+        );
+
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "abxab");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let mut token_source = lexer.lex(&mut source_reader, &mut message_context);
+
+        assert_eq!(
+            token_source.try_lex_all(),
+            Err(LexingError::UnexpectedCharacter('x'))
+        );
+        assert!(message_context.messages.is_empty());
+    }
+
+    #[test]
+    fn test_lexer_context_try_lex_all_unexpected_end_of_source() {
+        let mut lexer = Lexer::<TokenKindTest>::new();
+
+        assert_eq!(
+            lexer.add_trigger("ab", |_, _| Some(TokenKindTest::AB)),
+            Ok(())
+        );
+
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "aba");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let mut token_source = lexer.lex(&mut source_reader, &mut message_context);
+
+        assert_eq!(
+            token_source.try_lex_all(),
+            Err(LexingError::UnexpectedEndOfSource)
+        );
+    }
 }
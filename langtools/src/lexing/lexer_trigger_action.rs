@@ -20,17 +20,115 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use crate::sourcing::read_source::ReadSource;
+use crate::{
+    domain::token::{Token, TokenKind},
+    sourcing::read_source::ReadSource,
+};
 
-pub type LexerTriggerActionCallback<TTokenKind> = fn(&mut dyn ReadSource) -> Option<TTokenKind>;
+pub type LexerTriggerActionCallback<TTokenKind> =
+    fn(&mut dyn ReadSource, &str) -> Option<TTokenKind>;
+
+// Like `LexerTriggerActionCallback`, but also given the lexer context's user state
+pub type LexerTriggerActionStatefulCallback<TTokenKind, TState> =
+    fn(&mut dyn ReadSource, &str, &mut TState) -> Option<TTokenKind>;
+
+// Decides whether a contextual trigger is active given the last token that was lexed, not counting trivia. The token's
+// trailing trivia may not have been collected yet.
+pub type LexerTriggerPredicate<TTokenKind> = fn(Option<&Token<TTokenKind>>) -> bool;
+
+pub enum LexerTriggerCallbackKind<TTokenKind, TState> {
+    Plain(LexerTriggerActionCallback<TTokenKind>),
+    Stateful(LexerTriggerActionStatefulCallback<TTokenKind, TState>),
+}
+
+impl<TTokenKind, TState> Clone for LexerTriggerCallbackKind<TTokenKind, TState> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<TTokenKind, TState> Copy for LexerTriggerCallbackKind<TTokenKind, TState> {}
+
+impl<TTokenKind, TState> LexerTriggerCallbackKind<TTokenKind, TState> {
+    pub fn call(
+        &self,
+        read_source: &mut dyn ReadSource,
+        trigger_text: &str,
+        state: &mut TState,
+    ) -> Option<TTokenKind> {
+        match self {
+            LexerTriggerCallbackKind::Plain(callback) => callback(read_source, trigger_text),
+            LexerTriggerCallbackKind::Stateful(callback) => {
+                callback(read_source, trigger_text, state)
+            }
+        }
+    }
+}
 
 #[readonly::make]
-pub struct LexerTriggerAction<TTokenKind> {
-    pub callback: LexerTriggerActionCallback<TTokenKind>,
+pub struct LexerTriggerContextual<TTokenKind: TokenKind, TState = ()> {
+    pub predicate: LexerTriggerPredicate<TTokenKind>,
+    pub callback: LexerTriggerCallbackKind<TTokenKind, TState>,
 }
 
-impl<TTokenKind> LexerTriggerAction<TTokenKind> {
-    pub fn new(callback: LexerTriggerActionCallback<TTokenKind>) -> Self {
-        Self { callback }
+// Everything that can fire on one trigger prefix. Contextual triggers are tried in the order they were added and the
+// first active one wins. If none are active, the unconditional callback is used. If there isn't one either, the
+// prefix doesn't match in this context and a shorter trigger may match instead.
+#[readonly::make]
+pub struct LexerTriggerAction<TTokenKind: TokenKind, TState = ()> {
+    // The prefix the action was added for, which is what `Token::trigger_prefix` records
+    pub prefix: String,
+    pub callback: Option<LexerTriggerCallbackKind<TTokenKind, TState>>,
+    pub contextual: Vec<LexerTriggerContextual<TTokenKind, TState>>,
+}
+
+impl<TTokenKind: TokenKind, TState> LexerTriggerAction<TTokenKind, TState> {
+    pub fn new(prefix: String, callback: LexerTriggerCallbackKind<TTokenKind, TState>) -> Self {
+        Self {
+            prefix,
+            callback: Some(callback),
+            contextual: Vec::new(),
+        }
+    }
+
+    pub fn new_contextual(
+        prefix: String,
+        predicate: LexerTriggerPredicate<TTokenKind>,
+        callback: LexerTriggerCallbackKind<TTokenKind, TState>,
+    ) -> Self {
+        Self {
+            prefix,
+            callback: None,
+            contextual: Vec::from([LexerTriggerContextual {
+                predicate,
+                callback,
+            }]),
+        }
+    }
+
+    pub fn set_callback(&mut self, callback: LexerTriggerCallbackKind<TTokenKind, TState>) {
+        self.callback = Some(callback);
+    }
+
+    pub fn add_contextual(
+        &mut self,
+        predicate: LexerTriggerPredicate<TTokenKind>,
+        callback: LexerTriggerCallbackKind<TTokenKind, TState>,
+    ) {
+        self.contextual.push(LexerTriggerContextual {
+            predicate,
+            callback,
+        });
+    }
+
+    pub fn select(
+        &self,
+        last_token: Option<&Token<TTokenKind>>,
+    ) -> Option<LexerTriggerCallbackKind<TTokenKind, TState>> {
+        self.contextual
+            .iter()
+            .find(|contextual| (contextual.predicate)(last_token))
+            .map(|contextual| contextual.callback)
+            .or(self.callback)
     }
 }
@@ -92,7 +92,7 @@ impl<TElement: Eq + Hash, TAction> NFSA<TElement, TAction> {
 
         match self
             .try_get_state_mut(from_id)?
-            .transitions
+            .transitions_value
             .insert(on_element, to_id)
         {
             Some(_) => Err(FSAError::TransitionAlreadyExists),
@@ -105,13 +105,14 @@ impl<TElement: Eq + Hash, TAction> NFSA<TElement, TAction> {
             return Err(FSAError::OutOfRangeId(to_id));
         }
 
-        match self
+        if self
             .try_get_state_mut(from_id)?
-            .transitions
-            .insert(on_element, to_id)
+            .transitions_epsilon
+            .insert(to_id)
         {
-            Some(_) => Err(FSAError::TransitionAlreadyExists),
-            None => Ok(()),
+            Ok(())
+        } else {
+            Err(FSAError::TransitionAlreadyExists)
         }
     }
 
@@ -146,7 +147,7 @@ impl<TElement: Eq + Hash, TAction> NFSA<TElement, TAction> {
 
     pub fn try_get_transition(&self, from_id: FSAId, on_element: TElement) -> Result<FSAId> {
         self.try_get_state(from_id)?
-            .transitions
+            .transitions_value
             .get(&on_element)
             .ok_or(FSAError::NoSuchTransition)
             .copied()
@@ -156,16 +157,16 @@ impl<TElement: Eq + Hash, TAction> NFSA<TElement, TAction> {
         id < self.states.len()
     }
 
-    fn try_get_state(&self, id: FSAId) -> Result<&DFSAState<TElement, TAction>> {
+    fn try_get_state(&self, id: FSAId) -> Result<&NFSAState<TElement, TAction>> {
         self.states.get(id).ok_or(FSAError::OutOfRangeId(id))
     }
 
-    fn try_get_state_mut(&mut self, id: FSAId) -> Result<&mut DFSAState<TElement, TAction>> {
+    fn try_get_state_mut(&mut self, id: FSAId) -> Result<&mut NFSAState<TElement, TAction>> {
         self.states.get_mut(id).ok_or(FSAError::OutOfRangeId(id))
     }
 }
 
-impl<T: Eq + Hash, U> Default for DFSA<T, U> {
+impl<T: Eq + Hash, U> Default for NFSA<T, U> {
     fn default() -> Self {
         Self::new()
     }
@@ -176,45 +177,45 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_dfsa_add_transition_good() -> Result<()> {
-        let mut dfsa = DFSA::<char, String>::new();
-        let start = dfsa.add_state();
-        let a = dfsa.add_state();
-        dfsa.add_transition(start, 'a', a)?;
+    fn test_nfsa_add_transition_good() -> Result<()> {
+        let mut nfsa = NFSA::<char, String>::new();
+        let start = nfsa.add_state();
+        let a = nfsa.add_state();
+        nfsa.add_transition_value(start, 'a', a)?;
         Ok(())
     }
 
     #[test]
-    fn test_dfsa_add_transition_bad_from_id() {
-        let mut dfsa = DFSA::<char, String>::new();
-        let a = dfsa.add_state();
+    fn test_nfsa_add_transition_bad_from_id() {
+        let mut nfsa = NFSA::<char, String>::new();
+        let a = nfsa.add_state();
 
         assert_eq!(
-            dfsa.add_transition(100, 'a', a),
+            nfsa.add_transition_value(100, 'a', a),
             Err(FSAError::OutOfRangeId(100))
         );
     }
 
     #[test]
-    fn test_dfsa_add_transition_bad_to_id() {
-        let mut dfsa = DFSA::<char, String>::new();
-        let start = dfsa.add_state();
+    fn test_nfsa_add_transition_bad_to_id() {
+        let mut nfsa = NFSA::<char, String>::new();
+        let start = nfsa.add_state();
 
         assert_eq!(
-            dfsa.add_transition(start, 'a', 100),
+            nfsa.add_transition_value(start, 'a', 100),
             Err(FSAError::OutOfRangeId(100))
         );
     }
 
     #[test]
-    fn test_dfsa_add_transition_duplicate() -> Result<()> {
-        let mut dfsa = DFSA::<char, String>::new();
-        let start = dfsa.add_state();
-        let a = dfsa.add_state();
-        dfsa.add_transition(start, 'a', a)?;
+    fn test_nfsa_add_transition_duplicate() -> Result<()> {
+        let mut nfsa = NFSA::<char, String>::new();
+        let start = nfsa.add_state();
+        let a = nfsa.add_state();
+        nfsa.add_transition_value(start, 'a', a)?;
 
         assert_eq!(
-            dfsa.add_transition(start, 'a', a),
+            nfsa.add_transition_value(start, 'a', a),
             Err(FSAError::TransitionAlreadyExists)
         );
 
@@ -222,26 +223,26 @@ mod tests {
     }
 
     #[test]
-    fn test_dfsa_try_get_transition_good() -> Result<()> {
-        let mut dfsa = DFSA::<char, String>::new();
-        let start = dfsa.add_state();
-        let a = dfsa.add_state();
-        dfsa.add_transition(start, 'a', a)?;
+    fn test_nfsa_try_get_transition_good() -> Result<()> {
+        let mut nfsa = NFSA::<char, String>::new();
+        let start = nfsa.add_state();
+        let a = nfsa.add_state();
+        nfsa.add_transition_value(start, 'a', a)?;
 
-        assert_eq!(dfsa.try_get_transition(start, 'a'), Ok(a));
+        assert_eq!(nfsa.try_get_transition(start, 'a'), Ok(a));
 
         Ok(())
     }
 
     #[test]
-    fn test_dfsa_try_get_transition_bad_element() -> Result<()> {
-        let mut dfsa = DFSA::<char, String>::new();
-        let start = dfsa.add_state();
-        let a = dfsa.add_state();
-        dfsa.add_transition(start, 'a', a)?;
+    fn test_nfsa_try_get_transition_bad_element() -> Result<()> {
+        let mut nfsa = NFSA::<char, String>::new();
+        let start = nfsa.add_state();
+        let a = nfsa.add_state();
+        nfsa.add_transition_value(start, 'a', a)?;
 
         assert_eq!(
-            dfsa.try_get_transition(start, 'b'),
+            nfsa.try_get_transition(start, 'b'),
             Err(FSAError::NoSuchTransition)
         );
 
@@ -249,14 +250,14 @@ mod tests {
     }
 
     #[test]
-    fn test_dfsa_try_get_transition_bad_id() -> Result<()> {
-        let mut dfsa = DFSA::<char, String>::new();
-        let start = dfsa.add_state();
-        let a = dfsa.add_state();
-        dfsa.add_transition(start, 'a', a)?;
+    fn test_nfsa_try_get_transition_bad_id() -> Result<()> {
+        let mut nfsa = NFSA::<char, String>::new();
+        let start = nfsa.add_state();
+        let a = nfsa.add_state();
+        nfsa.add_transition_value(start, 'a', a)?;
 
         assert_eq!(
-            dfsa.try_get_transition(100, 'b'),
+            nfsa.try_get_transition(100, 'b'),
             Err(FSAError::OutOfRangeId(100))
         );
 
@@ -264,59 +265,59 @@ mod tests {
     }
 
     #[test]
-    fn test_dfsa_set_start_id_good() -> Result<()> {
-        let mut dfsa = DFSA::<char, String>::new();
-        let start = dfsa.add_state();
+    fn test_nfsa_set_start_id_good() -> Result<()> {
+        let mut nfsa = NFSA::<char, String>::new();
+        let start = nfsa.add_state();
 
-        dfsa.set_start_id(start)?;
-        dfsa.set_start_id(start)?;
+        nfsa.set_start_id(start)?;
+        nfsa.set_start_id(start)?;
 
         Ok(())
     }
 
     #[test]
-    fn test_dfsa_set_start_id_bad() {
-        let mut dfsa = DFSA::<char, String>::new();
+    fn test_nfsa_set_start_id_bad() {
+        let mut nfsa = NFSA::<char, String>::new();
 
-        assert_eq!(dfsa.set_start_id(100), Err(FSAError::OutOfRangeId(100)));
+        assert_eq!(nfsa.set_start_id(100), Err(FSAError::OutOfRangeId(100)));
     }
 
     #[test]
-    fn test_dfsa_try_get_start_id_good() -> Result<()> {
-        let mut dfsa = DFSA::<char, String>::new();
-        let start = dfsa.add_state();
-        dfsa.set_start_id(start)?;
+    fn test_nfsa_try_get_start_id_good() -> Result<()> {
+        let mut nfsa = NFSA::<char, String>::new();
+        let start = nfsa.add_state();
+        nfsa.set_start_id(start)?;
 
-        dfsa.try_get_start_id()?;
+        nfsa.try_get_start_id()?;
 
         Ok(())
     }
 
     #[test]
-    fn test_dfsa_try_get_start_id_bad() {
-        let mut dfsa = DFSA::<char, String>::new();
-        let _ = dfsa.add_state();
+    fn test_nfsa_try_get_start_id_bad() {
+        let mut nfsa = NFSA::<char, String>::new();
+        let _ = nfsa.add_state();
 
-        assert_eq!(dfsa.try_get_start_id(), Err(FSAError::NoStartId));
+        assert_eq!(nfsa.try_get_start_id(), Err(FSAError::NoStartId));
     }
 
     #[test]
-    fn test_dfsa_set_state_action_good() -> Result<()> {
-        let mut dfsa = DFSA::<char, String>::new();
-        let start = dfsa.add_state();
+    fn test_nfsa_set_state_action_good() -> Result<()> {
+        let mut nfsa = NFSA::<char, String>::new();
+        let start = nfsa.add_state();
 
-        dfsa.set_state_action(start, None)?;
-        dfsa.set_state_action(start, Some(String::from("hi")))?;
+        nfsa.set_state_action(start, None)?;
+        nfsa.set_state_action(start, Some(String::from("hi")))?;
 
         Ok(())
     }
 
     #[test]
-    fn test_dfsa_set_state_action_bad() {
-        let mut dfsa = DFSA::<char, String>::new();
+    fn test_nfsa_set_state_action_bad() {
+        let mut nfsa = NFSA::<char, String>::new();
 
         assert_eq!(
-            dfsa.set_state_action(100, None),
+            nfsa.set_state_action(100, None),
             Err(FSAError::OutOfRangeId(100))
         );
     }
@@ -0,0 +1,178 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::domain::token::{Token, TokenKind};
+
+pub type CanonicalizeKindsMapping<TKind> = fn(TKind) -> TKind;
+
+// Adapts a stream of tokens by mapping each kind to a canonical one, so that kinds the lexer tells apart but the parser
+// treats the same can be collapsed without changing the lexer. `TokenRemap` is for when the parser's kinds are a
+// different type.
+pub struct CanonicalizeKinds<'iter, TKind: TokenKind> {
+    iter: &'iter mut dyn Iterator<Item = Token<TKind>>,
+    mapping: CanonicalizeKindsMapping<TKind>,
+}
+
+impl<'iter, TKind: TokenKind> CanonicalizeKinds<'iter, TKind> {
+    pub fn new(
+        iter: &'iter mut dyn Iterator<Item = Token<TKind>>,
+        mapping: CanonicalizeKindsMapping<TKind>,
+    ) -> Self {
+        Self { iter, mapping }
+    }
+}
+
+impl<'iter, TKind: TokenKind> Iterator for CanonicalizeKinds<'iter, TKind> {
+    type Item = Token<TKind>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|token| token.map_kind(self.mapping))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{
+        lexing::token_reader::TokenReader,
+        parsing::{
+            parse::Parse, parser_kind_seq::ParserKindSeqBuilder, parsing_error::ParsingError,
+        },
+        tree::{visit::Visit, Tree},
+    };
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        Word,
+        Space,
+        Newline,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    struct TreeTest {
+        tokens: Vec<Token<()>>,
+    }
+
+    impl Tree for TreeTest {
+        fn token(&self) -> &Token<()> {
+            &self.tokens[0]
+        }
+    }
+
+    impl Visit for TreeTest {
+        fn visit(&self, _: crate::tree::visit::VisitCallback<Self>) {}
+
+        fn visit_mut(&self, _: crate::tree::visit::VisitCallbackMut<Self>) {}
+    }
+
+    fn canonicalize(kind: TokenKindTest) -> TokenKindTest {
+        match kind {
+            TokenKindTest::Newline => TokenKindTest::Space,
+            _ => kind,
+        }
+    }
+
+    #[test]
+    fn test_canonicalize_kinds_into_parser() {
+        let tokens = Vec::from([
+            Token::new(
+                PathBuf::from("--"),
+                0,
+                1,
+                1,
+                String::from("a"),
+                TokenKindTest::Word,
+            ),
+            Token::new(
+                PathBuf::from("--"),
+                1,
+                1,
+                2,
+                String::from(" "),
+                TokenKindTest::Space,
+            ),
+            Token::new(
+                PathBuf::from("--"),
+                2,
+                1,
+                3,
+                String::from("b"),
+                TokenKindTest::Word,
+            ),
+            Token::new(
+                PathBuf::from("--"),
+                3,
+                1,
+                4,
+                String::from("\n"),
+                TokenKindTest::Newline,
+            ),
+            Token::new(
+                PathBuf::from("--"),
+                4,
+                2,
+                1,
+                String::from("c"),
+                TokenKindTest::Word,
+            ),
+        ]);
+        let mut tokens_iter = tokens.into_iter();
+
+        let tokens_canonical: Vec<Token<TokenKindTest>> =
+            CanonicalizeKinds::new(&mut tokens_iter, canonicalize).collect();
+
+        let mut tokens_canonical_iter = tokens_canonical.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_canonical_iter);
+
+        let parser = ParserKindSeqBuilder::<TokenKindTest, TreeTest>::default()
+            .kinds(TokenKindTest::Word)
+            .kinds(TokenKindTest::Space)
+            .kinds(TokenKindTest::Word)
+            .kinds(TokenKindTest::Space)
+            .kinds(TokenKindTest::Word)
+            .action(|tokens| TreeTest { tokens })
+            .build()
+            .unwrap();
+
+        let tree: Result<TreeTest, ParsingError<TokenKindTest>> = parser.parse(&mut token_reader);
+
+        assert_eq!(
+            tree.map(|tree| tree
+                .tokens
+                .into_iter()
+                .map(|token| token.text)
+                .collect::<Vec<String>>()),
+            Ok(Vec::from([
+                String::from("a"),
+                String::from(" "),
+                String::from("b"),
+                String::from("\n"),
+                String::from("c")
+            ]))
+        );
+    }
+}
@@ -0,0 +1,123 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::collections::HashSet;
+
+use crate::domain::token::{Token, TokenKind};
+
+// Merges each run of adjacent tokens that share a kind in `kinds` into one token with `Token::merge`. Tokens are only
+// adjacent if one starts where the other ends with no trivia between them, so a run is broken by any other token or
+// gap in the source.
+pub fn coalesce_adjacent<TTokenKind: TokenKind>(
+    tokens: Vec<Token<TTokenKind>>,
+    kinds: HashSet<TTokenKind>,
+) -> Vec<Token<TTokenKind>> {
+    let mut result: Vec<Token<TTokenKind>> = Vec::with_capacity(tokens.len());
+
+    for token in tokens {
+        match result.pop() {
+            Some(last) if is_coalescable(&last, &token, &kinds) => {
+                result.push(last.merge(token));
+            }
+            Some(last) => {
+                result.push(last);
+                result.push(token);
+            }
+            None => result.push(token),
+        }
+    }
+
+    result
+}
+
+fn is_coalescable<TTokenKind: TokenKind>(
+    first: &Token<TTokenKind>,
+    second: &Token<TTokenKind>,
+    kinds: &HashSet<TTokenKind>,
+) -> bool {
+    first.kind == second.kind
+        && kinds.contains(&first.kind)
+        && first.trailing_trivia.is_empty()
+        && second.leading_trivia.is_empty()
+        && first.end_location() == second.location
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        Identifier,
+        Whitespace,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    fn token(offset: usize, text: &str, kind: TokenKindTest) -> Token<TokenKindTest> {
+        Token::new(
+            PathBuf::from("--"),
+            offset,
+            1,
+            offset as u32 + 1,
+            String::from(text),
+            kind,
+        )
+    }
+
+    #[test]
+    fn test_coalesce_adjacent() {
+        let tokens = Vec::from([
+            token(0, " ", TokenKindTest::Whitespace),
+            token(1, "\t", TokenKindTest::Whitespace),
+            token(2, " ", TokenKindTest::Whitespace),
+            token(3, "a", TokenKindTest::Identifier),
+            token(4, "b", TokenKindTest::Identifier),
+            token(5, " ", TokenKindTest::Whitespace),
+        ]);
+
+        assert_eq!(
+            coalesce_adjacent(tokens, HashSet::from([TokenKindTest::Whitespace])),
+            Vec::from([
+                token(0, " \t ", TokenKindTest::Whitespace),
+                token(3, "a", TokenKindTest::Identifier),
+                token(4, "b", TokenKindTest::Identifier),
+                token(5, " ", TokenKindTest::Whitespace),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_coalesce_adjacent_gap() {
+        let tokens = Vec::from([
+            token(0, " ", TokenKindTest::Whitespace),
+            token(2, " ", TokenKindTest::Whitespace),
+        ]);
+
+        assert_eq!(
+            coalesce_adjacent(tokens.clone(), HashSet::from([TokenKindTest::Whitespace])),
+            tokens
+        );
+    }
+}
@@ -0,0 +1,124 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::collections::HashSet;
+
+use crate::domain::token::{Token, TokenKind};
+
+pub fn filter_kinds<'token, TTokenKind: TokenKind + 'token>(
+    tokens: impl Iterator<Item = &'token Token<TTokenKind>>,
+    keep: HashSet<TTokenKind>,
+) -> impl Iterator<Item = &'token Token<TTokenKind>> {
+    tokens.filter(move |token| keep.contains(&token.kind))
+}
+
+pub fn strip_kinds<'token, TTokenKind: TokenKind + 'token>(
+    tokens: impl Iterator<Item = &'token Token<TTokenKind>>,
+    strip: HashSet<TTokenKind>,
+) -> impl Iterator<Item = &'token Token<TTokenKind>> {
+    tokens.filter(move |token| !strip.contains(&token.kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        Word,
+        Comment,
+        Whitespace,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    fn tokens() -> Vec<Token<TokenKindTest>> {
+        Vec::from([
+            Token::new(
+                PathBuf::from("--"),
+                0,
+                1,
+                1,
+                String::from("a"),
+                TokenKindTest::Word,
+            ),
+            Token::new(
+                PathBuf::from("--"),
+                1,
+                1,
+                2,
+                String::from(" "),
+                TokenKindTest::Whitespace,
+            ),
+            Token::new(
+                PathBuf::from("--"),
+                2,
+                1,
+                3,
+                String::from("#"),
+                TokenKindTest::Comment,
+            ),
+            Token::new(
+                PathBuf::from("--"),
+                3,
+                1,
+                4,
+                String::from(" "),
+                TokenKindTest::Whitespace,
+            ),
+            Token::new(
+                PathBuf::from("--"),
+                4,
+                1,
+                5,
+                String::from("b"),
+                TokenKindTest::Word,
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_strip_kinds() {
+        let tokens = tokens();
+
+        assert_eq!(
+            strip_kinds(tokens.iter(), HashSet::from([TokenKindTest::Whitespace]))
+                .map(|token| token.text.as_str())
+                .collect::<Vec<&str>>(),
+            Vec::from(["a", "#", "b"])
+        );
+    }
+
+    #[test]
+    fn test_filter_kinds() {
+        let tokens = tokens();
+
+        assert_eq!(
+            filter_kinds(tokens.iter(), HashSet::from([TokenKindTest::Word]))
+                .map(|token| token.text.as_str())
+                .collect::<Vec<&str>>(),
+            Vec::from(["a", "b"])
+        );
+    }
+}
@@ -20,19 +20,60 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use crate::domain::token::{Token, TokenKind};
+use std::collections::VecDeque;
 
+use crate::{
+    domain::{
+        source_location::SourceLocation,
+        source_span::SourceSpan,
+        token::{Token, TokenKind},
+    },
+    messaging::message::Message,
+};
+
+// Offsets are absolute: they count every token read from the iterator since the reader was created, regardless of
+// how many tokens are still buffered. When a maximum is set with `set_max_buffered`, tokens that have already been
+// consumed are dropped from the front of the buffer once it grows past the maximum. Tokens at or after the current
+// offset are never dropped, and neither are tokens at or after an offset held with `holding`, which parsers use for
+// offsets they may seek back to or get a span from. Seeking back to an offset whose token has been dropped panics,
+// the same way seeking forward does, since it means an offset was kept without being held.
+//
+// A limit set with `set_max_consumed` bounds how many tokens parsers can eat in total, counting tokens eaten again
+// after seeking back, so that a grammar that backtracks a lot can't take too long. Once it is reached the reader acts
+// as though the tokens have run out, which makes parsers fail quickly, and `is_limit_exceeded` is set the first time a
+// token is held back. `parse_within_limit` turns that into `ParsingError::ParseLimitExceeded`. Setting the limit again
+// starts counting from zero and clears `is_limit_exceeded`, so a reader can be given a new budget.
+//
+// Parsers that recover from errors, like `ParserRecover`, record their messages here with the offset that the failed
+// parse started at. Seeking back to or before that offset drops the message, since whatever made it is being parsed
+// again or abandoned.
 pub struct TokenReader<'iter, TTokenKind: TokenKind> {
-    tokens: Vec<&'iter Token<TTokenKind>>,
+    tokens: VecDeque<&'iter Token<TTokenKind>>,
+    tokens_offset: usize,
     offset: usize,
+    max_buffered: Option<usize>,
+    max_consumed: Option<usize>,
+    consumed: usize,
+    is_limit_exceeded: bool,
+    recovered: Vec<(usize, Message)>,
+    // Offsets held by `holding`, innermost last. Each is at or after the ones before it since nested parsers start
+    // at or after where their parent did, so the first is the low-water mark for dropping tokens.
+    held: Vec<usize>,
     iter: &'iter mut dyn Iterator<Item = &'iter Token<TTokenKind>>,
 }
 
 impl<'iter, TTokenKind: TokenKind> TokenReader<'iter, TTokenKind> {
     pub fn new(iter: &'iter mut dyn Iterator<Item = &'iter Token<TTokenKind>>) -> Self {
         Self {
-            tokens: Vec::new(),
+            tokens: VecDeque::new(),
+            tokens_offset: 0,
             offset: 0,
+            max_buffered: None,
+            max_consumed: None,
+            consumed: 0,
+            is_limit_exceeded: false,
+            recovered: Vec::new(),
+            held: Vec::new(),
             iter,
         }
     }
@@ -43,20 +84,88 @@ impl<'iter, TTokenKind: TokenKind> TokenReader<'iter, TTokenKind> {
 
     pub fn seek(&mut self, offset: usize) {
         assert!(offset <= self.offset, "cannot seek forward");
+        assert!(
+            offset >= self.tokens_offset,
+            "cannot seek to a token that has been dropped from the buffer"
+        );
         self.offset = offset;
+        self.recovered.retain(|(start, _)| *start < offset);
+    }
+
+    // Runs `f` with the current offset, keeping the tokens from that offset on buffered until it returns so that `f`
+    // can seek back to it or get a span from it
+    pub fn holding<TResult>(&mut self, f: impl FnOnce(&mut Self, usize) -> TResult) -> TResult {
+        let offset = self.offset;
+
+        self.held.push(offset);
+        let result = f(self, offset);
+        self.held.pop();
+
+        self.drop_consumed_tokens();
+
+        result
+    }
+
+    pub fn max_buffered(&self) -> Option<usize> {
+        self.max_buffered
+    }
+
+    pub fn set_max_buffered(&mut self, value: Option<usize>) {
+        self.max_buffered = value;
+        self.drop_consumed_tokens();
+    }
+
+    pub fn buffered_len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn max_consumed(&self) -> Option<usize> {
+        self.max_consumed
+    }
+
+    pub fn set_max_consumed(&mut self, value: Option<usize>) {
+        self.max_consumed = value;
+        self.consumed = 0;
+        self.is_limit_exceeded = false;
+    }
+
+    pub fn consumed(&self) -> usize {
+        self.consumed
+    }
+
+    pub fn is_limit_exceeded(&self) -> bool {
+        self.is_limit_exceeded
+    }
+
+    pub fn record_recovered(&mut self, offset: usize, message: Message) {
+        self.recovered.push((offset, message));
+    }
+
+    // Returns the messages recorded by recovering parsers in the order they were recorded, and forgets them
+    pub fn take_recovered(&mut self) -> Vec<Message> {
+        self.recovered
+            .drain(..)
+            .map(|(_, message)| message)
+            .collect()
     }
 
     pub fn has_more(&mut self) -> bool {
         self.ensure_buffer_is_filled();
 
-        self.offset < self.tokens.len()
+        self.offset < self.tokens_end() && !self.hold_back_for_limit()
+    }
+
+    // The standard end of input check for parsers. It needs `&mut self` since it may pull the next token from the
+    // iterator to find out.
+    pub fn at_end(&mut self) -> bool {
+        !self.has_more()
     }
 
     pub fn peek_next(&mut self) -> Option<&Token<TTokenKind>> {
         self.ensure_buffer_is_filled();
 
-        if self.offset < self.tokens.len() {
-            Some(self.tokens[self.offset])
+        if self.offset < self.tokens_end() && !self.hold_back_for_limit() {
+            Some(self.tokens[self.offset - self.tokens_offset])
         } else {
             None
         }
@@ -65,22 +174,99 @@ impl<'iter, TTokenKind: TokenKind> TokenReader<'iter, TTokenKind> {
     pub fn eat_next(&mut self) -> Option<&Token<TTokenKind>> {
         self.ensure_buffer_is_filled();
 
-        if self.offset < self.tokens.len() {
-            let result = Some(self.tokens[self.offset]);
+        if self.offset < self.tokens_end() && !self.hold_back_for_limit() {
+            let result = self.tokens[self.offset - self.tokens_offset];
+            self.offset += 1;
+            self.consumed += 1;
+            self.drop_consumed_tokens();
+            Some(result)
+        } else {
+            None
+        }
+    }
+
+    // Consumes every token from the current offset to the end, for reporting trailing tokens after a partial parse
+    pub fn collect_remaining(&mut self) -> Vec<&'iter Token<TTokenKind>> {
+        let mut result = Vec::new();
+
+        loop {
+            self.ensure_buffer_is_filled();
+
+            if self.offset >= self.tokens_end() {
+                return result;
+            }
+
+            result.push(self.tokens[self.offset - self.tokens_offset]);
             self.offset += 1;
-            result
+            self.drop_consumed_tokens();
+        }
+    }
+
+    pub fn span_since(&self, offset: usize) -> Option<SourceSpan> {
+        assert!(
+            offset >= self.tokens_offset,
+            "cannot get span from a token that has been dropped from the buffer"
+        );
+
+        if offset < self.offset {
+            Some(SourceSpan::new(
+                self.tokens[offset - self.tokens_offset].location.clone(),
+                self.tokens[self.offset - 1 - self.tokens_offset].end_location(),
+            ))
         } else {
             None
         }
     }
 
+    // The location of the next token, or the end of the last one read once there are no more. This is `None` if no
+    // tokens have been read at all, or if the last one has been dropped from the buffer.
+    pub fn location(&mut self) -> Option<SourceLocation> {
+        if let Some(token) = self.peek_next() {
+            Some(token.location.clone())
+        } else if self.offset > self.tokens_offset {
+            Some(self.tokens[self.offset - 1 - self.tokens_offset].end_location())
+        } else {
+            None
+        }
+    }
+
+    // Called only when there is a next token, so that reaching the limit exactly at the end isn't counted
+    fn hold_back_for_limit(&mut self) -> bool {
+        let result = self.max_consumed.is_some_and(|max| self.consumed >= max);
+
+        if result {
+            self.is_limit_exceeded = true;
+        }
+
+        result
+    }
+
+    fn tokens_end(&self) -> usize {
+        self.tokens_offset + self.tokens.len()
+    }
+
     fn ensure_buffer_is_filled(&mut self) {
-        if self.offset >= self.tokens.len() {
+        if self.offset >= self.tokens_end() {
             if let Some(token) = self.iter.next() {
-                self.tokens.push(token);
+                self.tokens.push_back(token);
             }
         }
     }
+
+    fn drop_consumed_tokens(&mut self) {
+        if let Some(max_buffered) = self.max_buffered {
+            let excess = self.tokens.len().saturating_sub(max_buffered);
+            let low_water = self
+                .held
+                .first()
+                .map_or(self.offset, |held| self.offset.min(*held));
+            let consumed = low_water - self.tokens_offset;
+            let count = excess.min(consumed);
+
+            self.tokens.drain(..count);
+            self.tokens_offset += count;
+        }
+    }
 }
 
 #[cfg(test)]
@@ -231,4 +417,235 @@ mod tests {
         assert_eq!(token_reader.peek_next(), None);
         assert_eq!(token_reader.eat_next(), None);
     }
+
+    #[test]
+    fn test_token_reader_max_buffered() {
+        let tokens: Vec<Token<TokenKindTest>> = (0..100)
+            .map(|offset| {
+                Token::new(
+                    PathBuf::from("--"),
+                    offset,
+                    1,
+                    offset as u32 + 1,
+                    String::from("a"),
+                    TokenKindTest::A,
+                )
+            })
+            .collect();
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+        token_reader.set_max_buffered(Some(4));
+
+        while let Some(token) = token_reader.eat_next().cloned() {
+            assert_eq!(token.location.offset + 1, token_reader.offset());
+            assert!(token_reader.buffered_len() <= 4);
+        }
+
+        assert_eq!(token_reader.offset(), 100);
+
+        token_reader.seek(97);
+
+        assert_eq!(
+            token_reader.eat_next().map(|token| token.location.offset),
+            Some(97)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot seek to a token that has been dropped from the buffer")]
+    fn test_token_reader_max_buffered_seek_dropped() {
+        let tokens: Vec<Token<TokenKindTest>> = (0..10)
+            .map(|offset| {
+                Token::new(
+                    PathBuf::from("--"),
+                    offset,
+                    1,
+                    offset as u32 + 1,
+                    String::from("a"),
+                    TokenKindTest::A,
+                )
+            })
+            .collect();
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+        token_reader.set_max_buffered(Some(2));
+
+        while token_reader.eat_next().is_some() {}
+
+        token_reader.seek(0);
+    }
+
+    #[test]
+    fn test_token_reader_max_buffered_holding() {
+        let tokens: Vec<Token<TokenKindTest>> = (0..10)
+            .map(|offset| {
+                Token::new(
+                    PathBuf::from("--"),
+                    offset,
+                    1,
+                    offset as u32 + 1,
+                    String::from("a"),
+                    TokenKindTest::A,
+                )
+            })
+            .collect();
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+        token_reader.set_max_buffered(Some(2));
+
+        token_reader.eat_next();
+
+        token_reader.holding(|token_reader, offset| {
+            while token_reader.eat_next().is_some() {}
+
+            assert_eq!(token_reader.buffered_len(), 9);
+            assert_eq!(
+                token_reader
+                    .span_since(offset)
+                    .map(|span| span.start.offset),
+                Some(1)
+            );
+
+            token_reader.seek(offset);
+        });
+
+        assert_eq!(token_reader.offset(), 1);
+
+        while token_reader.eat_next().is_some() {}
+
+        assert_eq!(token_reader.buffered_len(), 2);
+    }
+
+    #[test]
+    fn test_token_reader_max_consumed_reset() {
+        let tokens: Vec<Token<TokenKindTest>> = (0..4)
+            .map(|offset| {
+                Token::new(
+                    PathBuf::from("--"),
+                    offset,
+                    1,
+                    offset as u32 + 1,
+                    String::from("a"),
+                    TokenKindTest::A,
+                )
+            })
+            .collect();
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+        token_reader.set_max_consumed(Some(1));
+
+        assert!(token_reader.eat_next().is_some());
+        assert!(token_reader.eat_next().is_none());
+        assert!(token_reader.is_limit_exceeded());
+
+        token_reader.set_max_consumed(None);
+
+        assert!(!token_reader.is_limit_exceeded());
+        assert!(token_reader.eat_next().is_some());
+        assert_eq!(token_reader.consumed(), 1);
+
+        token_reader.set_max_consumed(Some(1));
+
+        assert!(token_reader.eat_next().is_some());
+        assert!(token_reader.eat_next().is_none());
+        assert_eq!(token_reader.offset(), 3);
+    }
+
+    #[test]
+    fn test_token_reader_at_end() {
+        let tokens = Vec::from([
+            Token::new(
+                PathBuf::from("--"),
+                0,
+                1,
+                1,
+                String::from("a"),
+                TokenKindTest::A,
+            ),
+            Token::new(
+                PathBuf::from("--"),
+                1,
+                1,
+                2,
+                String::from("b"),
+                TokenKindTest::B,
+            ),
+        ]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert!(!token_reader.at_end());
+        token_reader.eat_next();
+        assert!(!token_reader.at_end());
+        token_reader.eat_next();
+        assert!(token_reader.at_end());
+
+        token_reader.seek(1);
+
+        assert!(!token_reader.at_end());
+    }
+
+    #[test]
+    fn test_token_reader_collect_remaining() {
+        let tokens = (0..4)
+            .map(|offset| {
+                Token::new(
+                    PathBuf::from("--"),
+                    offset,
+                    1,
+                    offset as u32 + 1,
+                    String::from("a"),
+                    TokenKindTest::A,
+                )
+            })
+            .collect::<Vec<Token<TokenKindTest>>>();
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+        token_reader.set_max_buffered(Some(1));
+
+        token_reader.eat_next();
+
+        assert_eq!(
+            token_reader.collect_remaining(),
+            tokens[1..].iter().collect::<Vec<&Token<TokenKindTest>>>()
+        );
+        assert_eq!(token_reader.offset(), 4);
+        assert!(token_reader.at_end());
+        assert_eq!(
+            token_reader.collect_remaining(),
+            Vec::<&Token<TokenKindTest>>::new()
+        );
+    }
+
+    #[test]
+    fn test_token_reader_location() {
+        let tokens = Vec::from([Token::new(
+            PathBuf::from("--"),
+            0,
+            1,
+            1,
+            String::from("ab"),
+            TokenKindTest::A,
+        )]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            token_reader.location(),
+            Some(SourceLocation::new(PathBuf::from("--"), 0, 1, 1))
+        );
+
+        token_reader.eat_next();
+
+        assert_eq!(
+            token_reader.location(),
+            Some(SourceLocation::new(PathBuf::from("--"), 2, 1, 3))
+        );
+
+        let tokens = Vec::<Token<TokenKindTest>>::new();
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(token_reader.location(), None);
+    }
 }
@@ -0,0 +1,179 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::domain::token::{Token, TokenKind};
+
+pub type TokenRemapMapping<TKindFrom, TKindTo> = fn(TKindFrom) -> TKindTo;
+
+// Adapts a stream of tokens with the lexer's token kinds into a stream with the parser's token kinds, for designs where
+// the two differ
+pub struct TokenRemap<'iter, TKindFrom: TokenKind, TKindTo: TokenKind> {
+    iter: &'iter mut dyn Iterator<Item = Token<TKindFrom>>,
+    mapping: TokenRemapMapping<TKindFrom, TKindTo>,
+}
+
+impl<'iter, TKindFrom: TokenKind, TKindTo: TokenKind> TokenRemap<'iter, TKindFrom, TKindTo> {
+    pub fn new(
+        iter: &'iter mut dyn Iterator<Item = Token<TKindFrom>>,
+        mapping: TokenRemapMapping<TKindFrom, TKindTo>,
+    ) -> Self {
+        Self { iter, mapping }
+    }
+}
+
+impl<'iter, TKindFrom: TokenKind, TKindTo: TokenKind> Iterator
+    for TokenRemap<'iter, TKindFrom, TKindTo>
+{
+    type Item = Token<TKindTo>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|token| token.map_kind(self.mapping))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{
+        lexing::token_reader::TokenReader,
+        parsing::{
+            parse::Parse, parser_kind_seq::ParserKindSeqBuilder, parsing_error::ParsingError,
+        },
+        tree::{visit::Visit, Tree},
+    };
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindLexer {
+        Identifier,
+        Keyword,
+        Integer,
+    }
+
+    impl TokenKind for TokenKindLexer {}
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindParser {
+        Word,
+        Integer,
+    }
+
+    impl TokenKind for TokenKindParser {}
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    struct TreeTest {
+        tokens: Vec<Token<()>>,
+    }
+
+    impl Tree for TreeTest {
+        fn token(&self) -> &Token<()> {
+            &self.tokens[0]
+        }
+    }
+
+    impl Visit for TreeTest {
+        fn visit(&self, _: crate::tree::visit::VisitCallback<Self>) {}
+
+        fn visit_mut(&self, _: crate::tree::visit::VisitCallbackMut<Self>) {}
+    }
+
+    fn mapping(kind: TokenKindLexer) -> TokenKindParser {
+        match kind {
+            TokenKindLexer::Identifier | TokenKindLexer::Keyword => TokenKindParser::Word,
+            TokenKindLexer::Integer => TokenKindParser::Integer,
+        }
+    }
+
+    #[test]
+    fn test_token_remap_into_parser() {
+        let tokens = Vec::from([
+            Token::new(
+                PathBuf::from("--"),
+                0,
+                1,
+                1,
+                String::from("let"),
+                TokenKindLexer::Keyword,
+            ),
+            Token::new(
+                PathBuf::from("--"),
+                3,
+                1,
+                4,
+                String::from("x"),
+                TokenKindLexer::Identifier,
+            ),
+            Token::new(
+                PathBuf::from("--"),
+                4,
+                1,
+                5,
+                String::from("1"),
+                TokenKindLexer::Integer,
+            ),
+        ]);
+        let mut tokens_iter = tokens.into_iter();
+
+        let tokens_remapped: Vec<Token<TokenKindParser>> =
+            TokenRemap::new(&mut tokens_iter, mapping).collect();
+
+        assert_eq!(
+            tokens_remapped
+                .iter()
+                .map(|token| token.kind)
+                .collect::<Vec<TokenKindParser>>(),
+            Vec::from([
+                TokenKindParser::Word,
+                TokenKindParser::Word,
+                TokenKindParser::Integer
+            ])
+        );
+
+        let mut tokens_remapped_iter = tokens_remapped.iter();
+        let mut token_reader = TokenReader::<TokenKindParser>::new(&mut tokens_remapped_iter);
+
+        let parser = ParserKindSeqBuilder::<TokenKindParser, TreeTest>::default()
+            .kinds(TokenKindParser::Word)
+            .kinds(TokenKindParser::Word)
+            .kinds(TokenKindParser::Integer)
+            .action(|tokens| TreeTest { tokens })
+            .build()
+            .unwrap();
+
+        let tree: Result<TreeTest, ParsingError<TokenKindParser>> = parser.parse(&mut token_reader);
+
+        assert_eq!(
+            tree.map(|tree| tree
+                .tokens
+                .into_iter()
+                .map(|token| token.text)
+                .collect::<Vec<String>>()),
+            Ok(Vec::from([
+                String::from("let"),
+                String::from("x"),
+                String::from("1")
+            ]))
+        );
+    }
+}
@@ -0,0 +1,131 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::domain::token::{Token, TokenKind};
+
+// Called with the kinds of the tokens before and after the candidate, if there are any
+pub type SoftKeywordPredicate<TTokenKind> = fn(Option<TTokenKind>, Option<TTokenKind>) -> bool;
+
+// Retags tokens whose text is `text` as `kind` when `predicate` accepts their neighbors
+#[derive(Clone, Debug)]
+pub struct SoftKeywordRule<TTokenKind: TokenKind> {
+    pub text: String,
+    pub predicate: SoftKeywordPredicate<TTokenKind>,
+    pub kind: TTokenKind,
+}
+
+impl<TTokenKind: TokenKind> SoftKeywordRule<TTokenKind> {
+    pub fn new(text: &str, predicate: SoftKeywordPredicate<TTokenKind>, kind: TTokenKind) -> Self {
+        Self {
+            text: String::from(text),
+            predicate,
+            kind,
+        }
+    }
+}
+
+// Resolves keywords that are only keywords in certain positions, so the lexer can lex them as identifiers. The first
+// rule that matches a token wins. Predicates see the kinds that neighbors had before the pass, so the result doesn't
+// depend on the order tokens are retagged in.
+pub fn resolve_soft_keywords<TTokenKind: TokenKind>(
+    tokens: &mut [Token<TTokenKind>],
+    rules: &[SoftKeywordRule<TTokenKind>],
+) {
+    let kinds: Vec<TTokenKind> = tokens.iter().map(|token| token.kind).collect();
+
+    for (index, token) in tokens.iter_mut().enumerate() {
+        let previous = index.checked_sub(1).map(|index| kinds[index]);
+        let next = kinds.get(index + 1).copied();
+
+        if let Some(rule) = rules
+            .iter()
+            .find(|rule| rule.text == token.text && (rule.predicate)(previous, next))
+        {
+            token.kind = rule.kind;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        Identifier,
+        KeywordAsync,
+        KeywordFn,
+        Semicolon,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    fn tokens(texts: &[(&str, TokenKindTest)]) -> Vec<Token<TokenKindTest>> {
+        texts
+            .iter()
+            .enumerate()
+            .map(|(index, (text, kind))| {
+                Token::new(
+                    PathBuf::from("--"),
+                    index,
+                    1,
+                    index as u32 + 1,
+                    String::from(*text),
+                    *kind,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve_soft_keywords() {
+        let mut tokens = tokens(&[
+            ("async", TokenKindTest::Identifier),
+            ("fn", TokenKindTest::KeywordFn),
+            ("async", TokenKindTest::Identifier),
+            (";", TokenKindTest::Semicolon),
+            ("async", TokenKindTest::Identifier),
+        ]);
+
+        resolve_soft_keywords(
+            &mut tokens,
+            &[SoftKeywordRule::new(
+                "async",
+                |_, next| next == Some(TokenKindTest::KeywordFn),
+                TokenKindTest::KeywordAsync,
+            )],
+        );
+
+        assert_eq!(
+            tokens.iter().map(|token| token.kind).collect::<Vec<_>>(),
+            Vec::from([
+                TokenKindTest::KeywordAsync,
+                TokenKindTest::KeywordFn,
+                TokenKindTest::Identifier,
+                TokenKindTest::Semicolon,
+                TokenKindTest::Identifier,
+            ])
+        );
+    }
+}
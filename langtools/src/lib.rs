@@ -21,6 +21,8 @@
 // SOFTWARE.
 
 pub mod domain;
+#[cfg(feature = "proptest")]
+pub mod fuzzing;
 pub mod language_declaration;
 pub mod lexing;
 pub mod messaging;
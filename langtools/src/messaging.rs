@@ -22,3 +22,4 @@
 
 pub mod message;
 pub mod message_context;
+pub mod render;
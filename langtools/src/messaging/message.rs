@@ -20,7 +20,9 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use crate::domain::{source_info::SourceInfo, source_location::SourceLocation, token::Token};
+use crate::domain::{
+    source_info::SourceInfo, source_location::SourceLocation, source_span::SourceSpan, token::Token,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Copy)]
 pub enum Severity {
@@ -33,6 +35,21 @@ pub enum Severity {
     InternalError,
 }
 
+impl Severity {
+    // How serious the severity is, for finding the worst message. Internal severities rank alongside their user facing
+    // counterparts, except that internal errors rank with fatal errors since lexing or parsing can't continue past
+    // them either.
+    pub fn rank(&self) -> u8 {
+        match self {
+            Severity::Note => 0,
+            Severity::Info => 1,
+            Severity::Warning | Severity::InternalWarning => 2,
+            Severity::Error => 3,
+            Severity::FatalError | Severity::InternalError => 4,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum MessageSource {
     Global,
@@ -41,11 +58,30 @@ pub enum MessageSource {
     Token { token: Token<()> },
 }
 
+// A suggested edit that replaces the text in a span. An empty span is an insertion and an empty replacement is a
+// deletion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixIt {
+    pub span: SourceSpan,
+    pub replacement: String,
+}
+
+impl FixIt {
+    pub fn new(span: SourceSpan, replacement: String) -> Self {
+        Self { span, replacement }
+    }
+
+    pub fn is_insertion(&self) -> bool {
+        self.span.start == self.span.end
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Message {
     pub source: MessageSource,
     pub severity: Severity,
     pub description: String,
+    pub fixits: Vec<FixIt>,
 }
 
 impl Message {
@@ -54,6 +90,7 @@ impl Message {
             source: MessageSource::Global,
             severity,
             description,
+            fixits: Vec::new(),
         }
     }
 
@@ -62,6 +99,7 @@ impl Message {
             source: MessageSource::Source { source },
             severity,
             description,
+            fixits: Vec::new(),
         }
     }
 
@@ -70,6 +108,7 @@ impl Message {
             source: MessageSource::Location { location },
             severity,
             description,
+            fixits: Vec::new(),
         }
     }
 
@@ -78,6 +117,12 @@ impl Message {
             source: MessageSource::Token { token },
             severity,
             description,
+            fixits: Vec::new(),
         }
     }
+
+    pub fn with_fixit(mut self, fixit: FixIt) -> Self {
+        self.fixits.push(fixit);
+        self
+    }
 }
@@ -24,10 +24,46 @@ use std::collections::HashMap;
 
 use super::message::{Message, Severity};
 
+// Maps severities to process exit codes for command line drivers. Severities without a code map to 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExitPolicy {
+    codes: HashMap<Severity, i32>,
+}
+
+impl ExitPolicy {
+    // Errors and fatal errors exit with 1 and internal errors exit with 2
+    pub fn new() -> Self {
+        Self {
+            codes: HashMap::from([
+                (Severity::Error, 1),
+                (Severity::FatalError, 1),
+                (Severity::InternalError, 2),
+            ]),
+        }
+    }
+
+    pub fn set_code(&mut self, severity: Severity, code: i32) {
+        self.codes.insert(severity, code);
+    }
+
+    pub fn get_code(&self, severity: Severity) -> i32 {
+        self.codes.get(&severity).copied().unwrap_or(0)
+    }
+}
+
+impl Default for ExitPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[readonly::make]
 pub struct MessageContext {
     pub messages: Vec<Message>,
     severity_counts: HashMap<Severity, usize>,
+    // A copy of the first message with the highest severity rank emitted so far, kept apart from `messages` since that
+    // can be changed from outside
+    first_of_max_severity: Option<Message>,
 }
 
 impl MessageContext {
@@ -35,6 +71,7 @@ impl MessageContext {
         Self {
             messages: Vec::new(),
             severity_counts: HashMap::new(),
+            first_of_max_severity: None,
         }
     }
 
@@ -48,15 +85,76 @@ impl MessageContext {
             }
         }
 
+        if self
+            .first_of_max_severity()
+            .is_none_or(|first| message.severity.rank() > first.severity.rank())
+        {
+            self.first_of_max_severity = Some(message.clone());
+        }
+
         self.messages.push(message);
     }
 
+    // Forgets all emitted messages and their counts so the context can be reused, such as between inputs in a REPL
+    pub fn clear(&mut self) {
+        self.messages.clear();
+        self.severity_counts.clear();
+        self.first_of_max_severity = None;
+    }
+
+    // The earliest message among those with the highest severity rank, for reporting something like "first error: ..."
+    pub fn first_of_max_severity(&self) -> Option<&Message> {
+        self.first_of_max_severity.as_ref()
+    }
+
     pub fn count_with_severity(&self, severity: Severity) -> usize {
         self.severity_counts
             .get(&severity)
             .copied()
             .unwrap_or(0usize)
     }
+
+    // The exit code for the messages emitted so far. This is the largest code that `policy` gives any severity that has
+    // been emitted, so a driver reports its most serious outcome, or 0 if nothing has been emitted.
+    pub fn exit_code(&self, policy: &ExitPolicy) -> i32 {
+        self.severity_counts
+            .iter()
+            .filter(|(_, count)| **count > 0)
+            .map(|(severity, _)| policy.get_code(*severity))
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn summary_message(&self) -> Option<Message> {
+        let error_count = self.count_with_severity(Severity::Error)
+            + self.count_with_severity(Severity::FatalError)
+            + self.count_with_severity(Severity::InternalError);
+        let warning_count = self.count_with_severity(Severity::Warning)
+            + self.count_with_severity(Severity::InternalWarning);
+
+        if error_count == 0 {
+            return None;
+        }
+
+        let mut description = pluralize(error_count, "error");
+
+        if warning_count > 0 {
+            description.push_str(", ");
+            description.push_str(&pluralize(warning_count, "warning"));
+        }
+
+        description.push_str(" emitted");
+
+        Some(Message::new_global(Severity::Error, description))
+    }
+}
+
+fn pluralize(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{count} {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
 }
 
 impl Default for MessageContext {
@@ -80,4 +178,133 @@ mod tests {
         assert_eq!(message_context.count_with_severity(Severity::Info), 1);
         assert_eq!(message_context.count_with_severity(Severity::Error), 0);
     }
+
+    #[test]
+    fn test_message_context_clear() {
+        let mut message_context = MessageContext::new();
+        message_context.emit(Message::new_global(Severity::Error, String::from("a")));
+        message_context.emit(Message::new_global(Severity::Warning, String::from("b")));
+
+        message_context.clear();
+
+        assert!(message_context.messages.is_empty());
+        assert_eq!(message_context.count_with_severity(Severity::Error), 0);
+        assert_eq!(message_context.count_with_severity(Severity::Warning), 0);
+        assert!(message_context.first_of_max_severity().is_none());
+        assert!(message_context.summary_message().is_none());
+    }
+
+    #[test]
+    fn test_message_context_exit_code_clean() {
+        let message_context = MessageContext::new();
+
+        assert_eq!(message_context.exit_code(&ExitPolicy::new()), 0);
+    }
+
+    #[test]
+    fn test_message_context_exit_code_warning() {
+        let mut message_context = MessageContext::new();
+        message_context.emit(Message::new_global(Severity::Warning, String::from("a")));
+        message_context.emit(Message::new_global(Severity::Note, String::from("b")));
+
+        assert_eq!(message_context.exit_code(&ExitPolicy::new()), 0);
+
+        let mut policy = ExitPolicy::new();
+        policy.set_code(Severity::Warning, 3);
+
+        assert_eq!(message_context.exit_code(&policy), 3);
+    }
+
+    #[test]
+    fn test_message_context_exit_code_error() {
+        let mut message_context = MessageContext::new();
+        message_context.emit(Message::new_global(Severity::Warning, String::from("a")));
+        message_context.emit(Message::new_global(Severity::Error, String::from("b")));
+
+        assert_eq!(message_context.exit_code(&ExitPolicy::new()), 1);
+
+        message_context.emit(Message::new_global(Severity::FatalError, String::from("c")));
+
+        assert_eq!(message_context.exit_code(&ExitPolicy::new()), 1);
+    }
+
+    #[test]
+    fn test_message_context_exit_code_internal_error() {
+        let mut message_context = MessageContext::new();
+        message_context.emit(Message::new_global(
+            Severity::InternalError,
+            String::from("a"),
+        ));
+        message_context.emit(Message::new_global(Severity::Error, String::from("b")));
+
+        assert_eq!(message_context.exit_code(&ExitPolicy::new()), 2);
+    }
+
+    #[test]
+    fn test_message_context_summary_message() {
+        let mut message_context = MessageContext::new();
+        message_context.emit(Message::new_global(Severity::Error, String::from("a")));
+        message_context.emit(Message::new_global(Severity::Warning, String::from("b")));
+        message_context.emit(Message::new_global(Severity::Error, String::from("c")));
+        message_context.emit(Message::new_global(Severity::FatalError, String::from("d")));
+        message_context.emit(Message::new_global(Severity::Note, String::from("e")));
+
+        let summary = message_context.summary_message().unwrap();
+
+        assert_eq!(summary.severity, Severity::Error);
+        assert_eq!(summary.description, "3 errors, 1 warning emitted");
+    }
+
+    #[test]
+    fn test_message_context_summary_message_clean() {
+        let mut message_context = MessageContext::new();
+
+        assert!(message_context.summary_message().is_none());
+
+        message_context.emit(Message::new_global(Severity::Warning, String::from("a")));
+
+        assert!(message_context.summary_message().is_none());
+    }
+
+    #[test]
+    fn test_message_context_first_of_max_severity() {
+        let mut message_context = MessageContext::new();
+
+        assert!(message_context.first_of_max_severity().is_none());
+
+        message_context.emit(Message::new_global(Severity::Note, String::from("a")));
+        message_context.emit(Message::new_global(Severity::Warning, String::from("b")));
+        message_context.emit(Message::new_global(Severity::Error, String::from("c")));
+        message_context.emit(Message::new_global(Severity::Warning, String::from("d")));
+        message_context.emit(Message::new_global(Severity::Error, String::from("e")));
+
+        assert_eq!(
+            message_context
+                .first_of_max_severity()
+                .map(|message| message.description.as_str()),
+            Some("c")
+        );
+
+        message_context.emit(Message::new_global(Severity::FatalError, String::from("f")));
+        message_context.emit(Message::new_global(
+            Severity::InternalError,
+            String::from("g"),
+        ));
+
+        assert_eq!(
+            message_context
+                .first_of_max_severity()
+                .map(|message| message.description.as_str()),
+            Some("f")
+        );
+
+        message_context.messages.clear();
+
+        assert_eq!(
+            message_context
+                .first_of_max_severity()
+                .map(|message| message.description.as_str()),
+            Some("f")
+        );
+    }
 }
@@ -0,0 +1,684 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::{
+    cmp::Reverse,
+    io::{self, Write},
+};
+
+use super::message::{FixIt, Message, MessageSource, Severity};
+use crate::{
+    domain::{
+        source_location::{SourceLocation, COLUMN_INITIAL},
+        source_span::SourceSpan,
+    },
+    sourcing::source_map::SourceMap,
+};
+
+// Controls how control characters in rendered source are displayed so that raw control bytes aren't written to the
+// terminal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EscapePolicy {
+    // Tabs are kept so that snippets line up with the source, everything else is escaped
+    KeepTabs,
+    All,
+    Nothing,
+}
+
+// The order that `render_to` writes messages in. Messages without a location, like global ones, come after those with
+// one when sorting. Sorting is stable so ties stay in emission order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortOrder {
+    EmissionOrder,
+    ByLocation,
+    // Most severe first
+    BySeverityThenLocation,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RenderOptions {
+    pub snippets: bool,
+    pub escape: EscapePolicy,
+    pub sort: SortOrder,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            snippets: true,
+            escape: EscapePolicy::KeepTabs,
+            sort: SortOrder::EmissionOrder,
+        }
+    }
+}
+
+pub fn escape_for_display(value: &str) -> String {
+    value
+        .chars()
+        .map(|value| escape_char(value, EscapePolicy::All))
+        .collect()
+}
+
+fn escape_char(value: char, escape: EscapePolicy) -> String {
+    if !value.is_control() || escape == EscapePolicy::Nothing {
+        return value.to_string();
+    }
+
+    match value {
+        '\t' if escape == EscapePolicy::KeepTabs => String::from("\t"),
+        '\t' => String::from("\\t"),
+        '\n' => String::from("\\n"),
+        '\r' => String::from("\\r"),
+        _ if value.is_ascii() => format!("\\x{:02x}", value as u32),
+        _ => format!("\\u{{{:x}}}", value as u32),
+    }
+}
+
+pub fn render_severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Note => "note",
+        Severity::Info => "info",
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+        Severity::FatalError => "fatal error",
+        Severity::InternalWarning => "internal warning",
+        Severity::InternalError => "internal error",
+    }
+}
+
+pub fn render_location(location: &SourceLocation) -> String {
+    format!(
+        "{}:{}:{}",
+        location.info.path.display(),
+        location.line,
+        location.column
+    )
+}
+
+// Renders a span as its start location followed by its end, leaving out the parts of the end that are the same as the
+// start, like `a.txt:1:3-7` or `a.txt:1:3-2:4`. A span should never cross sources, but if concatenated sources or
+// expansions produce one anyway then each end is rendered with its own path and a note is added, rather than pairing
+// one path with line numbers from the other.
+pub fn render_span(span: &SourceSpan) -> String {
+    if span.start.info != span.end.info {
+        format!(
+            "{}-{} (span crosses sources)",
+            render_location(&span.start),
+            render_location(&span.end)
+        )
+    } else if span.start.line == span.end.line {
+        format!("{}-{}", render_location(&span.start), span.end.column)
+    } else {
+        format!(
+            "{}-{}:{}",
+            render_location(&span.start),
+            span.end.line,
+            span.end.column
+        )
+    }
+}
+
+pub fn render_fixit(fixit: &FixIt) -> String {
+    let replacement = escape_for_display(&fixit.replacement);
+
+    if fixit.is_insertion() {
+        format!(
+            "help: insert `{replacement}` at {}",
+            render_location(&fixit.span.start)
+        )
+    } else if fixit.replacement.is_empty() {
+        format!("help: remove {}", render_span(&fixit.span))
+    } else {
+        format!(
+            "help: replace {} with `{replacement}`",
+            render_span(&fixit.span)
+        )
+    }
+}
+
+pub fn render_snippet(
+    location: &SourceLocation,
+    width: usize,
+    source_map: &SourceMap,
+    options: &RenderOptions,
+) -> Option<String> {
+    let line = source_map.try_get_line(&location.info, location.line)?;
+    let column_index = location.column.checked_sub(COLUMN_INITIAL)? as usize;
+
+    let (text, underline) = underline_line(line, column_index, column_index + width, options);
+
+    Some(format!("{text}\n{underline}"))
+}
+
+// The most lines of a span that `render_frame` shows. Longer spans show their first and last lines around a `...` row.
+pub const FRAME_MAX_LINES: usize = 5;
+
+// Renders every line that a span covers with its line number in a gutter and the span underlined on each, like:
+//
+//     2 | let a = /* x
+//       |         ^^^^
+//     3 | */ b
+//       | ^^
+//
+// A span that ends at the start of a line doesn't show that line. Returns `None` if the span crosses sources or any
+// of its lines aren't in the source map.
+pub fn render_frame(
+    span: &SourceSpan,
+    source_map: &SourceMap,
+    options: &RenderOptions,
+) -> Option<String> {
+    if span.start.info != span.end.info {
+        return None;
+    }
+
+    let first_line = span.start.line;
+    let mut last_line = span.end.line;
+    let mut end_index = span.end.column.checked_sub(COLUMN_INITIAL)? as usize;
+
+    if end_index == 0 && last_line > first_line {
+        last_line -= 1;
+        end_index = usize::MAX;
+    }
+
+    let gutter_width = last_line.to_string().len();
+    let line_count = (last_line - first_line + 1) as usize;
+    let mut rows = Vec::new();
+
+    for (index, line_number) in (first_line..=last_line).enumerate() {
+        if line_count > FRAME_MAX_LINES {
+            let head = FRAME_MAX_LINES / 2;
+
+            if index == head {
+                rows.push(String::from("..."));
+            }
+
+            if index >= head && index < line_count - (FRAME_MAX_LINES - head) {
+                continue;
+            }
+        }
+
+        let line = source_map.try_get_line(&span.start.info, line_number)?;
+
+        let start_index = if line_number == first_line {
+            span.start.column.checked_sub(COLUMN_INITIAL)? as usize
+        } else {
+            0
+        };
+
+        let (text, underline) = underline_line(
+            line,
+            start_index,
+            if line_number == last_line {
+                end_index
+            } else {
+                usize::MAX
+            },
+            options,
+        );
+
+        if text.is_empty() {
+            rows.push(format!("{line_number:>gutter_width$} |"));
+        } else {
+            rows.push(format!("{line_number:>gutter_width$} | {text}"));
+        }
+
+        rows.push(format!("{:gutter_width$} | {underline}", ""));
+    }
+
+    Some(rows.join("\n"))
+}
+
+// Escapes a line of source and makes the line to go under it, with carets under the characters from `start_index` up
+// to `end_index`. There is always at least one caret so that empty spans and empty lines still point somewhere.
+fn underline_line(
+    line: &str,
+    start_index: usize,
+    end_index: usize,
+    options: &RenderOptions,
+) -> (String, String) {
+    let mut text = String::new();
+    let mut padding = String::new();
+    let mut caret_count = 0;
+
+    for (index, value) in line.chars().enumerate() {
+        let escaped = escape_char(value, options.escape);
+        let escaped_width = escaped.chars().count();
+
+        text.push_str(&escaped);
+
+        if index < start_index {
+            // Tabs are kept in the padding so the caret lines up no matter how wide the terminal renders them
+            if escaped == "\t" {
+                padding.push('\t');
+            } else {
+                padding.push_str(&" ".repeat(escaped_width));
+            }
+        } else if index < end_index {
+            caret_count += escaped_width;
+        }
+    }
+
+    padding.push_str(&"^".repeat(caret_count.max(1)));
+
+    (text, padding)
+}
+
+pub fn render_message(
+    message: &Message,
+    source_map: &SourceMap,
+    options: &RenderOptions,
+) -> String {
+    let mut result = Vec::new();
+
+    write_message(message, source_map, &mut result, options)
+        .expect("writing to a vector should not fail");
+
+    String::from_utf8(result).expect("rendered messages should be valid UTF-8")
+}
+
+// Streams messages one at a time so that large numbers of diagnostics don't need to be built up into a single string
+pub fn render_to<W: Write>(
+    messages: &[&Message],
+    source_map: &SourceMap,
+    w: &mut W,
+    options: &RenderOptions,
+) -> io::Result<()> {
+    let mut messages = messages.to_vec();
+
+    match options.sort {
+        SortOrder::EmissionOrder => {}
+        SortOrder::ByLocation => messages.sort_by_key(|message| location_sort_key(message)),
+        SortOrder::BySeverityThenLocation => messages
+            .sort_by_key(|message| (Reverse(message.severity.rank()), location_sort_key(message))),
+    }
+
+    for message in messages {
+        write_message(message, source_map, w, options)?;
+        writeln!(w)?;
+    }
+
+    Ok(())
+}
+
+fn location_sort_key(message: &Message) -> (bool, Option<&SourceLocation>) {
+    let location = match &message.source {
+        MessageSource::Global | MessageSource::Source { .. } => None,
+        MessageSource::Location { location } => Some(location),
+        MessageSource::Token { token } => Some(&token.location),
+    };
+
+    (location.is_none(), location)
+}
+
+fn write_message<W: Write>(
+    message: &Message,
+    source_map: &SourceMap,
+    w: &mut W,
+    options: &RenderOptions,
+) -> io::Result<()> {
+    let snippet_location = match &message.source {
+        MessageSource::Global => None,
+        MessageSource::Source { source } => {
+            write!(w, "{}: ", source.path.display())?;
+            None
+        }
+        MessageSource::Location { location } => {
+            write!(w, "{}: ", render_location(location))?;
+            Some((location, 1))
+        }
+        MessageSource::Token { token } => {
+            write!(w, "{}: ", render_location(&token.location))?;
+            Some((&token.location, token.text.chars().count()))
+        }
+    };
+
+    write!(
+        w,
+        "{}: {}",
+        render_severity(message.severity),
+        message.description
+    )?;
+
+    if options.snippets {
+        if let Some(snippet) = snippet_location
+            .and_then(|(location, width)| render_snippet(location, width, source_map, options))
+        {
+            write!(w, "\n{snippet}")?;
+        }
+    }
+
+    for fixit in &message.fixits {
+        write!(w, "\n{}", render_fixit(fixit))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::domain::{source_info::SourceInfo, token::Token};
+
+    use super::*;
+
+    #[test]
+    fn test_render_location() {
+        assert_eq!(
+            render_location(&SourceLocation::new(PathBuf::from("a.txt"), 4, 2, 3)),
+            "a.txt:2:3"
+        );
+    }
+
+    #[test]
+    fn test_render_snippet() {
+        let mut source_map = SourceMap::new();
+        source_map.add(&mut SourceInfo::new(PathBuf::from("--")), "ab\n\tcd ef\n");
+
+        assert_eq!(
+            render_snippet(
+                &SourceLocation::new(PathBuf::from("--"), 6, 2, 5),
+                2,
+                &source_map,
+                &RenderOptions::default()
+            ),
+            Some(String::from("\tcd ef\n\t   ^^"))
+        );
+        assert_eq!(
+            render_snippet(
+                &SourceLocation::new(PathBuf::from("--"), 3, 2, 1),
+                100,
+                &source_map,
+                &RenderOptions::default()
+            ),
+            Some(String::from("\tcd ef\n^^^^^^"))
+        );
+        assert_eq!(
+            render_snippet(
+                &SourceLocation::new(PathBuf::from("--"), 9, 3, 1),
+                1,
+                &source_map,
+                &RenderOptions::default()
+            ),
+            Some(String::from("\n^"))
+        );
+        assert_eq!(
+            render_snippet(
+                &SourceLocation::new(PathBuf::from("other"), 0, 1, 1),
+                1,
+                &source_map,
+                &RenderOptions::default()
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_render_message() {
+        let mut source_map = SourceMap::new();
+        source_map.add(&mut SourceInfo::new(PathBuf::from("a.txt")), "let x\n");
+
+        let message = Message::new_token(
+            Token::new(PathBuf::from("a.txt"), 4, 1, 5, String::from("x"), ()),
+            Severity::Warning,
+            String::from("unused variable"),
+        );
+
+        assert_eq!(
+            render_message(&message, &source_map, &RenderOptions::default()),
+            "a.txt:1:5: warning: unused variable\nlet x\n    ^"
+        );
+        assert_eq!(
+            render_message(
+                &message,
+                &source_map,
+                &RenderOptions {
+                    snippets: false,
+                    ..RenderOptions::default()
+                }
+            ),
+            "a.txt:1:5: warning: unused variable"
+        );
+    }
+
+    #[test]
+    fn test_render_to() {
+        let mut source_map = SourceMap::new();
+        source_map.add(&mut SourceInfo::new(PathBuf::from("a.txt")), "ab\n");
+
+        let messages = Vec::from([
+            Message::new_location(
+                SourceLocation::new(PathBuf::from("a.txt"), 1, 1, 2),
+                Severity::Error,
+                String::from("bad"),
+            ),
+            Message::new_global(Severity::Note, String::from("done")),
+        ]);
+
+        let mut buffer = Vec::<u8>::new();
+
+        render_to(
+            &messages.iter().collect::<Vec<&Message>>(),
+            &source_map,
+            &mut buffer,
+            &RenderOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            buffer,
+            b"a.txt:1:2: error: bad\nab\n ^\nnote: done\n".to_vec()
+        );
+    }
+
+    fn render_sorted(messages: &[Message], sort: SortOrder) -> String {
+        let mut buffer = Vec::<u8>::new();
+
+        render_to(
+            &messages.iter().collect::<Vec<&Message>>(),
+            &SourceMap::new(),
+            &mut buffer,
+            &RenderOptions {
+                sort,
+                ..RenderOptions::default()
+            },
+        )
+        .unwrap();
+
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_render_to_sort_order() {
+        let messages = Vec::from([
+            Message::new_location(
+                SourceLocation::new(PathBuf::from("a.txt"), 4, 2, 1),
+                Severity::Warning,
+                String::from("w2"),
+            ),
+            Message::new_global(Severity::Error, String::from("g")),
+            Message::new_location(
+                SourceLocation::new(PathBuf::from("a.txt"), 2, 1, 3),
+                Severity::Error,
+                String::from("e1"),
+            ),
+            Message::new_location(
+                SourceLocation::new(PathBuf::from("a.txt"), 0, 1, 1),
+                Severity::Warning,
+                String::from("w1"),
+            ),
+        ]);
+
+        assert_eq!(
+            render_sorted(&messages, SortOrder::EmissionOrder),
+            "a.txt:2:1: warning: w2\nerror: g\na.txt:1:3: error: e1\na.txt:1:1: warning: w1\n"
+        );
+        assert_eq!(
+            render_sorted(&messages, SortOrder::ByLocation),
+            "a.txt:1:1: warning: w1\na.txt:1:3: error: e1\na.txt:2:1: warning: w2\nerror: g\n"
+        );
+        assert_eq!(
+            render_sorted(&messages, SortOrder::BySeverityThenLocation),
+            "a.txt:1:3: error: e1\nerror: g\na.txt:1:1: warning: w1\na.txt:2:1: warning: w2\n"
+        );
+    }
+
+    #[test]
+    fn test_render_frame() {
+        let mut source_map = SourceMap::new();
+        source_map.add(
+            &mut SourceInfo::new(PathBuf::from("a.txt")),
+            "x\nlet a = /* b\n\nc */ d\n",
+        );
+
+        let span = SourceSpan::new(
+            SourceLocation::new(PathBuf::from("a.txt"), 10, 2, 9),
+            SourceLocation::new(PathBuf::from("a.txt"), 20, 4, 5),
+        );
+
+        assert_eq!(
+            render_frame(&span, &source_map, &RenderOptions::default()).unwrap(),
+            "2 | let a = /* b\n  |         ^^^^\n3 |\n  | ^\n4 | c */ d\n  | ^^^^"
+        );
+    }
+
+    #[test]
+    fn test_render_frame_truncated() {
+        let mut source_map = SourceMap::new();
+        source_map.add(
+            &mut SourceInfo::new(PathBuf::from("a.txt")),
+            "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n",
+        );
+
+        // Ends at the start of line 11, so line 10 is the last one shown
+        let span = SourceSpan::new(
+            SourceLocation::new(PathBuf::from("a.txt"), 0, 1, 1),
+            SourceLocation::new(PathBuf::from("a.txt"), 21, 11, 1),
+        );
+
+        assert_eq!(
+            render_frame(&span, &source_map, &RenderOptions::default()).unwrap(),
+            " 1 | 1\n   | ^\n 2 | 2\n   | ^\n...\n 8 | 8\n   | ^\n 9 | 9\n   | ^\n10 | 10\n   | ^^"
+        );
+    }
+
+    #[test]
+    fn test_escape_for_display() {
+        assert_eq!(escape_for_display("a\tb\0\n\u{85}"), "a\\tb\\x00\\n\\u{85}");
+    }
+
+    #[test]
+    fn test_render_message_control_characters() {
+        let mut source_map = SourceMap::new();
+        source_map.add(&mut SourceInfo::new(PathBuf::from("a.txt")), "a\t\0b\n");
+
+        let message = Message::new_token(
+            Token::new(PathBuf::from("a.txt"), 1, 1, 2, String::from("\t\0"), ()),
+            Severity::Error,
+            String::from("bad"),
+        );
+
+        assert_eq!(
+            render_message(&message, &source_map, &RenderOptions::default()),
+            "a.txt:1:2: error: bad\na\t\\x00b\n ^^^^^"
+        );
+        assert_eq!(
+            render_message(
+                &message,
+                &source_map,
+                &RenderOptions {
+                    escape: EscapePolicy::All,
+                    ..RenderOptions::default()
+                }
+            ),
+            "a.txt:1:2: error: bad\na\\t\\x00b\n ^^^^^^"
+        );
+    }
+
+    #[test]
+    fn test_render_span() {
+        let start = SourceLocation::new(PathBuf::from("a.txt"), 2, 1, 3);
+
+        assert_eq!(
+            render_span(&SourceSpan::new(
+                start.clone(),
+                SourceLocation::new(PathBuf::from("a.txt"), 6, 1, 7)
+            )),
+            "a.txt:1:3-7"
+        );
+        assert_eq!(
+            render_span(&SourceSpan::new(
+                start,
+                SourceLocation::new(PathBuf::from("a.txt"), 12, 2, 4)
+            )),
+            "a.txt:1:3-2:4"
+        );
+    }
+
+    #[test]
+    fn test_render_span_crosses_sources() {
+        assert_eq!(
+            render_span(&SourceSpan::new(
+                SourceLocation::new(PathBuf::from("a.txt"), 2, 1, 3),
+                SourceLocation::new(PathBuf::from("b.txt"), 4, 1, 5)
+            )),
+            "a.txt:1:3-b.txt:1:5 (span crosses sources)"
+        );
+    }
+
+    #[test]
+    fn test_render_message_fixit() {
+        let mut source_map = SourceMap::new();
+        source_map.add(&mut SourceInfo::new(PathBuf::from("a.txt")), "let x\n");
+
+        let end = SourceLocation::new(PathBuf::from("a.txt"), 5, 1, 6);
+
+        let message =
+            Message::new_location(end.clone(), Severity::Error, String::from("expected `;`"))
+                .with_fixit(FixIt::new(
+                    SourceSpan::new(end.clone(), end),
+                    String::from(";"),
+                ));
+
+        assert_eq!(
+            render_message(&message, &source_map, &RenderOptions::default()),
+            "a.txt:1:6: error: expected `;`\nlet x\n     ^\nhelp: insert `;` at a.txt:1:6"
+        );
+    }
+
+    #[test]
+    fn test_render_fixit_replace_and_remove() {
+        let span = SourceSpan::new(
+            SourceLocation::new(PathBuf::from("a.txt"), 0, 1, 1),
+            SourceLocation::new(PathBuf::from("a.txt"), 3, 1, 4),
+        );
+
+        assert_eq!(
+            render_fixit(&FixIt::new(span.clone(), String::from("var"))),
+            "help: replace a.txt:1:1-4 with `var`"
+        );
+        assert_eq!(
+            render_fixit(&FixIt::new(span, String::new())),
+            "help: remove a.txt:1:1-4"
+        );
+    }
+}
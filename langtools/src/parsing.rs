@@ -20,8 +20,83 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+pub mod operator_table;
 pub mod parse;
+pub mod parser_anchor;
+pub mod parser_any_of_keeping;
+pub mod parser_bracketed_list;
 pub mod parser_choice;
+pub mod parser_factored;
+pub mod parser_followed_by;
+pub mod parser_guarded;
+pub mod parser_kind_seq;
+pub mod parser_map_err;
+pub mod parser_or;
+pub mod parser_pratt;
+pub mod parser_recover;
+pub mod parser_repeat_bounded;
+pub mod parser_repetition;
 pub mod parser_sequential;
 pub mod parser_token;
+pub mod parser_until;
+pub mod parser_with_expected;
 pub mod parsing_error;
+#[cfg(test)]
+mod testing;
+
+use self::parse::Parse;
+use crate::{
+    domain::token::{Token, TokenKind},
+    lexing::{lexer::Lexer, token_reader::TokenReader},
+    messaging::{
+        message::{Message, Severity},
+        message_context::MessageContext,
+    },
+    sourcing::{source_new_string, source_reader::SourceReader},
+    tree::Tree,
+};
+
+// Runs the whole pipeline over a string: lexing, then parsing with `root`. A tree is only returned if `root` consumed
+// every token. Lexing and parsing errors are returned as messages.
+pub fn parse_str<TTokenKind: TokenKind, TTree: Tree>(
+    text: &str,
+    lexer: &Lexer<TTokenKind>,
+    root: &dyn Parse<TTokenKind, TTree>,
+) -> (Option<TTree>, MessageContext) {
+    let mut message_context = MessageContext::new();
+
+    let mut source = source_new_string(text);
+    let mut source_reader = SourceReader::new(&mut source);
+    source_reader
+        .enable_buffering()
+        .expect("buffering should not be enabled on a new source reader");
+
+    let tokens: Vec<Token<TTokenKind>> = lexer
+        .lex(&mut source_reader, &mut message_context)
+        .collect();
+
+    let mut tokens_iter = tokens.iter();
+    let mut token_reader = TokenReader::new(&mut tokens_iter);
+
+    let tree = match root.parse(&mut token_reader) {
+        Ok(tree) => match token_reader.peek_next() {
+            Some(token) => {
+                message_context.emit(Message::new_token(
+                    token.to_kindless(),
+                    Severity::Error,
+                    String::from("expected end of source"),
+                ));
+
+                None
+            }
+            None => Some(tree),
+        },
+        Err(err) => {
+            message_context.emit(err.to_message(None));
+
+            None
+        }
+    };
+
+    (tree, message_context)
+}
@@ -0,0 +1,227 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    domain::token::{Token, TokenKind},
+    tree::Tree,
+};
+
+pub type BindingPower = u8;
+
+pub type OperatorUnaryAction<TTree> = fn(Token<()>, TTree) -> TTree;
+pub type OperatorBinaryAction<TTree> = fn(Token<()>, TTree, TTree) -> TTree;
+
+pub struct OperatorPrefix<TTree> {
+    pub binding_power: BindingPower,
+    pub action: OperatorUnaryAction<TTree>,
+}
+
+// Binding powers decide associativity: a left power lower than the right one makes the operator left associative, and
+// the other way around makes it right associative
+pub struct OperatorInfix<TTree> {
+    pub left_binding_power: BindingPower,
+    pub right_binding_power: BindingPower,
+    pub action: OperatorBinaryAction<TTree>,
+}
+
+pub struct OperatorPostfix<TTree> {
+    pub binding_power: BindingPower,
+    pub action: OperatorUnaryAction<TTree>,
+}
+
+// The operators for a Pratt parser, keyed by token kind. A token kind can be both a prefix and an infix operator, like
+// `-`, since prefix operators are only looked for where an operand is expected. Registering a kind again for the same
+// fixity replaces the earlier registration. A kind can't be both an infix and a postfix operator though, since both are
+// looked for after an operand, which `ParserPrattBuilder::build` reports as a conflict.
+pub struct OperatorTable<TTokenKind: TokenKind, TTree: Tree> {
+    prefix: HashMap<TTokenKind, OperatorPrefix<TTree>>,
+    infix: HashMap<TTokenKind, OperatorInfix<TTree>>,
+    postfix: HashMap<TTokenKind, OperatorPostfix<TTree>>,
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> OperatorTable<TTokenKind, TTree> {
+    pub fn new() -> Self {
+        Self {
+            prefix: HashMap::new(),
+            infix: HashMap::new(),
+            postfix: HashMap::new(),
+        }
+    }
+
+    pub fn add_prefix(
+        &mut self,
+        token_kind: TTokenKind,
+        binding_power: BindingPower,
+        action: OperatorUnaryAction<TTree>,
+    ) -> &mut Self {
+        self.prefix.insert(
+            token_kind,
+            OperatorPrefix {
+                binding_power,
+                action,
+            },
+        );
+        self
+    }
+
+    pub fn add_infix(
+        &mut self,
+        token_kind: TTokenKind,
+        left_binding_power: BindingPower,
+        right_binding_power: BindingPower,
+        action: OperatorBinaryAction<TTree>,
+    ) -> &mut Self {
+        self.infix.insert(
+            token_kind,
+            OperatorInfix {
+                left_binding_power,
+                right_binding_power,
+                action,
+            },
+        );
+        self
+    }
+
+    pub fn add_postfix(
+        &mut self,
+        token_kind: TTokenKind,
+        binding_power: BindingPower,
+        action: OperatorUnaryAction<TTree>,
+    ) -> &mut Self {
+        self.postfix.insert(
+            token_kind,
+            OperatorPostfix {
+                binding_power,
+                action,
+            },
+        );
+        self
+    }
+
+    pub fn try_get_prefix(&self, token_kind: &TTokenKind) -> Option<&OperatorPrefix<TTree>> {
+        self.prefix.get(token_kind)
+    }
+
+    pub fn try_get_infix(&self, token_kind: &TTokenKind) -> Option<&OperatorInfix<TTree>> {
+        self.infix.get(token_kind)
+    }
+
+    pub fn try_get_postfix(&self, token_kind: &TTokenKind) -> Option<&OperatorPostfix<TTree>> {
+        self.postfix.get(token_kind)
+    }
+
+    pub fn prefix_token_kinds(&self) -> HashSet<TTokenKind> {
+        self.prefix.keys().copied().collect()
+    }
+
+    pub fn token_kinds(&self) -> HashSet<TTokenKind> {
+        self.prefix
+            .keys()
+            .chain(self.infix.keys())
+            .chain(self.postfix.keys())
+            .copied()
+            .collect()
+    }
+
+    // The first token kind registered as both an infix and a postfix operator, if any
+    pub fn find_conflict(&self) -> Option<TTokenKind> {
+        self.infix
+            .keys()
+            .find(|token_kind| self.postfix.contains_key(token_kind))
+            .copied()
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Default for OperatorTable<TTokenKind, TTree> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tree::visit::Visit;
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        Minus,
+        Bang,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    struct TreeTest {
+        token: Token<()>,
+    }
+
+    impl Tree for TreeTest {
+        fn token(&self) -> &Token<()> {
+            &self.token
+        }
+    }
+
+    impl Visit for TreeTest {
+        fn visit(&self, _: crate::tree::visit::VisitCallback<Self>) {}
+
+        fn visit_mut(&self, _: crate::tree::visit::VisitCallbackMut<Self>) {}
+    }
+
+    #[test]
+    fn test_operator_table_prefix_and_infix() {
+        let mut table = OperatorTable::<TokenKindTest, TreeTest>::new();
+
+        table
+            .add_prefix(TokenKindTest::Minus, 5, |token, _| TreeTest { token })
+            .add_infix(TokenKindTest::Minus, 1, 2, |token, _, _| TreeTest { token });
+
+        assert_eq!(
+            table
+                .try_get_prefix(&TokenKindTest::Minus)
+                .map(|prefix| prefix.binding_power),
+            Some(5)
+        );
+        assert_eq!(
+            table
+                .try_get_infix(&TokenKindTest::Minus)
+                .map(|infix| (infix.left_binding_power, infix.right_binding_power)),
+            Some((1, 2))
+        );
+        assert!(table.try_get_postfix(&TokenKindTest::Minus).is_none());
+        assert_eq!(table.find_conflict(), None);
+        assert_eq!(table.token_kinds(), HashSet::from([TokenKindTest::Minus]));
+    }
+
+    #[test]
+    fn test_operator_table_infix_postfix_conflict() {
+        let mut table = OperatorTable::<TokenKindTest, TreeTest>::new();
+
+        table
+            .add_infix(TokenKindTest::Bang, 1, 2, |token, _, _| TreeTest { token })
+            .add_postfix(TokenKindTest::Bang, 7, |token, _| TreeTest { token });
+
+        assert_eq!(table.find_conflict(), Some(TokenKindTest::Bang));
+    }
+}
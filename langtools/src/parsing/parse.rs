@@ -23,7 +23,12 @@
 use std::collections::HashSet;
 
 use super::parsing_error::ParsingError;
-use crate::{domain::token::TokenKind, lexing::token_reader::TokenReader, tree::Tree};
+use crate::{
+    domain::token::TokenKind,
+    lexing::token_reader::TokenReader,
+    messaging::message_context::MessageContext,
+    tree::{ErrorTree, Tree},
+};
 
 pub trait Parse<TTokenKind: TokenKind, TTree: Tree> {
     fn parse(
@@ -31,6 +36,16 @@ pub trait Parse<TTokenKind: TokenKind, TTree: Tree> {
         token_reader: &mut TokenReader<TTokenKind>,
     ) -> Result<TTree, ParsingError<TTokenKind>>;
 
+    // Checks whether the input matches without keeping a tree. This must consume the same tokens and return the same
+    // errors as `parse`. Combinators override it to call `matches` on their children so that no trees are built under
+    // them, but leaves still build theirs unless they override it too.
+    fn matches(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<(), ParsingError<TTokenKind>> {
+        self.parse(token_reader).map(|_| ())
+    }
+
     fn expected_tokens_unsafe(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>>;
 
     fn expected_tokens(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
@@ -42,4 +57,345 @@ pub trait Parse<TTokenKind: TokenKind, TTree: Tree> {
             Ok(result)
         }
     }
+
+    // Parsers that are built out of other parsers return them here so that the grammar can be walked
+    fn sub_parsers(&self) -> Vec<&dyn Parse<TTokenKind, TTree>> {
+        Vec::new()
+    }
+
+    // Whether this parser might succeed without consuming any tokens. This is a conservative guess used by
+    // `check_productive`: parsers that can't tell, including any defined outside this crate that don't override it,
+    // are assumed to always consume something.
+    fn can_match_empty(&self) -> bool {
+        false
+    }
+
+    // The sub parsers that this parser runs in a loop, for `check_productive`
+    fn repeated_parsers(&self) -> Vec<&dyn Parse<TTokenKind, TTree>> {
+        Vec::new()
+    }
+
+    // Every token kind that this parser matches itself, not counting its sub parsers
+    fn own_token_kinds(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+        if self.sub_parsers().is_empty() {
+            self.expected_tokens()
+        } else {
+            Ok(HashSet::new())
+        }
+    }
+}
+
+// Unions the expected tokens of several parsers, for combinators that could start with any of them
+pub fn union_expected<TTokenKind: TokenKind, TTree: Tree>(
+    parsers: &[&dyn Parse<TTokenKind, TTree>],
+) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+    let mut result = HashSet::new();
+
+    for parser in parsers {
+        result.extend(parser.expected_tokens()?);
+    }
+
+    Ok(result)
+}
+
+// Collects the token kinds matched anywhere in the grammar under `root`. Diffing this against the token kinds that a
+// lexer can emit shows which kinds the grammar never accepts.
+pub fn reachable_token_kinds<TTokenKind: TokenKind, TTree: Tree>(
+    root: &dyn Parse<TTokenKind, TTree>,
+) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+    let mut result = HashSet::new();
+    let mut stack = Vec::from([root]);
+
+    while let Some(parser) = stack.pop() {
+        result.extend(parser.own_token_kinds()?);
+        stack.extend(parser.sub_parsers());
+    }
+
+    Ok(result)
+}
+
+// Checks that no parser under `root` repeats a parser that can succeed without consuming anything, like a repetition
+// of an optional token. Such a loop would never end, so the repeating parsers stop at the first empty match instead,
+// which quietly cuts the repetition short. Finding them when the grammar is built is easier than debugging that. This
+// relies on `can_match_empty`, so it can miss loops over parsers that don't report it, and it can flag a loop whose
+// item only matches empty input in contexts that never arise.
+pub fn check_productive<TTokenKind: TokenKind, TTree: Tree>(
+    root: &dyn Parse<TTokenKind, TTree>,
+) -> Result<(), ParsingError<TTokenKind>> {
+    let mut stack = Vec::from([root]);
+
+    while let Some(parser) = stack.pop() {
+        if parser
+            .repeated_parsers()
+            .iter()
+            .any(|repeated| repeated.can_match_empty())
+        {
+            return Err(ParsingError::UnproductiveRepetition {
+                expected_token_kinds: parser.expected_tokens()?,
+            });
+        }
+
+        stack.extend(parser.sub_parsers());
+    }
+
+    Ok(())
+}
+
+// Parses with `root` and, if the token reader held tokens back because its consumption limit was reached, reports that
+// instead of whatever the parse ended with, since any other error or tree would be an artifact of the limit
+pub fn parse_within_limit<TTokenKind: TokenKind, TTree: Tree>(
+    root: &dyn Parse<TTokenKind, TTree>,
+    token_reader: &mut TokenReader<TTokenKind>,
+) -> Result<TTree, ParsingError<TTokenKind>> {
+    let result = root.parse(token_reader);
+
+    match token_reader.max_consumed() {
+        Some(limit) if token_reader.is_limit_exceeded() => {
+            Err(ParsingError::ParseLimitExceeded { limit })
+        }
+        _ => result,
+    }
+}
+
+// Parses with `root` for a best effort tree, emitting every error into `message_context` instead of returning it. Errors
+// that `ParserRecover` recovered from become error nodes inside the tree. If `root` itself fails then the whole tree is
+// an error node, unless there were no tokens to put it at.
+pub fn parse_recovering<TTokenKind: TokenKind, TTree: ErrorTree>(
+    root: &dyn Parse<TTokenKind, TTree>,
+    token_reader: &mut TokenReader<TTokenKind>,
+    message_context: &mut MessageContext,
+) -> Option<TTree> {
+    let location = token_reader.location();
+    let result = root.parse(token_reader);
+
+    for message in token_reader.take_recovered() {
+        message_context.emit(message);
+    }
+
+    match result {
+        Ok(tree) => Some(tree),
+        Err(err) => {
+            message_context.emit(err.to_message(location.clone()));
+            location.map(TTree::error_node)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{
+        domain::token::Token,
+        parsing::{
+            parser_choice::ParserChoiceBuilder,
+            parser_kind_seq::ParserKindSeqBuilder,
+            parser_or::ParserOrBuilder,
+            parser_repetition::ParserRepetitionBuilder,
+            parser_sequential::ParserSequentialBuilder,
+            testing::{parser_token, tokens},
+        },
+        tree::visit::Visit,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        A,
+        B,
+        C,
+        D,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    struct TreeTest {
+        token: Token<()>,
+    }
+
+    impl Tree for TreeTest {
+        fn token(&self) -> &Token<()> {
+            &self.token
+        }
+    }
+
+    impl Visit for TreeTest {
+        fn visit(&self, _: crate::tree::visit::VisitCallback<Self>) {}
+
+        fn visit_mut(&self, _: crate::tree::visit::VisitCallbackMut<Self>) {}
+    }
+
+    #[test]
+    fn test_union_expected() {
+        let a = parser_token(TokenKindTest::A, |token| TreeTest { token });
+        let b = parser_token(TokenKindTest::B, |token| TreeTest { token });
+
+        assert_eq!(
+            union_expected(&[a.as_ref(), b.as_ref()]),
+            Ok(HashSet::from([TokenKindTest::A, TokenKindTest::B]))
+        );
+        assert_eq!(
+            union_expected::<TokenKindTest, TreeTest>(&[]),
+            Ok(HashSet::new())
+        );
+    }
+
+    #[test]
+    fn test_reachable_token_kinds() {
+        let root = ParserChoiceBuilder::<TokenKindTest, TreeTest>::default()
+            .choices(Box::new(
+                ParserSequentialBuilder::<TokenKindTest, TreeTest>::default()
+                    .sequence(parser_token(TokenKindTest::A, |token| TreeTest { token }))
+                    .sequence(parser_token(TokenKindTest::B, |token| TreeTest { token }))
+                    .action(|token, _| TreeTest { token })
+                    .build()
+                    .unwrap(),
+            ))
+            .choices(Box::new(
+                ParserKindSeqBuilder::<TokenKindTest, TreeTest>::default()
+                    .kinds(TokenKindTest::A)
+                    .kinds(TokenKindTest::C)
+                    .action(|tokens| TreeTest {
+                        token: tokens[0].clone(),
+                    })
+                    .build()
+                    .unwrap(),
+            ))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            root.expected_tokens(),
+            Ok(HashSet::from([TokenKindTest::A]))
+        );
+
+        let reachable = reachable_token_kinds(&root).unwrap();
+
+        assert_eq!(
+            reachable,
+            HashSet::from([TokenKindTest::A, TokenKindTest::B, TokenKindTest::C])
+        );
+        assert!(!reachable.contains(&TokenKindTest::D));
+    }
+
+    fn parser_repetition(
+        item: Box<dyn Parse<TokenKindTest, TreeTest>>,
+    ) -> Box<dyn Parse<TokenKindTest, TreeTest>> {
+        Box::new(
+            ParserRepetitionBuilder::<TokenKindTest, TreeTest>::default()
+                .item(item)
+                .action(|_, span| TreeTest {
+                    token: Token::new_from_location(span.unwrap().start, String::new(), ()),
+                })
+                .build()
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_check_productive() {
+        let root = ParserSequentialBuilder::<TokenKindTest, TreeTest>::default()
+            .sequence(parser_token(TokenKindTest::A, |token| TreeTest { token }))
+            .sequence(parser_repetition(parser_token(TokenKindTest::B, |token| {
+                TreeTest { token }
+            })))
+            .action(|token, _| TreeTest { token })
+            .build()
+            .unwrap();
+
+        assert_eq!(check_productive(&root), Ok(()));
+    }
+
+    #[test]
+    fn test_check_productive_many_optional() {
+        // many(optional(B))
+        let root = ParserSequentialBuilder::<TokenKindTest, TreeTest>::default()
+            .sequence(parser_token(TokenKindTest::A, |token| TreeTest { token }))
+            .sequence(parser_repetition(Box::new(
+                ParserOrBuilder::<TokenKindTest, TreeTest>::default()
+                    .child(parser_token(TokenKindTest::B, |token| TreeTest { token }))
+                    .default_tree(|| TreeTest {
+                        token: Token::new(PathBuf::from("--"), 0, 1, 1, String::new(), ()),
+                    })
+                    .build()
+                    .unwrap(),
+            )))
+            .action(|token, _| TreeTest { token })
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            check_productive(&root),
+            Err(ParsingError::UnproductiveRepetition {
+                expected_token_kinds: HashSet::from([TokenKindTest::B]),
+            })
+        );
+    }
+
+    fn parser_backtracking() -> Box<dyn Parse<TokenKindTest, TreeTest>> {
+        let mut builder = ParserChoiceBuilder::<TokenKindTest, TreeTest>::default();
+
+        for last in [TokenKindTest::B, TokenKindTest::C, TokenKindTest::D] {
+            builder.choices(Box::new(
+                ParserKindSeqBuilder::<TokenKindTest, TreeTest>::default()
+                    .kinds(TokenKindTest::A)
+                    .kinds(TokenKindTest::A)
+                    .kinds(TokenKindTest::A)
+                    .kinds(last)
+                    .action(|tokens| TreeTest {
+                        token: tokens[0].clone(),
+                    })
+                    .build()
+                    .unwrap(),
+            ));
+        }
+
+        Box::new(builder.build().unwrap())
+    }
+
+    #[test]
+    fn test_parse_within_limit() {
+        let root = parser_backtracking();
+
+        // Every alternative reads the three As before failing
+        let tokens = tokens(&[TokenKindTest::A; 4]);
+
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::new(&mut tokens_iter);
+        token_reader.set_max_consumed(Some(6));
+
+        assert_eq!(
+            parse_within_limit(root.as_ref(), &mut token_reader),
+            Err(ParsingError::ParseLimitExceeded { limit: 6 })
+        );
+
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::new(&mut tokens_iter);
+
+        assert!(matches!(
+            parse_within_limit(root.as_ref(), &mut token_reader),
+            Err(ParsingError::UnexpectedToken { .. })
+        ));
+        assert!(token_reader.consumed() > 6);
+    }
+
+    #[test]
+    fn test_parse_within_limit_exact() {
+        let root = parser_backtracking();
+        let tokens = tokens(&[
+            TokenKindTest::A,
+            TokenKindTest::A,
+            TokenKindTest::A,
+            TokenKindTest::B,
+        ]);
+
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::new(&mut tokens_iter);
+        token_reader.set_max_consumed(Some(4));
+
+        assert!(parse_within_limit(root.as_ref(), &mut token_reader).is_ok());
+        assert!(!token_reader.is_limit_exceeded());
+    }
 }
@@ -0,0 +1,277 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use super::{parse::Parse, parsing_error::ParsingError};
+use crate::{domain::token::TokenKind, lexing::token_reader::TokenReader, tree::Tree};
+use std::collections::HashSet;
+
+// Captures the token reader's offset as an anchor and parses the child. The child only succeeds if it leaves the
+// reader at the anchor, so this asserts that no tokens were consumed. Otherwise it seeks back to the anchor and fails
+// with `AnchorMoved`. If the child fails, its error is returned as is after seeking back. This is useful in recovery logic and speculative parsing, where a child is expected to match
+// without moving, such as a `ParserOr` falling back to its default.
+pub struct ParserAnchor<TTokenKind: TokenKind, TTree: Tree> {
+    child: Box<dyn Parse<TTokenKind, TTree>>,
+}
+
+pub struct ParserAnchorBuilder<TTokenKind: TokenKind, TTree: Tree> {
+    child: Option<Box<dyn Parse<TTokenKind, TTree>>>,
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> ParserAnchorBuilder<TTokenKind, TTree> {
+    pub fn child(&mut self, value: Box<dyn Parse<TTokenKind, TTree>>) -> &mut Self {
+        self.child = Some(value);
+        self
+    }
+
+    pub fn build(&mut self) -> Result<ParserAnchor<TTokenKind, TTree>, ParsingError<TTokenKind>> {
+        Ok(ParserAnchor {
+            child: self
+                .child
+                .take()
+                .ok_or(ParsingError::RequiredParserFieldMissing("child"))?,
+        })
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Default for ParserAnchorBuilder<TTokenKind, TTree> {
+    fn default() -> Self {
+        Self { child: None }
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Parse<TTokenKind, TTree>
+    for ParserAnchor<TTokenKind, TTree>
+{
+    fn parse(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<TTree, ParsingError<TTokenKind>> {
+        token_reader.holding(
+            |token_reader, anchor| match self.child.parse(token_reader) {
+                Ok(tree) if token_reader.offset() == anchor => Ok(tree),
+                Ok(_) => {
+                    let offset = token_reader.offset();
+                    token_reader.seek(anchor);
+                    Err(ParsingError::AnchorMoved { anchor, offset })
+                }
+                Err(err) => {
+                    token_reader.seek(anchor);
+                    Err(err)
+                }
+            },
+        )
+    }
+
+    fn expected_tokens_unsafe(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+        self.child.expected_tokens()
+    }
+
+    fn sub_parsers(&self) -> Vec<&dyn Parse<TTokenKind, TTree>> {
+        Vec::from([self.child.as_ref()])
+    }
+
+    fn can_match_empty(&self) -> bool {
+        // The child only succeeds here if it consumed nothing
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{
+        domain::token::Token,
+        parsing::{
+            parser_choice::ParserChoiceBuilder, parser_map_err::ParserMapErrBuilder,
+            parser_or::ParserOrBuilder, parser_token::ParserTokenBuilder,
+        },
+        tree::visit::Visit,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        A,
+        B,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    struct TreeTest {
+        token: Token<()>,
+    }
+
+    impl Tree for TreeTest {
+        fn token(&self) -> &Token<()> {
+            &self.token
+        }
+    }
+
+    impl Visit for TreeTest {
+        fn visit(&self, _: crate::tree::visit::VisitCallback<Self>) {}
+
+        fn visit_mut(&self, _: crate::tree::visit::VisitCallbackMut<Self>) {}
+    }
+
+    fn parser_anchor() -> ParserAnchor<TokenKindTest, TreeTest> {
+        ParserAnchorBuilder::<TokenKindTest, TreeTest>::default()
+            .child(Box::new(
+                ParserOrBuilder::<TokenKindTest, TreeTest>::default()
+                    .child(Box::new(
+                        ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                            .token_kind(TokenKindTest::A)
+                            .action(|token| TreeTest { token })
+                            .build()
+                            .unwrap(),
+                    ))
+                    .default_tree(|| TreeTest {
+                        token: Token::new(
+                            PathBuf::from("--"),
+                            0,
+                            1,
+                            1,
+                            String::from("default"),
+                            (),
+                        ),
+                    })
+                    .build()
+                    .unwrap(),
+            ))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parser_anchor_not_consumed() {
+        let tokens = Vec::from([Token::new(
+            PathBuf::from("--"),
+            0,
+            1,
+            1,
+            String::from("b"),
+            TokenKindTest::B,
+        )]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_anchor()
+                .parse(&mut token_reader)
+                .map(|tree| tree.token.text),
+            Ok(String::from("default"))
+        );
+        assert_eq!(token_reader.offset(), 0);
+    }
+
+    #[test]
+    fn test_parser_anchor_consumed() {
+        let tokens = Vec::from([
+            Token::new(
+                PathBuf::from("--"),
+                0,
+                1,
+                1,
+                String::from("b"),
+                TokenKindTest::B,
+            ),
+            Token::new(
+                PathBuf::from("--"),
+                1,
+                1,
+                2,
+                String::from("a"),
+                TokenKindTest::A,
+            ),
+        ]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        token_reader.eat_next();
+
+        assert_eq!(
+            parser_anchor().parse(&mut token_reader),
+            Err(ParsingError::AnchorMoved {
+                anchor: 1,
+                offset: 2
+            })
+        );
+        assert_eq!(token_reader.offset(), 1);
+    }
+
+    #[test]
+    fn test_parser_anchor_committed_in_choice() {
+        let tokens = Vec::from([Token::new(
+            PathBuf::from("--"),
+            0,
+            1,
+            1,
+            String::from("b"),
+            TokenKindTest::B,
+        )]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        let parser = ParserChoiceBuilder::<TokenKindTest, TreeTest>::default()
+            .choices(Box::new(
+                ParserAnchorBuilder::<TokenKindTest, TreeTest>::default()
+                    // Fails after consuming the token it rejects, with an error after a commit point
+                    .child(Box::new(
+                        ParserMapErrBuilder::<TokenKindTest, TreeTest>::default()
+                            .child(Box::new(
+                                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                                    .token_kind(TokenKindTest::A)
+                                    .action(|token| TreeTest { token })
+                                    .build()
+                                    .unwrap(),
+                            ))
+                            .map(ParsingError::into_committed)
+                            .build()
+                            .unwrap(),
+                    ))
+                    .build()
+                    .unwrap(),
+            ))
+            .choices(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::B)
+                    .action(|token| TreeTest { token })
+                    .build()
+                    .unwrap(),
+            ))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            parser.parse(&mut token_reader),
+            Err(ParsingError::Committed(Box::new(
+                ParsingError::UnexpectedToken {
+                    expected_token_kinds: HashSet::from([TokenKindTest::A]),
+                    actual_token: Box::new(tokens[0].clone()),
+                }
+            )))
+        );
+        assert_eq!(token_reader.offset(), 0);
+    }
+}
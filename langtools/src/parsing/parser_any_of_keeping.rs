@@ -0,0 +1,207 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+use super::{parse::Parse, parsing_error::ParsingError};
+use crate::{
+    domain::token::{Token, TokenKind},
+    lexing::token_reader::TokenReader,
+    tree::Tree,
+};
+use std::{collections::HashSet, mem};
+
+// Called with the matched token and its kind, since the kind is lost when the token is made kindless
+pub type ParserAnyOfKeepingAction<TTokenKind, TTree> = fn(Token<()>, TTokenKind) -> TTree;
+
+// Matches a single token of any kind in `kinds`, like a literal that could be a number, a string, or a character. The
+// reader is left where it was if the token doesn't match.
+pub struct ParserAnyOfKeeping<TTokenKind: TokenKind, TTree: Tree> {
+    kinds: HashSet<TTokenKind>,
+    action: ParserAnyOfKeepingAction<TTokenKind, TTree>,
+}
+
+pub struct ParserAnyOfKeepingBuilder<TTokenKind: TokenKind, TTree: Tree> {
+    kinds: HashSet<TTokenKind>,
+    action: Option<ParserAnyOfKeepingAction<TTokenKind, TTree>>,
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> ParserAnyOfKeepingBuilder<TTokenKind, TTree> {
+    pub fn kinds(&mut self, value: TTokenKind) -> &mut Self {
+        self.kinds.insert(value);
+        self
+    }
+
+    pub fn action(&mut self, value: ParserAnyOfKeepingAction<TTokenKind, TTree>) -> &mut Self {
+        self.action = Some(value);
+        self
+    }
+
+    pub fn build(
+        &mut self,
+    ) -> Result<ParserAnyOfKeeping<TTokenKind, TTree>, ParsingError<TTokenKind>> {
+        if self.kinds.is_empty() {
+            return Err(ParsingError::RequiredParserFieldMissing("kinds"));
+        }
+
+        Ok(ParserAnyOfKeeping {
+            kinds: mem::take(&mut self.kinds),
+            action: self
+                .action
+                .ok_or(ParsingError::RequiredParserFieldMissing("action"))?,
+        })
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Default for ParserAnyOfKeepingBuilder<TTokenKind, TTree> {
+    fn default() -> Self {
+        Self {
+            kinds: HashSet::new(),
+            action: None,
+        }
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Parse<TTokenKind, TTree>
+    for ParserAnyOfKeeping<TTokenKind, TTree>
+{
+    fn parse(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<TTree, ParsingError<TTokenKind>> {
+        token_reader.holding(|token_reader, offset| {
+            let error = match token_reader.eat_next() {
+                Some(token) if self.kinds.contains(&token.kind) => {
+                    return Ok((self.action)(token.to_kindless(), token.kind));
+                }
+                Some(token) => ParsingError::UnexpectedToken {
+                    expected_token_kinds: self.kinds.clone(),
+                    actual_token: Box::new(token.clone()),
+                },
+                None => ParsingError::UnexpectedEndOfSource {
+                    expected_token_kinds: self.kinds.clone(),
+                },
+            };
+
+            token_reader.seek(offset);
+            Err(error)
+        })
+    }
+
+    fn expected_tokens_unsafe(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+        Ok(self.kinds.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::tree::visit::Visit;
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        Number,
+        String,
+        Character,
+        Identifier,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    struct TreeTest {
+        token: Token<()>,
+        kind: TokenKindTest,
+    }
+
+    impl Tree for TreeTest {
+        fn token(&self) -> &Token<()> {
+            &self.token
+        }
+    }
+
+    impl Visit for TreeTest {
+        fn visit(&self, _: crate::tree::visit::VisitCallback<Self>) {}
+
+        fn visit_mut(&self, _: crate::tree::visit::VisitCallbackMut<Self>) {}
+    }
+
+    fn parser_literal() -> ParserAnyOfKeeping<TokenKindTest, TreeTest> {
+        ParserAnyOfKeepingBuilder::<TokenKindTest, TreeTest>::default()
+            .kinds(TokenKindTest::Number)
+            .kinds(TokenKindTest::String)
+            .kinds(TokenKindTest::Character)
+            .action(|token, kind| TreeTest { token, kind })
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parser_any_of_keeping_records_kind() {
+        let tokens = Vec::from([Token::new(
+            PathBuf::from("--"),
+            0,
+            1,
+            1,
+            String::from("\"a\""),
+            TokenKindTest::String,
+        )]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_literal().parse(&mut token_reader),
+            Ok(TreeTest {
+                token: tokens[0].to_kindless(),
+                kind: TokenKindTest::String,
+            })
+        );
+        assert_eq!(token_reader.offset(), 1);
+    }
+
+    #[test]
+    fn test_parser_any_of_keeping_unexpected_token() {
+        let tokens = Vec::from([Token::new(
+            PathBuf::from("--"),
+            0,
+            1,
+            1,
+            String::from("x"),
+            TokenKindTest::Identifier,
+        )]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_literal().parse(&mut token_reader),
+            Err(ParsingError::UnexpectedToken {
+                expected_token_kinds: HashSet::from([
+                    TokenKindTest::Number,
+                    TokenKindTest::String,
+                    TokenKindTest::Character,
+                ]),
+                actual_token: Box::new(tokens[0].clone()),
+            })
+        );
+        assert_eq!(token_reader.offset(), 0);
+    }
+}
@@ -0,0 +1,376 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use super::{parse::Parse, parsing_error::ParsingError};
+use crate::{
+    domain::token::{Token, TokenKind},
+    lexing::token_reader::TokenReader,
+    tree::Tree,
+};
+use std::collections::HashSet;
+
+// Called with the open token, for the location of the list, and the elements parsed
+pub type ParserBracketedListAction<TTree> = fn(Token<()>, Vec<TTree>) -> TTree;
+
+// Parses `open`, then zero or more elements separated by `separator`, then `close`, like `(a, b, c)`. An open token
+// followed directly by a close token is an empty list. A separator before the close token is only accepted with
+// `allow_trailing`, and a lone separator like `(,)` never is. On failure the token reader is rewound to where the list
+// started.
+pub struct ParserBracketedList<TTokenKind: TokenKind, TTree: Tree> {
+    open: TTokenKind,
+    element: Box<dyn Parse<TTokenKind, TTree>>,
+    separator: TTokenKind,
+    close: TTokenKind,
+    allow_trailing: bool,
+    action: ParserBracketedListAction<TTree>,
+}
+
+pub struct ParserBracketedListBuilder<TTokenKind: TokenKind, TTree: Tree> {
+    open: Option<TTokenKind>,
+    element: Option<Box<dyn Parse<TTokenKind, TTree>>>,
+    separator: Option<TTokenKind>,
+    close: Option<TTokenKind>,
+    allow_trailing: bool,
+    action: Option<ParserBracketedListAction<TTree>>,
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> ParserBracketedListBuilder<TTokenKind, TTree> {
+    pub fn open(&mut self, value: TTokenKind) -> &mut Self {
+        self.open = Some(value);
+        self
+    }
+
+    pub fn element(&mut self, value: Box<dyn Parse<TTokenKind, TTree>>) -> &mut Self {
+        self.element = Some(value);
+        self
+    }
+
+    pub fn separator(&mut self, value: TTokenKind) -> &mut Self {
+        self.separator = Some(value);
+        self
+    }
+
+    pub fn close(&mut self, value: TTokenKind) -> &mut Self {
+        self.close = Some(value);
+        self
+    }
+
+    pub fn allow_trailing(&mut self, value: bool) -> &mut Self {
+        self.allow_trailing = value;
+        self
+    }
+
+    pub fn action(&mut self, value: ParserBracketedListAction<TTree>) -> &mut Self {
+        self.action = Some(value);
+        self
+    }
+
+    pub fn build(
+        &mut self,
+    ) -> Result<ParserBracketedList<TTokenKind, TTree>, ParsingError<TTokenKind>> {
+        Ok(ParserBracketedList {
+            open: self
+                .open
+                .ok_or(ParsingError::RequiredParserFieldMissing("open"))?,
+            element: self
+                .element
+                .take()
+                .ok_or(ParsingError::RequiredParserFieldMissing("element"))?,
+            separator: self
+                .separator
+                .ok_or(ParsingError::RequiredParserFieldMissing("separator"))?,
+            close: self
+                .close
+                .ok_or(ParsingError::RequiredParserFieldMissing("close"))?,
+            allow_trailing: self.allow_trailing,
+            action: self
+                .action
+                .ok_or(ParsingError::RequiredParserFieldMissing("action"))?,
+        })
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Default for ParserBracketedListBuilder<TTokenKind, TTree> {
+    fn default() -> Self {
+        Self {
+            open: None,
+            element: None,
+            separator: None,
+            close: None,
+            allow_trailing: false,
+            action: None,
+        }
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> ParserBracketedList<TTokenKind, TTree> {
+    fn parse_list(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<TTree, ParsingError<TTokenKind>> {
+        let open = match token_reader.eat_next() {
+            Some(token) if token.kind == self.open => token.to_kindless(),
+            Some(token) => {
+                return Err(ParsingError::UnexpectedToken {
+                    expected_token_kinds: self.expected_tokens()?,
+                    actual_token: Box::new(token.clone()),
+                })
+            }
+            None => {
+                return Err(ParsingError::UnexpectedEndOfSource {
+                    expected_token_kinds: self.expected_tokens()?,
+                })
+            }
+        };
+
+        let mut elements = Vec::new();
+
+        // Check for an empty list
+        match token_reader.peek_next() {
+            Some(token) if token.kind == self.close => {
+                token_reader.eat_next();
+                return Ok((self.action)(open, elements));
+            }
+            Some(_) => {}
+            None => {
+                let mut expected_token_kinds = self.element.expected_tokens()?;
+                expected_token_kinds.insert(self.close);
+
+                return Err(ParsingError::UnexpectedEndOfSource {
+                    expected_token_kinds,
+                });
+            }
+        }
+
+        loop {
+            elements.push(self.element.parse(token_reader)?);
+
+            match token_reader.eat_next() {
+                Some(token) if token.kind == self.close => break,
+                Some(token) if token.kind == self.separator => {
+                    if self.allow_trailing
+                        && token_reader
+                            .peek_next()
+                            .is_some_and(|token| token.kind == self.close)
+                    {
+                        token_reader.eat_next();
+                        break;
+                    }
+                }
+                Some(token) => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected_token_kinds: HashSet::from([self.separator, self.close]),
+                        actual_token: Box::new(token.clone()),
+                    })
+                }
+                None => {
+                    return Err(ParsingError::UnexpectedEndOfSource {
+                        expected_token_kinds: HashSet::from([self.separator, self.close]),
+                    })
+                }
+            }
+        }
+
+        Ok((self.action)(open, elements))
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Parse<TTokenKind, TTree>
+    for ParserBracketedList<TTokenKind, TTree>
+{
+    fn parse(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<TTree, ParsingError<TTokenKind>> {
+        token_reader.holding(|token_reader, offset| {
+            let result = self.parse_list(token_reader);
+
+            if result.is_err() {
+                token_reader.seek(offset);
+            }
+
+            result
+        })
+    }
+
+    fn expected_tokens_unsafe(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+        Ok(HashSet::from([self.open]))
+    }
+
+    fn sub_parsers(&self) -> Vec<&dyn Parse<TTokenKind, TTree>> {
+        Vec::from([self.element.as_ref()])
+    }
+
+    fn own_token_kinds(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+        Ok(HashSet::from([self.open, self.separator, self.close]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parsing::{parser_token::ParserTokenBuilder, testing::tokens},
+        tree::visit::Visit,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        LeftParen,
+        RightParen,
+        Comma,
+        Item,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    enum TreeTest {
+        Item(Token<()>),
+        List(Token<()>, Vec<TreeTest>),
+    }
+
+    impl Tree for TreeTest {
+        fn token(&self) -> &Token<()> {
+            match self {
+                TreeTest::Item(token) => token,
+                TreeTest::List(token, _) => token,
+            }
+        }
+    }
+
+    impl Visit for TreeTest {
+        fn visit(&self, _: crate::tree::visit::VisitCallback<Self>) {}
+
+        fn visit_mut(&self, _: crate::tree::visit::VisitCallbackMut<Self>) {}
+    }
+
+    fn parser_bracketed_list(allow_trailing: bool) -> ParserBracketedList<TokenKindTest, TreeTest> {
+        ParserBracketedListBuilder::<TokenKindTest, TreeTest>::default()
+            .open(TokenKindTest::LeftParen)
+            .element(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::Item)
+                    .action(TreeTest::Item)
+                    .build()
+                    .unwrap(),
+            ))
+            .separator(TokenKindTest::Comma)
+            .close(TokenKindTest::RightParen)
+            .allow_trailing(allow_trailing)
+            .action(TreeTest::List)
+            .build()
+            .unwrap()
+    }
+
+    fn element_count(
+        parser: &ParserBracketedList<TokenKindTest, TreeTest>,
+        tokens: &[Token<TokenKindTest>],
+    ) -> Result<usize, ParsingError<TokenKindTest>> {
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::new(&mut tokens_iter);
+
+        let result = parser.parse(&mut token_reader).map(|tree| match tree {
+            TreeTest::List(_, elements) => elements.len(),
+            TreeTest::Item(_) => panic!("expected a list"),
+        });
+
+        if result.is_ok() {
+            assert!(token_reader.at_end());
+        }
+
+        result
+    }
+
+    #[test]
+    fn test_parser_bracketed_list_empty() {
+        let tokens = tokens(&[TokenKindTest::LeftParen, TokenKindTest::RightParen]);
+
+        assert_eq!(element_count(&parser_bracketed_list(false), &tokens), Ok(0));
+    }
+
+    #[test]
+    fn test_parser_bracketed_list_one() {
+        let tokens = tokens(&[
+            TokenKindTest::LeftParen,
+            TokenKindTest::Item,
+            TokenKindTest::RightParen,
+        ]);
+
+        assert_eq!(element_count(&parser_bracketed_list(false), &tokens), Ok(1));
+    }
+
+    #[test]
+    fn test_parser_bracketed_list_two() {
+        let tokens = tokens(&[
+            TokenKindTest::LeftParen,
+            TokenKindTest::Item,
+            TokenKindTest::Comma,
+            TokenKindTest::Item,
+            TokenKindTest::RightParen,
+        ]);
+
+        assert_eq!(element_count(&parser_bracketed_list(false), &tokens), Ok(2));
+    }
+
+    #[test]
+    fn test_parser_bracketed_list_trailing() {
+        let tokens = tokens(&[
+            TokenKindTest::LeftParen,
+            TokenKindTest::Item,
+            TokenKindTest::Comma,
+            TokenKindTest::Item,
+            TokenKindTest::Comma,
+            TokenKindTest::RightParen,
+        ]);
+
+        assert_eq!(element_count(&parser_bracketed_list(true), &tokens), Ok(2));
+        assert_eq!(
+            element_count(&parser_bracketed_list(false), &tokens),
+            Err(ParsingError::UnexpectedToken {
+                expected_token_kinds: HashSet::from([TokenKindTest::Item]),
+                actual_token: Box::new(tokens[5].clone()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parser_bracketed_list_missing_close() {
+        let tokens = tokens(&[
+            TokenKindTest::LeftParen,
+            TokenKindTest::Item,
+            TokenKindTest::Item,
+        ]);
+
+        assert_eq!(
+            element_count(&parser_bracketed_list(false), &tokens),
+            Err(ParsingError::UnexpectedToken {
+                expected_token_kinds: HashSet::from([
+                    TokenKindTest::Comma,
+                    TokenKindTest::RightParen
+                ]),
+                actual_token: Box::new(tokens[2].clone()),
+            })
+        );
+    }
+}
@@ -20,16 +20,30 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use super::{parse::Parse, parsing_error::ParsingError};
-use crate::{domain::token::TokenKind, tree::Tree};
+use super::{
+    parse::{union_expected, Parse},
+    parsing_error::ParsingError,
+};
+use crate::{domain::token::TokenKind, lexing::token_reader::TokenReader, tree::Tree};
 use std::{collections::HashSet, mem};
 
+// With ambiguity detection enabled, every choice is tried instead of stopping at the first success. If more than one
+// choice succeeds and ends at the same offset as the first successful choice, parsing fails with `AmbiguousParse`.
+// This is meant as a diagnostic for grammar authors since it costs a parse of every choice.
+//
+// With `reject_shadowed`, building fails with `ShadowedChoice` if a choice expects exactly one token kind and an
+// earlier choice expects exactly that same kind. The earlier choice is tried first on that kind so the later one is
+// likely dead, such as when the same parser was added twice. This is a heuristic: it doesn't catch every unreachable
+// choice, and the later choice is still reachable if the earlier one can fail after matching its first token.
 pub struct ParserChoice<TTokenKind: TokenKind, TTree: Tree> {
     choices: Vec<Box<dyn Parse<TTokenKind, TTree>>>,
+    detect_ambiguity: bool,
 }
 
 pub struct ParserChoiceBuilder<TTokenKind: TokenKind, TTree: Tree> {
     choices: Vec<Box<dyn Parse<TTokenKind, TTree>>>,
+    detect_ambiguity: bool,
+    reject_shadowed: bool,
 }
 
 impl<TTokenKind: TokenKind, TTree: Tree> ParserChoiceBuilder<TTokenKind, TTree> {
@@ -38,64 +52,196 @@ impl<TTokenKind: TokenKind, TTree: Tree> ParserChoiceBuilder<TTokenKind, TTree>
         self
     }
 
+    pub fn detect_ambiguity(&mut self, value: bool) -> &mut Self {
+        self.detect_ambiguity = value;
+        self
+    }
+
+    pub fn reject_shadowed(&mut self, value: bool) -> &mut Self {
+        self.reject_shadowed = value;
+        self
+    }
+
     pub fn build(&mut self) -> Result<ParserChoice<TTokenKind, TTree>, ParsingError<TTokenKind>> {
         if self.choices.is_empty() {
             return Err(ParsingError::RequiredParserFieldMissing("choices"));
         }
 
+        if self.reject_shadowed {
+            self.check_shadowed()?;
+        }
+
         Ok(ParserChoice {
             choices: mem::take(&mut self.choices),
+            detect_ambiguity: self.detect_ambiguity,
         })
     }
 }
 
+impl<TTokenKind: TokenKind, TTree: Tree> ParserChoiceBuilder<TTokenKind, TTree> {
+    fn check_shadowed(&self) -> Result<(), ParsingError<TTokenKind>> {
+        let mut single_kinds = HashSet::new();
+
+        for (index, choice) in self.choices.iter().enumerate() {
+            let expected = choice.expected_tokens()?;
+
+            if expected.len() != 1 {
+                continue;
+            }
+
+            let token_kind = *expected
+                .iter()
+                .next()
+                .expect("expected tokens should have one element");
+
+            if !single_kinds.insert(token_kind) {
+                return Err(ParsingError::ShadowedChoice {
+                    choice: index,
+                    token_kind,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
 impl<TTokenKind: TokenKind, TTree: Tree> Default for ParserChoiceBuilder<TTokenKind, TTree> {
     fn default() -> Self {
         Self {
             choices: Vec::new(),
+            detect_ambiguity: false,
+            reject_shadowed: false,
         }
     }
 }
 
+impl<TTokenKind: TokenKind, TTree: Tree> ParserChoice<TTokenKind, TTree> {
+    fn no_choice_error(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<ParsingError<TTokenKind>, ParsingError<TTokenKind>> {
+        if let Some(token) = token_reader.peek_next() {
+            Ok(ParsingError::UnexpectedToken {
+                expected_token_kinds: self.expected_tokens()?,
+                actual_token: Box::new(token.clone()),
+            })
+        } else {
+            Ok(ParsingError::UnexpectedEndOfSource {
+                expected_token_kinds: self.expected_tokens()?,
+            })
+        }
+    }
+
+    fn parse_detecting_ambiguity(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Option<Result<TTree, ParsingError<TTokenKind>>> {
+        token_reader.holding(|token_reader, offset| {
+            let mut first: Option<(&dyn Parse<TTokenKind, TTree>, usize)> = None;
+            let mut alternatives = 0;
+
+            for child_parser in self.choices.iter() {
+                let succeeded = match child_parser.parse(token_reader) {
+                    Ok(_) => true,
+                    Err(err) if err.is_committed() => return Some(Err(err)),
+                    Err(_) => false,
+                };
+                let end_offset = token_reader.offset();
+                token_reader.seek(offset);
+
+                if !succeeded {
+                    continue;
+                }
+
+                match first {
+                    None => {
+                        first = Some((child_parser.as_ref(), end_offset));
+                        alternatives = 1;
+                    }
+                    Some((_, first_end_offset)) if first_end_offset == end_offset => {
+                        alternatives += 1;
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            let (first_parser, _) = first?;
+
+            if alternatives > 1 {
+                Some(Err(ParsingError::AmbiguousParse { alternatives }))
+            } else {
+                // The token reader cannot seek forward, so the successful choice is parsed again to leave the reader after it
+                Some(first_parser.parse(token_reader))
+            }
+        })
+    }
+}
+
 impl<TTokenKind: TokenKind, TTree: Tree> Parse<TTokenKind, TTree>
     for ParserChoice<TTokenKind, TTree>
 {
     fn parse(
         &self,
-        token_reader: &mut crate::lexing::token_reader::TokenReader<TTokenKind>,
+        token_reader: &mut TokenReader<TTokenKind>,
     ) -> Result<TTree, ParsingError<TTokenKind>> {
-        let offset = token_reader.offset();
-
-        for child_parser in self.choices.iter() {
-            if let Ok(child) = child_parser.parse(token_reader) {
-                return Ok(child);
-            } else {
-                token_reader.seek(offset);
+        if self.detect_ambiguity {
+            if let Some(result) = self.parse_detecting_ambiguity(token_reader) {
+                return result;
+            }
+        } else {
+            let result = token_reader.holding(|token_reader, offset| {
+                for child_parser in self.choices.iter() {
+                    match child_parser.parse(token_reader) {
+                        Ok(child) => return Some(Ok(child)),
+                        Err(err) if err.is_committed() => return Some(Err(err)),
+                        Err(_) => token_reader.seek(offset),
+                    }
+                }
+
+                None
+            });
+
+            if let Some(result) = result {
+                return result;
             }
         }
 
-        if let Some(token) = token_reader.peek_next() {
-            Err(ParsingError::UnexpectedToken {
-                expected_token_kinds: self.expected_tokens()?,
-                actual_token: token.clone(),
-            })
-        } else {
-            Err(ParsingError::UnexpectedEndOfSource {
-                expected_token_kinds: self.expected_tokens()?,
-            })
+        Err(self.no_choice_error(token_reader)?)
+    }
+
+    fn matches(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<(), ParsingError<TTokenKind>> {
+        // Ambiguity detection has to parse the winning choice again anyway
+        if self.detect_ambiguity {
+            return self.parse(token_reader).map(|_| ());
         }
+
+        token_reader.holding(|token_reader, offset| {
+            for child_parser in self.choices.iter() {
+                match child_parser.matches(token_reader) {
+                    Ok(()) => return Ok(()),
+                    Err(err) if err.is_committed() => return Err(err),
+                    Err(_) => token_reader.seek(offset),
+                }
+            }
+
+            Err(self.no_choice_error(token_reader)?)
+        })
     }
 
     fn expected_tokens_unsafe(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
-        let mut result = HashSet::<TTokenKind>::new();
+        union_expected(&self.sub_parsers())
+    }
 
-        for child_parser in self.choices.iter() {
-            for token_kind in child_parser.expected_tokens()? {
-                result.insert(token_kind);
-            }
-        }
+    fn sub_parsers(&self) -> Vec<&dyn Parse<TTokenKind, TTree>> {
+        self.choices.iter().map(|choice| choice.as_ref()).collect()
+    }
 
-        Ok(result)
+    fn can_match_empty(&self) -> bool {
+        self.choices.iter().any(|choice| choice.can_match_empty())
     }
 }
 
@@ -104,8 +250,10 @@ mod tests {
     use std::{collections::HashSet, path::PathBuf};
 
     use crate::{
-        domain::token::Token, lexing::token_reader::TokenReader,
-        parsing::parser_token::ParserTokenBuilder, tree::visit::Visit,
+        domain::token::Token,
+        lexing::token_reader::TokenReader,
+        parsing::{parser_sequential::ParserSequentialBuilder, parser_token::ParserTokenBuilder},
+        tree::visit::Visit,
     };
 
     use super::*;
@@ -150,14 +298,14 @@ mod tests {
             .choices(Box::new(
                 ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
                     .token_kind(TokenKindTest::A)
-                    .action(|token| TreeTest::A(token))
+                    .action(TreeTest::A)
                     .build()
                     .unwrap(),
             ))
             .choices(Box::new(
                 ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
                     .token_kind(TokenKindTest::B)
-                    .action(|token| TreeTest::B(token))
+                    .action(TreeTest::B)
                     .build()
                     .unwrap(),
             ))
@@ -172,6 +320,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parser_choice_expected_order() {
+        let tokens = Vec::new();
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        let mut builder = ParserChoiceBuilder::<TokenKindTest, TreeTest>::default();
+
+        for token_kind in [TokenKindTest::C, TokenKindTest::A, TokenKindTest::B] {
+            builder.choices(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(token_kind)
+                    .action(TreeTest::A)
+                    .build()
+                    .unwrap(),
+            ));
+        }
+
+        let error = builder
+            .build()
+            .unwrap()
+            .parse(&mut token_reader)
+            .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "unexpected end of source, expected A, B, or C"
+        );
+        assert_eq!(
+            format!("{error:?}"),
+            "UnexpectedEndOfSource { expected_token_kinds: {A, B, C} }"
+        );
+    }
+
     #[test]
     fn test_parser_sequential_unexpected_token() {
         let tokens = Vec::from([Token::new(
@@ -189,14 +371,14 @@ mod tests {
             .choices(Box::new(
                 ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
                     .token_kind(TokenKindTest::A)
-                    .action(|token| TreeTest::A(token))
+                    .action(TreeTest::A)
                     .build()
                     .unwrap(),
             ))
             .choices(Box::new(
                 ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
                     .token_kind(TokenKindTest::B)
-                    .action(|token| TreeTest::B(token))
+                    .action(TreeTest::B)
                     .build()
                     .unwrap(),
             ))
@@ -207,14 +389,14 @@ mod tests {
             parser.parse(&mut token_reader),
             Err(ParsingError::UnexpectedToken {
                 expected_token_kinds: HashSet::from([TokenKindTest::A, TokenKindTest::B]),
-                actual_token: Token::new(
+                actual_token: Box::new(Token::new(
                     PathBuf::from("--"),
                     0,
                     1,
                     1,
                     String::from("c"),
                     TokenKindTest::C
-                )
+                ))
             })
         );
     }
@@ -236,14 +418,14 @@ mod tests {
             .choices(Box::new(
                 ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
                     .token_kind(TokenKindTest::A)
-                    .action(|token| TreeTest::A(token))
+                    .action(TreeTest::A)
                     .build()
                     .unwrap(),
             ))
             .choices(Box::new(
                 ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
                     .token_kind(TokenKindTest::B)
-                    .action(|token| TreeTest::B(token))
+                    .action(TreeTest::B)
                     .build()
                     .unwrap(),
             ))
@@ -280,17 +462,101 @@ mod tests {
             .choices(Box::new(
                 ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
                     .token_kind(TokenKindTest::A)
-                    .action(|token| TreeTest::A(token))
+                    .action(TreeTest::A)
+                    .build()
+                    .unwrap(),
+            ))
+            .choices(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::B)
+                    .action(TreeTest::B)
+                    .build()
+                    .unwrap(),
+            ))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            parser.parse(&mut token_reader),
+            Ok(TreeTest::B(Token::new(
+                PathBuf::from("--"),
+                0,
+                1,
+                1,
+                String::from("b"),
+                ()
+            )))
+        );
+    }
+
+    #[test]
+    fn test_parser_choice_ambiguous() {
+        let tokens = Vec::from([Token::new(
+            PathBuf::from("--"),
+            0,
+            1,
+            1,
+            String::from("a"),
+            TokenKindTest::A,
+        )]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        let parser = ParserChoiceBuilder::<TokenKindTest, TreeTest>::default()
+            .choices(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::A)
+                    .action(TreeTest::A)
+                    .build()
+                    .unwrap(),
+            ))
+            .choices(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::A)
+                    .action(TreeTest::B)
+                    .build()
+                    .unwrap(),
+            ))
+            .detect_ambiguity(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            parser.parse(&mut token_reader),
+            Err(ParsingError::AmbiguousParse { alternatives: 2 })
+        );
+        assert_eq!(token_reader.offset(), 0);
+    }
+
+    #[test]
+    fn test_parser_choice_detect_ambiguity_unambiguous() {
+        let tokens = Vec::from([Token::new(
+            PathBuf::from("--"),
+            0,
+            1,
+            1,
+            String::from("b"),
+            TokenKindTest::B,
+        )]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        let parser = ParserChoiceBuilder::<TokenKindTest, TreeTest>::default()
+            .choices(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::A)
+                    .action(TreeTest::A)
                     .build()
                     .unwrap(),
             ))
             .choices(Box::new(
                 ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
                     .token_kind(TokenKindTest::B)
-                    .action(|token| TreeTest::B(token))
+                    .action(TreeTest::B)
                     .build()
                     .unwrap(),
             ))
+            .detect_ambiguity(true)
             .build()
             .unwrap();
 
@@ -305,5 +571,199 @@ mod tests {
                 ()
             )))
         );
+        assert_eq!(token_reader.offset(), 1);
+    }
+
+    #[test]
+    fn test_parser_choice_matches() {
+        let parser = ParserChoiceBuilder::<TokenKindTest, TreeTest>::default()
+            .choices(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::A)
+                    .action(TreeTest::A)
+                    .build()
+                    .unwrap(),
+            ))
+            .choices(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::B)
+                    .action(TreeTest::B)
+                    .build()
+                    .unwrap(),
+            ))
+            .build()
+            .unwrap();
+
+        for kind in [TokenKindTest::A, TokenKindTest::B, TokenKindTest::C] {
+            let tokens = Vec::from([Token::new(
+                PathBuf::from("--"),
+                0,
+                1,
+                1,
+                String::from("x"),
+                kind,
+            )]);
+
+            let mut tokens_iter = tokens.iter();
+            let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+            let parsed = parser.parse(&mut token_reader).map(|_| ());
+            let parsed_offset = token_reader.offset();
+
+            let mut tokens_iter = tokens.iter();
+            let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+            let matched = parser.matches(&mut token_reader);
+
+            assert_eq!(matched, parsed);
+            assert_eq!(token_reader.offset(), parsed_offset);
+        }
+    }
+
+    #[test]
+    fn test_parser_choice_reject_shadowed() {
+        let result = ParserChoiceBuilder::<TokenKindTest, TreeTest>::default()
+            .choices(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::A)
+                    .action(TreeTest::A)
+                    .build()
+                    .unwrap(),
+            ))
+            .choices(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::B)
+                    .action(TreeTest::B)
+                    .build()
+                    .unwrap(),
+            ))
+            .choices(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::A)
+                    .action(TreeTest::B)
+                    .build()
+                    .unwrap(),
+            ))
+            .reject_shadowed(true)
+            .build();
+
+        assert_eq!(
+            result.err(),
+            Some(ParsingError::ShadowedChoice {
+                choice: 2,
+                token_kind: TokenKindTest::A
+            })
+        );
+    }
+
+    #[test]
+    fn test_parser_choice_reject_shadowed_distinct() {
+        let result = ParserChoiceBuilder::<TokenKindTest, TreeTest>::default()
+            .choices(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::A)
+                    .action(TreeTest::A)
+                    .build()
+                    .unwrap(),
+            ))
+            .choices(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::B)
+                    .action(TreeTest::B)
+                    .build()
+                    .unwrap(),
+            ))
+            .reject_shadowed(true)
+            .build();
+
+        assert!(result.is_ok());
+    }
+
+    fn parser_choice_with_commit(commit: bool) -> ParserChoice<TokenKindTest, TreeTest> {
+        let token = |token_kind| -> Box<dyn Parse<TokenKindTest, TreeTest>> {
+            Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(token_kind)
+                    .action(TreeTest::A)
+                    .build()
+                    .unwrap(),
+            )
+        };
+
+        let mut first = ParserSequentialBuilder::<TokenKindTest, TreeTest>::default();
+        first.sequence(token(TokenKindTest::A));
+
+        if commit {
+            first.commit();
+        }
+
+        first
+            .sequence(token(TokenKindTest::B))
+            .action(|token, _| TreeTest::A(token));
+
+        ParserChoiceBuilder::<TokenKindTest, TreeTest>::default()
+            .choices(Box::new(first.build().unwrap()))
+            .choices(Box::new(
+                ParserSequentialBuilder::<TokenKindTest, TreeTest>::default()
+                    .sequence(token(TokenKindTest::A))
+                    .sequence(token(TokenKindTest::C))
+                    .action(|token, _| TreeTest::B(token))
+                    .build()
+                    .unwrap(),
+            ))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parser_choice_committed() {
+        let tokens = Vec::from([
+            Token::new(
+                PathBuf::from("--"),
+                0,
+                1,
+                1,
+                String::from("a"),
+                TokenKindTest::A,
+            ),
+            Token::new(
+                PathBuf::from("--"),
+                1,
+                1,
+                2,
+                String::from("c"),
+                TokenKindTest::C,
+            ),
+        ]);
+
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_choice_with_commit(false).parse(&mut token_reader),
+            Ok(TreeTest::B(tokens[0].to_kindless()))
+        );
+
+        let committed_error = ParsingError::Committed(Box::new(ParsingError::UnexpectedToken {
+            expected_token_kinds: HashSet::from([TokenKindTest::B]),
+            actual_token: Box::new(tokens[1].clone()),
+        }));
+
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_choice_with_commit(true).parse(&mut token_reader),
+            Err(committed_error.clone())
+        );
+
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_choice_with_commit(true).matches(&mut token_reader),
+            Err(committed_error.clone())
+        );
+        if let ParsingError::Committed(error) = &committed_error {
+            assert_eq!(committed_error.to_string(), error.to_string());
+        }
     }
 }
@@ -0,0 +1,368 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use super::{
+    parse::{union_expected, Parse},
+    parsing_error::ParsingError,
+};
+use crate::{
+    domain::token::{Token, TokenKind},
+    lexing::token_reader::TokenReader,
+    tree::Tree,
+};
+use std::{collections::HashSet, mem};
+
+pub type ParserFactoredAction<TTree> = fn(Token<()>, TTree, TTree) -> TTree;
+
+// A left-factored choice. Instead of writing this:
+//
+//   choice(sequence(A, B), sequence(A, C))
+//
+// which parses A once for each alternative, the common prefix is parsed once and then the branches are tried:
+//
+//   factored(prefix: A, branches: [B, C])
+//
+// The action receives the prefix's tree and the tree of the branch that succeeded.
+//
+// Parsers are opaque trait objects, so the prefix cannot be pulled out of existing alternatives automatically. The
+// grammar author supplies it explicitly and can use `have_same_first_tokens` to check whether a set of alternatives
+// is a candidate for factoring. Equal first tokens are only a hint: two alternatives can start with the same token
+// kinds without sharing an identical leading parser, and factoring is only correct when the prefix parser behaves
+// identically for every alternative.
+pub struct ParserFactored<TTokenKind: TokenKind, TTree: Tree> {
+    prefix: Box<dyn Parse<TTokenKind, TTree>>,
+    branches: Vec<Box<dyn Parse<TTokenKind, TTree>>>,
+    action: ParserFactoredAction<TTree>,
+}
+
+pub struct ParserFactoredBuilder<TTokenKind: TokenKind, TTree: Tree> {
+    prefix: Option<Box<dyn Parse<TTokenKind, TTree>>>,
+    branches: Vec<Box<dyn Parse<TTokenKind, TTree>>>,
+    action: Option<ParserFactoredAction<TTree>>,
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> ParserFactoredBuilder<TTokenKind, TTree> {
+    pub fn prefix(&mut self, value: Box<dyn Parse<TTokenKind, TTree>>) -> &mut Self {
+        self.prefix = Some(value);
+        self
+    }
+
+    pub fn branches(&mut self, value: Box<dyn Parse<TTokenKind, TTree>>) -> &mut Self {
+        self.branches.push(value);
+        self
+    }
+
+    pub fn action(&mut self, value: ParserFactoredAction<TTree>) -> &mut Self {
+        self.action = Some(value);
+        self
+    }
+
+    pub fn build(&mut self) -> Result<ParserFactored<TTokenKind, TTree>, ParsingError<TTokenKind>> {
+        if self.branches.is_empty() {
+            return Err(ParsingError::RequiredParserFieldMissing("branches"));
+        }
+
+        Ok(ParserFactored {
+            prefix: self
+                .prefix
+                .take()
+                .ok_or(ParsingError::RequiredParserFieldMissing("prefix"))?,
+            branches: mem::take(&mut self.branches),
+            action: self
+                .action
+                .ok_or(ParsingError::RequiredParserFieldMissing("action"))?,
+        })
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Default for ParserFactoredBuilder<TTokenKind, TTree> {
+    fn default() -> Self {
+        Self {
+            prefix: None,
+            branches: Vec::new(),
+            action: None,
+        }
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> ParserFactored<TTokenKind, TTree> {
+    fn expected_tokens_branches(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+        union_expected(
+            &self
+                .branches
+                .iter()
+                .map(|branch| branch.as_ref())
+                .collect::<Vec<&dyn Parse<TTokenKind, TTree>>>(),
+        )
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Parse<TTokenKind, TTree>
+    for ParserFactored<TTokenKind, TTree>
+{
+    fn parse(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<TTree, ParsingError<TTokenKind>> {
+        let prefix = self.prefix.parse(token_reader)?;
+
+        token_reader.holding(|token_reader, offset| {
+            for branch_parser in self.branches.iter() {
+                match branch_parser.parse(token_reader) {
+                    Ok(branch) => return Ok((self.action)(prefix.token().clone(), prefix, branch)),
+                    Err(err) if err.is_committed() => return Err(err),
+                    Err(_) => token_reader.seek(offset),
+                }
+            }
+
+            // Only the branches are expected at this point since the prefix has already been parsed
+            if let Some(token) = token_reader.peek_next() {
+                Err(ParsingError::UnexpectedToken {
+                    expected_token_kinds: self.expected_tokens_branches()?,
+                    actual_token: Box::new(token.clone()),
+                })
+            } else {
+                Err(ParsingError::UnexpectedEndOfSource {
+                    expected_token_kinds: self.expected_tokens_branches()?,
+                })
+            }
+        })
+    }
+
+    fn expected_tokens_unsafe(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+        self.prefix.expected_tokens()
+    }
+
+    fn sub_parsers(&self) -> Vec<&dyn Parse<TTokenKind, TTree>> {
+        let mut result = Vec::from([self.prefix.as_ref()]);
+        result.extend(self.branches.iter().map(|branch| branch.as_ref()));
+        result
+    }
+
+    fn can_match_empty(&self) -> bool {
+        self.prefix.can_match_empty() && self.branches.iter().any(|branch| branch.can_match_empty())
+    }
+}
+
+pub fn have_same_first_tokens<TTokenKind: TokenKind, TTree: Tree>(
+    parsers: &[&dyn Parse<TTokenKind, TTree>],
+) -> Result<bool, ParsingError<TTokenKind>> {
+    let mut first: Option<HashSet<TTokenKind>> = None;
+
+    for parser in parsers {
+        let expected_tokens = parser.expected_tokens()?;
+
+        if let Some(first) = &first {
+            if *first != expected_tokens {
+                return Ok(false);
+            }
+        } else {
+            first = Some(expected_tokens);
+        }
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, path::PathBuf};
+
+    use crate::{
+        domain::token::Token,
+        lexing::token_reader::TokenReader,
+        parsing::{parser_sequential::ParserSequentialBuilder, testing::parser_token},
+        tree::visit::Visit,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        A,
+        B,
+        C,
+        D,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    enum TreeTest {
+        Leaf(Token<()>),
+        Pair(Token<()>, Box<TreeTest>, Box<TreeTest>),
+    }
+
+    impl Tree for TreeTest {
+        fn token(&self) -> &Token<()> {
+            match &self {
+                TreeTest::Leaf(token) => token,
+                TreeTest::Pair(token, _, _) => token,
+            }
+        }
+    }
+
+    impl Visit for TreeTest {
+        fn visit(&self, _: crate::tree::visit::VisitCallback<Self>) {}
+
+        fn visit_mut(&self, _: crate::tree::visit::VisitCallbackMut<Self>) {}
+    }
+
+    fn parser_factored() -> ParserFactored<TokenKindTest, TreeTest> {
+        ParserFactoredBuilder::<TokenKindTest, TreeTest>::default()
+            .prefix(parser_token(TokenKindTest::A, TreeTest::Leaf))
+            .branches(parser_token(TokenKindTest::B, TreeTest::Leaf))
+            .branches(parser_token(TokenKindTest::C, TreeTest::Leaf))
+            .action(|token, prefix, branch| {
+                TreeTest::Pair(token, Box::new(prefix), Box::new(branch))
+            })
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parser_factored_success() {
+        let tokens = Vec::from([
+            Token::new(
+                PathBuf::from("--"),
+                0,
+                1,
+                1,
+                String::from("a"),
+                TokenKindTest::A,
+            ),
+            Token::new(
+                PathBuf::from("--"),
+                1,
+                1,
+                2,
+                String::from("c"),
+                TokenKindTest::C,
+            ),
+        ]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_factored().parse(&mut token_reader),
+            Ok(TreeTest::Pair(
+                Token::new(PathBuf::from("--"), 0, 1, 1, String::from("a"), ()),
+                Box::new(TreeTest::Leaf(Token::new(
+                    PathBuf::from("--"),
+                    0,
+                    1,
+                    1,
+                    String::from("a"),
+                    ()
+                ))),
+                Box::new(TreeTest::Leaf(Token::new(
+                    PathBuf::from("--"),
+                    1,
+                    1,
+                    2,
+                    String::from("c"),
+                    ()
+                )))
+            ))
+        );
+        assert_eq!(token_reader.offset(), 2);
+    }
+
+    #[test]
+    fn test_parser_factored_unexpected_token_after_prefix() {
+        let tokens = Vec::from([
+            Token::new(
+                PathBuf::from("--"),
+                0,
+                1,
+                1,
+                String::from("a"),
+                TokenKindTest::A,
+            ),
+            Token::new(
+                PathBuf::from("--"),
+                1,
+                1,
+                2,
+                String::from("d"),
+                TokenKindTest::D,
+            ),
+        ]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_factored().parse(&mut token_reader),
+            Err(ParsingError::UnexpectedToken {
+                expected_token_kinds: HashSet::from([TokenKindTest::B, TokenKindTest::C]),
+                actual_token: Box::new(tokens[1].clone()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parser_factored_unexpected_end_of_source_after_prefix() {
+        let tokens = Vec::from([Token::new(
+            PathBuf::from("--"),
+            0,
+            1,
+            1,
+            String::from("a"),
+            TokenKindTest::A,
+        )]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_factored().parse(&mut token_reader),
+            Err(ParsingError::UnexpectedEndOfSource {
+                expected_token_kinds: HashSet::from([TokenKindTest::B, TokenKindTest::C]),
+            })
+        );
+    }
+
+    #[test]
+    fn test_have_same_first_tokens() {
+        let sequence_ab = ParserSequentialBuilder::<TokenKindTest, TreeTest>::default()
+            .sequence(parser_token(TokenKindTest::A, TreeTest::Leaf))
+            .sequence(parser_token(TokenKindTest::B, TreeTest::Leaf))
+            .action(|token, _| TreeTest::Leaf(token))
+            .build()
+            .unwrap();
+        let sequence_ac = ParserSequentialBuilder::<TokenKindTest, TreeTest>::default()
+            .sequence(parser_token(TokenKindTest::A, TreeTest::Leaf))
+            .sequence(parser_token(TokenKindTest::C, TreeTest::Leaf))
+            .action(|token, _| TreeTest::Leaf(token))
+            .build()
+            .unwrap();
+        let token_b = parser_token(TokenKindTest::B, TreeTest::Leaf);
+
+        assert_eq!(
+            have_same_first_tokens(&[&sequence_ab, &sequence_ac]),
+            Ok(true)
+        );
+        assert_eq!(
+            have_same_first_tokens(&[&sequence_ab, token_b.as_ref()]),
+            Ok(false)
+        );
+    }
+}
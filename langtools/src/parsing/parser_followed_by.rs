@@ -0,0 +1,221 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use super::{parse::Parse, parsing_error::ParsingError};
+use crate::{domain::token::TokenKind, lexing::token_reader::TokenReader, tree::Tree};
+use std::collections::HashSet;
+
+// Parses the child and then peeks at the next token without consuming it. If it isn't of `lookahead_kind`, the reader
+// seeks back to before the child and parsing fails. Only the child's tree is returned.
+pub struct ParserFollowedBy<TTokenKind: TokenKind, TTree: Tree> {
+    child: Box<dyn Parse<TTokenKind, TTree>>,
+    lookahead_kind: TTokenKind,
+}
+
+pub struct ParserFollowedByBuilder<TTokenKind: TokenKind, TTree: Tree> {
+    child: Option<Box<dyn Parse<TTokenKind, TTree>>>,
+    lookahead_kind: Option<TTokenKind>,
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> ParserFollowedByBuilder<TTokenKind, TTree> {
+    pub fn child(&mut self, value: Box<dyn Parse<TTokenKind, TTree>>) -> &mut Self {
+        self.child = Some(value);
+        self
+    }
+
+    pub fn lookahead_kind(&mut self, value: TTokenKind) -> &mut Self {
+        self.lookahead_kind = Some(value);
+        self
+    }
+
+    pub fn build(
+        &mut self,
+    ) -> Result<ParserFollowedBy<TTokenKind, TTree>, ParsingError<TTokenKind>> {
+        Ok(ParserFollowedBy {
+            child: self
+                .child
+                .take()
+                .ok_or(ParsingError::RequiredParserFieldMissing("child"))?,
+            lookahead_kind: self
+                .lookahead_kind
+                .ok_or(ParsingError::RequiredParserFieldMissing("lookahead_kind"))?,
+        })
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Default for ParserFollowedByBuilder<TTokenKind, TTree> {
+    fn default() -> Self {
+        Self {
+            child: None,
+            lookahead_kind: None,
+        }
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Parse<TTokenKind, TTree>
+    for ParserFollowedBy<TTokenKind, TTree>
+{
+    fn parse(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<TTree, ParsingError<TTokenKind>> {
+        token_reader.holding(|token_reader, offset| {
+            let error = match self.child.parse(token_reader) {
+                Ok(child) => match token_reader.peek_next() {
+                    Some(token) if token.kind == self.lookahead_kind => return Ok(child),
+                    Some(token) => ParsingError::UnexpectedToken {
+                        expected_token_kinds: HashSet::from([self.lookahead_kind]),
+                        actual_token: Box::new(token.clone()),
+                    },
+                    None => ParsingError::UnexpectedEndOfSource {
+                        expected_token_kinds: HashSet::from([self.lookahead_kind]),
+                    },
+                },
+                Err(err) => err,
+            };
+
+            token_reader.seek(offset);
+            Err(error)
+        })
+    }
+
+    fn expected_tokens_unsafe(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+        self.child.expected_tokens()
+    }
+
+    fn sub_parsers(&self) -> Vec<&dyn Parse<TTokenKind, TTree>> {
+        Vec::from([self.child.as_ref()])
+    }
+
+    fn can_match_empty(&self) -> bool {
+        self.child.can_match_empty()
+    }
+
+    fn own_token_kinds(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+        Ok(HashSet::from([self.lookahead_kind]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        domain::token::Token,
+        parsing::{parser_token::ParserTokenBuilder, testing::tokens},
+        tree::visit::Visit,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        Identifier,
+        LeftParen,
+        Semicolon,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    struct TreeTest {
+        token: Token<()>,
+    }
+
+    impl Tree for TreeTest {
+        fn token(&self) -> &Token<()> {
+            &self.token
+        }
+    }
+
+    impl Visit for TreeTest {
+        fn visit(&self, _: crate::tree::visit::VisitCallback<Self>) {}
+
+        fn visit_mut(&self, _: crate::tree::visit::VisitCallbackMut<Self>) {}
+    }
+
+    fn parser_followed_by() -> ParserFollowedBy<TokenKindTest, TreeTest> {
+        ParserFollowedByBuilder::<TokenKindTest, TreeTest>::default()
+            .child(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::Identifier)
+                    .action(|token| TreeTest { token })
+                    .build()
+                    .unwrap(),
+            ))
+            .lookahead_kind(TokenKindTest::LeftParen)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parser_followed_by_followed() {
+        let tokens = tokens(&[TokenKindTest::Identifier, TokenKindTest::LeftParen]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_followed_by().parse(&mut token_reader),
+            Ok(TreeTest {
+                token: tokens[0].to_kindless()
+            })
+        );
+        assert_eq!(token_reader.offset(), 1);
+    }
+
+    #[test]
+    fn test_parser_followed_by_not_followed() {
+        let tokens = tokens(&[TokenKindTest::Identifier, TokenKindTest::Semicolon]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_followed_by().parse(&mut token_reader),
+            Err(ParsingError::UnexpectedToken {
+                expected_token_kinds: HashSet::from([TokenKindTest::LeftParen]),
+                actual_token: Box::new(tokens[1].clone()),
+            })
+        );
+        assert_eq!(token_reader.offset(), 0);
+    }
+
+    #[test]
+    fn test_parser_followed_by_end_of_source() {
+        let tokens = tokens(&[TokenKindTest::Identifier]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_followed_by().parse(&mut token_reader),
+            Err(ParsingError::UnexpectedEndOfSource {
+                expected_token_kinds: HashSet::from([TokenKindTest::LeftParen]),
+            })
+        );
+        assert_eq!(token_reader.offset(), 0);
+    }
+
+    #[test]
+    fn test_parser_followed_by_own_token_kinds() {
+        assert_eq!(
+            parser_followed_by().own_token_kinds(),
+            Ok(HashSet::from([TokenKindTest::LeftParen]))
+        );
+    }
+}
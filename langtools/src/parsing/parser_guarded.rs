@@ -0,0 +1,283 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use super::{parse::Parse, parsing_error::ParsingError};
+use crate::{domain::token::TokenKind, lexing::token_reader::TokenReader, tree::Tree};
+use std::{collections::HashSet, mem};
+
+// Only attempts the child if the next token's kind is one of the guard kinds, and otherwise fails without consuming
+// anything. As a choice this dispatches on the lookahead instead of backtracking out of a child that can't match. The
+// guard kinds are reported as the expected tokens, so they should cover every token the child can start with.
+pub struct ParserGuarded<TTokenKind: TokenKind, TTree: Tree> {
+    guard_kinds: HashSet<TTokenKind>,
+    child: Box<dyn Parse<TTokenKind, TTree>>,
+}
+
+pub struct ParserGuardedBuilder<TTokenKind: TokenKind, TTree: Tree> {
+    guard_kinds: HashSet<TTokenKind>,
+    child: Option<Box<dyn Parse<TTokenKind, TTree>>>,
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> ParserGuardedBuilder<TTokenKind, TTree> {
+    pub fn guard_kind(&mut self, value: TTokenKind) -> &mut Self {
+        self.guard_kinds.insert(value);
+        self
+    }
+
+    pub fn child(&mut self, value: Box<dyn Parse<TTokenKind, TTree>>) -> &mut Self {
+        self.child = Some(value);
+        self
+    }
+
+    pub fn build(&mut self) -> Result<ParserGuarded<TTokenKind, TTree>, ParsingError<TTokenKind>> {
+        if self.guard_kinds.is_empty() {
+            return Err(ParsingError::RequiredParserFieldMissing("guard_kinds"));
+        }
+
+        Ok(ParserGuarded {
+            guard_kinds: mem::take(&mut self.guard_kinds),
+            child: self
+                .child
+                .take()
+                .ok_or(ParsingError::RequiredParserFieldMissing("child"))?,
+        })
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Default for ParserGuardedBuilder<TTokenKind, TTree> {
+    fn default() -> Self {
+        Self {
+            guard_kinds: HashSet::new(),
+            child: None,
+        }
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> ParserGuarded<TTokenKind, TTree> {
+    fn check_guard(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<(), ParsingError<TTokenKind>> {
+        match token_reader.peek_next() {
+            Some(token) if self.guard_kinds.contains(&token.kind) => Ok(()),
+            Some(token) => Err(ParsingError::UnexpectedToken {
+                expected_token_kinds: self.guard_kinds.clone(),
+                actual_token: Box::new(token.clone()),
+            }),
+            None => Err(ParsingError::UnexpectedEndOfSource {
+                expected_token_kinds: self.guard_kinds.clone(),
+            }),
+        }
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Parse<TTokenKind, TTree>
+    for ParserGuarded<TTokenKind, TTree>
+{
+    fn parse(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<TTree, ParsingError<TTokenKind>> {
+        self.check_guard(token_reader)?;
+        self.child.parse(token_reader)
+    }
+
+    fn matches(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<(), ParsingError<TTokenKind>> {
+        self.check_guard(token_reader)?;
+        self.child.matches(token_reader)
+    }
+
+    fn expected_tokens_unsafe(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+        Ok(self.guard_kinds.clone())
+    }
+
+    fn sub_parsers(&self) -> Vec<&dyn Parse<TTokenKind, TTree>> {
+        Vec::from([self.child.as_ref()])
+    }
+
+    // The guard only peeks, so this can still match without consuming tokens if the child can
+    fn can_match_empty(&self) -> bool {
+        self.child.can_match_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        domain::token::Token,
+        parsing::{
+            parser_choice::ParserChoiceBuilder,
+            testing::{parser_token, tokens},
+        },
+        tree::visit::Visit,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        A,
+        B,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    struct TreeTest {
+        token: Token<()>,
+    }
+
+    impl Tree for TreeTest {
+        fn token(&self) -> &Token<()> {
+            &self.token
+        }
+    }
+
+    impl Visit for TreeTest {
+        fn visit(&self, _: crate::tree::visit::VisitCallback<Self>) {}
+
+        fn visit_mut(&self, _: crate::tree::visit::VisitCallbackMut<Self>) {}
+    }
+
+    // Fails the test if it is ever attempted
+    struct ParserUnreachable;
+
+    impl Parse<TokenKindTest, TreeTest> for ParserUnreachable {
+        fn parse(
+            &self,
+            _: &mut TokenReader<TokenKindTest>,
+        ) -> Result<TreeTest, ParsingError<TokenKindTest>> {
+            panic!("child should not be attempted")
+        }
+
+        fn expected_tokens_unsafe(
+            &self,
+        ) -> Result<HashSet<TokenKindTest>, ParsingError<TokenKindTest>> {
+            Ok(HashSet::from([TokenKindTest::A]))
+        }
+    }
+
+    #[test]
+    fn test_parser_guarded_success() {
+        let tokens = tokens(&[TokenKindTest::A]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        let parser = ParserGuardedBuilder::<TokenKindTest, TreeTest>::default()
+            .guard_kind(TokenKindTest::A)
+            .child(parser_token(TokenKindTest::A, |token| TreeTest { token }))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            parser.parse(&mut token_reader),
+            Ok(TreeTest {
+                token: tokens[0].to_kindless()
+            })
+        );
+        assert_eq!(
+            parser.expected_tokens(),
+            Ok(HashSet::from([TokenKindTest::A]))
+        );
+    }
+
+    #[test]
+    fn test_parser_guarded_child_not_attempted() {
+        let tokens = tokens(&[TokenKindTest::B]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        let parser = ParserGuardedBuilder::<TokenKindTest, TreeTest>::default()
+            .guard_kind(TokenKindTest::A)
+            .child(Box::new(ParserUnreachable))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            parser.parse(&mut token_reader),
+            Err(ParsingError::UnexpectedToken {
+                expected_token_kinds: HashSet::from([TokenKindTest::A]),
+                actual_token: Box::new(tokens[0].clone()),
+            })
+        );
+        assert_eq!(token_reader.offset(), 0);
+    }
+
+    #[test]
+    fn test_parser_guarded_end_of_source() {
+        let tokens = Vec::new();
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        let parser = ParserGuardedBuilder::<TokenKindTest, TreeTest>::default()
+            .guard_kind(TokenKindTest::A)
+            .child(Box::new(ParserUnreachable))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            parser.matches(&mut token_reader),
+            Err(ParsingError::UnexpectedEndOfSource {
+                expected_token_kinds: HashSet::from([TokenKindTest::A]),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parser_guarded_in_choice() {
+        let tokens = tokens(&[TokenKindTest::B]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        let parser = ParserChoiceBuilder::<TokenKindTest, TreeTest>::default()
+            .choices(Box::new(
+                ParserGuardedBuilder::<TokenKindTest, TreeTest>::default()
+                    .guard_kind(TokenKindTest::A)
+                    .child(Box::new(ParserUnreachable))
+                    .build()
+                    .unwrap(),
+            ))
+            .choices(parser_token(TokenKindTest::B, |token| TreeTest { token }))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            parser.parse(&mut token_reader),
+            Ok(TreeTest {
+                token: tokens[0].to_kindless()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parser_guarded_missing_guard() {
+        assert!(matches!(
+            ParserGuardedBuilder::<TokenKindTest, TreeTest>::default()
+                .child(parser_token(TokenKindTest::A, |token| TreeTest { token }))
+                .build(),
+            Err(ParsingError::RequiredParserFieldMissing("guard_kinds"))
+        ));
+    }
+}
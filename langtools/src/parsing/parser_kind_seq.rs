@@ -0,0 +1,263 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use super::{parse::Parse, parsing_error::ParsingError};
+use crate::{
+    domain::token::{Token, TokenKind},
+    lexing::token_reader::TokenReader,
+    tree::Tree,
+};
+use std::{collections::HashSet, mem};
+
+pub type ParserKindSeqAction<TTree> = fn(Vec<Token<()>>) -> TTree;
+
+pub struct ParserKindSeq<TTokenKind: TokenKind, TTree: Tree> {
+    kinds: Vec<TTokenKind>,
+    action: ParserKindSeqAction<TTree>,
+}
+
+pub struct ParserKindSeqBuilder<TTokenKind: TokenKind, TTree: Tree> {
+    kinds: Vec<TTokenKind>,
+    action: Option<ParserKindSeqAction<TTree>>,
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> ParserKindSeqBuilder<TTokenKind, TTree> {
+    pub fn kinds(&mut self, value: TTokenKind) -> &mut Self {
+        self.kinds.push(value);
+        self
+    }
+
+    pub fn action(&mut self, value: ParserKindSeqAction<TTree>) -> &mut Self {
+        self.action = Some(value);
+        self
+    }
+
+    pub fn build(&mut self) -> Result<ParserKindSeq<TTokenKind, TTree>, ParsingError<TTokenKind>> {
+        if self.kinds.is_empty() {
+            return Err(ParsingError::RequiredParserFieldMissing("kinds"));
+        }
+
+        Ok(ParserKindSeq {
+            kinds: mem::take(&mut self.kinds),
+            action: self
+                .action
+                .ok_or(ParsingError::RequiredParserFieldMissing("action"))?,
+        })
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Default for ParserKindSeqBuilder<TTokenKind, TTree> {
+    fn default() -> Self {
+        Self {
+            kinds: Vec::new(),
+            action: None,
+        }
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Parse<TTokenKind, TTree>
+    for ParserKindSeq<TTokenKind, TTree>
+{
+    fn parse(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<TTree, ParsingError<TTokenKind>> {
+        token_reader.holding(|token_reader, offset| {
+            let mut tokens = Vec::<Token<()>>::with_capacity(self.kinds.len());
+
+            for kind in self.kinds.iter() {
+                let error = match token_reader.eat_next() {
+                    Some(token) if token.kind == *kind => {
+                        tokens.push(token.to_kindless());
+                        continue;
+                    }
+                    Some(token) => ParsingError::UnexpectedToken {
+                        expected_token_kinds: HashSet::from([*kind]),
+                        actual_token: Box::new(token.clone()),
+                    },
+                    None => ParsingError::UnexpectedEndOfSource {
+                        expected_token_kinds: HashSet::from([*kind]),
+                    },
+                };
+
+                token_reader.seek(offset);
+                return Err(error);
+            }
+
+            Ok((self.action)(tokens))
+        })
+    }
+
+    fn expected_tokens_unsafe(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+        let first = self
+            .kinds
+            .first()
+            .ok_or(ParsingError::RequiredParserFieldMissing("kinds"))?;
+
+        Ok(HashSet::from([*first]))
+    }
+
+    fn own_token_kinds(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+        Ok(self.kinds.iter().copied().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, path::PathBuf};
+
+    use crate::{domain::token::Token, lexing::token_reader::TokenReader, tree::visit::Visit};
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        Equals,
+        GreaterThan,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    struct TreeTest {
+        tokens: Vec<Token<()>>,
+    }
+
+    impl Tree for TreeTest {
+        fn token(&self) -> &Token<()> {
+            &self.tokens[0]
+        }
+    }
+
+    impl Visit for TreeTest {
+        fn visit(&self, _: crate::tree::visit::VisitCallback<Self>) {}
+
+        fn visit_mut(&self, _: crate::tree::visit::VisitCallbackMut<Self>) {}
+    }
+
+    fn parser_arrow() -> ParserKindSeq<TokenKindTest, TreeTest> {
+        ParserKindSeqBuilder::<TokenKindTest, TreeTest>::default()
+            .kinds(TokenKindTest::Equals)
+            .kinds(TokenKindTest::GreaterThan)
+            .action(|tokens| TreeTest { tokens })
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parser_kind_seq_success() {
+        let tokens = Vec::from([
+            Token::new(
+                PathBuf::from("--"),
+                0,
+                1,
+                1,
+                String::from("="),
+                TokenKindTest::Equals,
+            ),
+            Token::new(
+                PathBuf::from("--"),
+                1,
+                1,
+                2,
+                String::from(">"),
+                TokenKindTest::GreaterThan,
+            ),
+        ]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_arrow().parse(&mut token_reader),
+            Ok(TreeTest {
+                tokens: Vec::from([
+                    Token::new(PathBuf::from("--"), 0, 1, 1, String::from("="), ()),
+                    Token::new(PathBuf::from("--"), 1, 1, 2, String::from(">"), ()),
+                ])
+            })
+        );
+        assert_eq!(token_reader.offset(), 2);
+    }
+
+    #[test]
+    fn test_parser_kind_seq_partial_match() {
+        let tokens = Vec::from([
+            Token::new(
+                PathBuf::from("--"),
+                0,
+                1,
+                1,
+                String::from("="),
+                TokenKindTest::Equals,
+            ),
+            Token::new(
+                PathBuf::from("--"),
+                1,
+                1,
+                2,
+                String::from("="),
+                TokenKindTest::Equals,
+            ),
+        ]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_arrow().parse(&mut token_reader),
+            Err(ParsingError::UnexpectedToken {
+                expected_token_kinds: HashSet::from([TokenKindTest::GreaterThan]),
+                actual_token: Box::new(tokens[1].clone()),
+            })
+        );
+        assert_eq!(token_reader.offset(), 0);
+    }
+
+    #[test]
+    fn test_parser_kind_seq_end_of_source() {
+        let tokens = Vec::from([Token::new(
+            PathBuf::from("--"),
+            0,
+            1,
+            1,
+            String::from("="),
+            TokenKindTest::Equals,
+        )]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_arrow().parse(&mut token_reader),
+            Err(ParsingError::UnexpectedEndOfSource {
+                expected_token_kinds: HashSet::from([TokenKindTest::GreaterThan]),
+            })
+        );
+        assert_eq!(token_reader.offset(), 0);
+    }
+
+    #[test]
+    fn test_parser_kind_seq_expected_tokens() {
+        assert_eq!(
+            parser_arrow().expected_tokens(),
+            Ok(HashSet::from([TokenKindTest::Equals]))
+        );
+    }
+}
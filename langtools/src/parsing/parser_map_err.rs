@@ -0,0 +1,271 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+use super::{parse::Parse, parsing_error::ParsingError};
+use crate::{domain::token::TokenKind, lexing::token_reader::TokenReader, tree::Tree};
+use std::collections::HashSet;
+
+pub type ParserMapErrMap<TTokenKind> = fn(ParsingError<TTokenKind>) -> ParsingError<TTokenKind>;
+
+// Parses the child and passes any error it fails with through `map`, for replacing a generic error with a domain
+// specific one like "expected a type annotation after `:`". Where the reader is left is up to the child. An error after
+// a commit point is unwrapped before it is mapped and wrapped again after, so it stays committed.
+pub struct ParserMapErr<TTokenKind: TokenKind, TTree: Tree> {
+    child: Box<dyn Parse<TTokenKind, TTree>>,
+    map: ParserMapErrMap<TTokenKind>,
+}
+
+pub struct ParserMapErrBuilder<TTokenKind: TokenKind, TTree: Tree> {
+    child: Option<Box<dyn Parse<TTokenKind, TTree>>>,
+    map: Option<ParserMapErrMap<TTokenKind>>,
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> ParserMapErrBuilder<TTokenKind, TTree> {
+    pub fn child(&mut self, value: Box<dyn Parse<TTokenKind, TTree>>) -> &mut Self {
+        self.child = Some(value);
+        self
+    }
+
+    pub fn map(&mut self, value: ParserMapErrMap<TTokenKind>) -> &mut Self {
+        self.map = Some(value);
+        self
+    }
+
+    pub fn build(&mut self) -> Result<ParserMapErr<TTokenKind, TTree>, ParsingError<TTokenKind>> {
+        Ok(ParserMapErr {
+            child: self
+                .child
+                .take()
+                .ok_or(ParsingError::RequiredParserFieldMissing("child"))?,
+            map: self
+                .map
+                .ok_or(ParsingError::RequiredParserFieldMissing("map"))?,
+        })
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Default for ParserMapErrBuilder<TTokenKind, TTree> {
+    fn default() -> Self {
+        Self {
+            child: None,
+            map: None,
+        }
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> ParserMapErr<TTokenKind, TTree> {
+    fn map_error(&self, error: ParsingError<TTokenKind>) -> ParsingError<TTokenKind> {
+        match error {
+            ParsingError::Committed(error) => (self.map)(*error).into_committed(),
+            error => (self.map)(error),
+        }
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Parse<TTokenKind, TTree>
+    for ParserMapErr<TTokenKind, TTree>
+{
+    fn parse(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<TTree, ParsingError<TTokenKind>> {
+        self.child
+            .parse(token_reader)
+            .map_err(|error| self.map_error(error))
+    }
+
+    fn matches(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<(), ParsingError<TTokenKind>> {
+        self.child
+            .matches(token_reader)
+            .map_err(|error| self.map_error(error))
+    }
+
+    fn expected_tokens_unsafe(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+        self.child.expected_tokens()
+    }
+
+    fn sub_parsers(&self) -> Vec<&dyn Parse<TTokenKind, TTree>> {
+        Vec::from([self.child.as_ref()])
+    }
+
+    fn can_match_empty(&self) -> bool {
+        self.child.can_match_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{
+        domain::token::Token,
+        messaging::message::MessageSource,
+        parsing::{parser_sequential::ParserSequentialBuilder, testing::parser_token},
+        tree::visit::Visit,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        Type,
+        Equals,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    struct TreeTest {
+        token: Token<()>,
+    }
+
+    impl Tree for TreeTest {
+        fn token(&self) -> &Token<()> {
+            &self.token
+        }
+    }
+
+    impl Visit for TreeTest {
+        fn visit(&self, _: crate::tree::visit::VisitCallback<Self>) {}
+
+        fn visit_mut(&self, _: crate::tree::visit::VisitCallbackMut<Self>) {}
+    }
+
+    fn map(error: ParsingError<TokenKindTest>) -> ParsingError<TokenKindTest> {
+        ParsingError::Custom {
+            message: String::from("expected a type annotation after `:`"),
+            token: error.token().cloned().map(Box::new),
+        }
+    }
+
+    fn parser_map_err() -> ParserMapErr<TokenKindTest, TreeTest> {
+        ParserMapErrBuilder::<TokenKindTest, TreeTest>::default()
+            .child(parser_token(TokenKindTest::Type, |token| TreeTest {
+                token,
+            }))
+            .map(map)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parser_map_err_success() {
+        let tokens = Vec::from([Token::new(
+            PathBuf::from("--"),
+            0,
+            1,
+            1,
+            String::from("int"),
+            TokenKindTest::Type,
+        )]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_map_err().parse(&mut token_reader),
+            Ok(TreeTest {
+                token: tokens[0].to_kindless()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parser_map_err_rewrites_error() {
+        let tokens = Vec::from([Token::new(
+            PathBuf::from("--"),
+            0,
+            1,
+            1,
+            String::from("="),
+            TokenKindTest::Equals,
+        )]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        let error = parser_map_err().parse(&mut token_reader).unwrap_err();
+
+        assert_eq!(
+            error,
+            ParsingError::Custom {
+                message: String::from("expected a type annotation after `:`"),
+                token: Some(Box::new(tokens[0].clone())),
+            }
+        );
+        assert_eq!(error.to_string(), "expected a type annotation after `:`");
+        assert!(matches!(
+            error.to_message(None).source,
+            MessageSource::Location { location } if location == tokens[0].location
+        ));
+    }
+
+    #[test]
+    fn test_parser_map_err_keeps_committed() {
+        let tokens = Vec::from([
+            Token::new(
+                PathBuf::from("--"),
+                0,
+                1,
+                1,
+                String::from("="),
+                TokenKindTest::Equals,
+            ),
+            Token::new(
+                PathBuf::from("--"),
+                1,
+                1,
+                2,
+                String::from("="),
+                TokenKindTest::Equals,
+            ),
+        ]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        let parser = ParserMapErrBuilder::<TokenKindTest, TreeTest>::default()
+            .child(Box::new(
+                ParserSequentialBuilder::<TokenKindTest, TreeTest>::default()
+                    .sequence(parser_token(TokenKindTest::Equals, |token| TreeTest {
+                        token,
+                    }))
+                    .commit()
+                    .sequence(parser_token(TokenKindTest::Type, |token| TreeTest {
+                        token,
+                    }))
+                    .action(|token, _| TreeTest { token })
+                    .build()
+                    .unwrap(),
+            ))
+            .map(map)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            parser.parse(&mut token_reader),
+            Err(ParsingError::Committed(Box::new(ParsingError::Custom {
+                message: String::from("expected a type annotation after `:`"),
+                token: Some(Box::new(tokens[1].clone())),
+            })))
+        );
+    }
+}
@@ -0,0 +1,213 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use super::{parse::Parse, parsing_error::ParsingError};
+use crate::{domain::token::TokenKind, lexing::token_reader::TokenReader, tree::Tree};
+use std::collections::HashSet;
+
+pub type ParserOrDefault<TTree> = fn() -> TTree;
+
+// Parses the child or, if it fails, seeks back and substitutes the tree returned by `default`. Parsing only fails if the
+// child fails after a commit point.
+pub struct ParserOr<TTokenKind: TokenKind, TTree: Tree> {
+    child: Box<dyn Parse<TTokenKind, TTree>>,
+    default: ParserOrDefault<TTree>,
+}
+
+pub struct ParserOrBuilder<TTokenKind: TokenKind, TTree: Tree> {
+    child: Option<Box<dyn Parse<TTokenKind, TTree>>>,
+    default: Option<ParserOrDefault<TTree>>,
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> ParserOrBuilder<TTokenKind, TTree> {
+    pub fn child(&mut self, value: Box<dyn Parse<TTokenKind, TTree>>) -> &mut Self {
+        self.child = Some(value);
+        self
+    }
+
+    // Named to avoid clashing with `Default::default` on the builder
+    pub fn default_tree(&mut self, value: ParserOrDefault<TTree>) -> &mut Self {
+        self.default = Some(value);
+        self
+    }
+
+    pub fn build(&mut self) -> Result<ParserOr<TTokenKind, TTree>, ParsingError<TTokenKind>> {
+        Ok(ParserOr {
+            child: self
+                .child
+                .take()
+                .ok_or(ParsingError::RequiredParserFieldMissing("child"))?,
+            default: self
+                .default
+                .ok_or(ParsingError::RequiredParserFieldMissing("default_tree"))?,
+        })
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Default for ParserOrBuilder<TTokenKind, TTree> {
+    fn default() -> Self {
+        Self {
+            child: None,
+            default: None,
+        }
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Parse<TTokenKind, TTree> for ParserOr<TTokenKind, TTree> {
+    fn parse(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<TTree, ParsingError<TTokenKind>> {
+        token_reader.holding(
+            |token_reader, offset| match self.child.parse(token_reader) {
+                Ok(child) => Ok(child),
+                Err(err) if err.is_committed() => Err(err),
+                Err(_) => {
+                    token_reader.seek(offset);
+                    Ok((self.default)())
+                }
+            },
+        )
+    }
+
+    fn expected_tokens_unsafe(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+        self.child.expected_tokens()
+    }
+
+    fn sub_parsers(&self) -> Vec<&dyn Parse<TTokenKind, TTree>> {
+        Vec::from([self.child.as_ref()])
+    }
+
+    fn can_match_empty(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{
+        domain::token::Token, parsing::parser_token::ParserTokenBuilder, tree::visit::Visit,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        A,
+        B,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    struct TreeTest {
+        token: Token<()>,
+    }
+
+    impl Tree for TreeTest {
+        fn token(&self) -> &Token<()> {
+            &self.token
+        }
+    }
+
+    impl Visit for TreeTest {
+        fn visit(&self, _: crate::tree::visit::VisitCallback<Self>) {}
+
+        fn visit_mut(&self, _: crate::tree::visit::VisitCallbackMut<Self>) {}
+    }
+
+    fn parser_or() -> ParserOr<TokenKindTest, TreeTest> {
+        ParserOrBuilder::<TokenKindTest, TreeTest>::default()
+            .child(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::A)
+                    .action(|token| TreeTest { token })
+                    .build()
+                    .unwrap(),
+            ))
+            .default_tree(|| TreeTest {
+                token: Token::new(PathBuf::from("--"), 0, 1, 1, String::from("default"), ()),
+            })
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parser_or_present() {
+        let tokens = Vec::from([Token::new(
+            PathBuf::from("--"),
+            0,
+            1,
+            1,
+            String::from("a"),
+            TokenKindTest::A,
+        )]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_or().parse(&mut token_reader),
+            Ok(TreeTest {
+                token: Token::new(PathBuf::from("--"), 0, 1, 1, String::from("a"), ())
+            })
+        );
+        assert_eq!(token_reader.offset(), 1);
+    }
+
+    #[test]
+    fn test_parser_or_absent() {
+        let tokens = Vec::from([Token::new(
+            PathBuf::from("--"),
+            0,
+            1,
+            1,
+            String::from("b"),
+            TokenKindTest::B,
+        )]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_or().parse(&mut token_reader),
+            Ok(TreeTest {
+                token: Token::new(PathBuf::from("--"), 0, 1, 1, String::from("default"), ())
+            })
+        );
+        assert_eq!(token_reader.offset(), 0);
+    }
+
+    #[test]
+    fn test_parser_or_end_of_source() {
+        let tokens = Vec::new();
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_or()
+                .parse(&mut token_reader)
+                .map(|tree| tree.token.text),
+            Ok(String::from("default"))
+        );
+    }
+}
@@ -0,0 +1,389 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+use super::{
+    operator_table::{BindingPower, OperatorTable},
+    parse::Parse,
+    parsing_error::ParsingError,
+};
+use crate::{domain::token::TokenKind, lexing::token_reader::TokenReader, tree::Tree};
+use std::collections::HashSet;
+
+// Parses expressions by precedence climbing over an operator table. Operands are parsed by `operand`, which is only
+// tried where no prefix operator matches. After each operand, postfix and infix operators are applied for as long as
+// they bind at least as tightly as the operator the operand belongs to. On failure the reader seeks back to where it
+// started.
+pub struct ParserPratt<TTokenKind: TokenKind, TTree: Tree> {
+    operand: Box<dyn Parse<TTokenKind, TTree>>,
+    table: OperatorTable<TTokenKind, TTree>,
+}
+
+pub struct ParserPrattBuilder<TTokenKind: TokenKind, TTree: Tree> {
+    operand: Option<Box<dyn Parse<TTokenKind, TTree>>>,
+    table: Option<OperatorTable<TTokenKind, TTree>>,
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> ParserPrattBuilder<TTokenKind, TTree> {
+    pub fn operand(&mut self, value: Box<dyn Parse<TTokenKind, TTree>>) -> &mut Self {
+        self.operand = Some(value);
+        self
+    }
+
+    pub fn table(&mut self, value: OperatorTable<TTokenKind, TTree>) -> &mut Self {
+        self.table = Some(value);
+        self
+    }
+
+    pub fn build(&mut self) -> Result<ParserPratt<TTokenKind, TTree>, ParsingError<TTokenKind>> {
+        let table = self
+            .table
+            .take()
+            .ok_or(ParsingError::RequiredParserFieldMissing("table"))?;
+
+        if let Some(token_kind) = table.find_conflict() {
+            return Err(ParsingError::OperatorConflict { token_kind });
+        }
+
+        Ok(ParserPratt {
+            operand: self
+                .operand
+                .take()
+                .ok_or(ParsingError::RequiredParserFieldMissing("operand"))?,
+            table,
+        })
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Default for ParserPrattBuilder<TTokenKind, TTree> {
+    fn default() -> Self {
+        Self {
+            operand: None,
+            table: None,
+        }
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> ParserPratt<TTokenKind, TTree> {
+    fn parse_expression(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+        min_binding_power: BindingPower,
+    ) -> Result<TTree, ParsingError<TTokenKind>> {
+        let prefix = token_reader
+            .peek_next()
+            .and_then(|token| self.table.try_get_prefix(&token.kind));
+
+        let mut result = if let Some(prefix) = prefix {
+            let token = token_reader
+                .eat_next()
+                .expect("peeked token should be available")
+                .to_kindless();
+            let operand = self.parse_expression(token_reader, prefix.binding_power)?;
+
+            (prefix.action)(token, operand)
+        } else {
+            self.operand.parse(token_reader)?
+        };
+
+        while let Some(token_kind) = token_reader.peek_next().map(|token| token.kind) {
+            if let Some(postfix) = self.table.try_get_postfix(&token_kind) {
+                if postfix.binding_power < min_binding_power {
+                    break;
+                }
+
+                let token = token_reader
+                    .eat_next()
+                    .expect("peeked token should be available")
+                    .to_kindless();
+
+                result = (postfix.action)(token, result);
+            } else if let Some(infix) = self.table.try_get_infix(&token_kind) {
+                if infix.left_binding_power < min_binding_power {
+                    break;
+                }
+
+                let token = token_reader
+                    .eat_next()
+                    .expect("peeked token should be available")
+                    .to_kindless();
+                let right = self.parse_expression(token_reader, infix.right_binding_power)?;
+
+                result = (infix.action)(token, result, right);
+            } else {
+                break;
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Parse<TTokenKind, TTree>
+    for ParserPratt<TTokenKind, TTree>
+{
+    fn parse(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<TTree, ParsingError<TTokenKind>> {
+        token_reader.holding(|token_reader, offset| {
+            let result = self.parse_expression(token_reader, 0);
+
+            if result.is_err() {
+                token_reader.seek(offset);
+            }
+
+            result
+        })
+    }
+
+    fn expected_tokens_unsafe(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+        let mut result = self.operand.expected_tokens()?;
+        result.extend(self.table.prefix_token_kinds());
+        Ok(result)
+    }
+
+    fn sub_parsers(&self) -> Vec<&dyn Parse<TTokenKind, TTree>> {
+        Vec::from([self.operand.as_ref()])
+    }
+
+    fn can_match_empty(&self) -> bool {
+        self.operand.can_match_empty()
+    }
+
+    fn own_token_kinds(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+        Ok(self.table.token_kinds())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        domain::token::Token,
+        parsing::{parser_token::ParserTokenBuilder, testing::tokens},
+        tree::visit::Visit,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        Number,
+        Plus,
+        Minus,
+        Star,
+        Slash,
+        Bang,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    enum TreeTest {
+        Number(Token<()>),
+        Unary(Token<()>, Box<TreeTest>),
+        Binary(Token<()>, Box<TreeTest>, Box<TreeTest>),
+    }
+
+    impl Tree for TreeTest {
+        fn token(&self) -> &Token<()> {
+            match self {
+                TreeTest::Number(token) => token,
+                TreeTest::Unary(token, _) => token,
+                TreeTest::Binary(token, _, _) => token,
+            }
+        }
+    }
+
+    impl Visit for TreeTest {
+        fn visit(&self, _: crate::tree::visit::VisitCallback<Self>) {}
+
+        fn visit_mut(&self, _: crate::tree::visit::VisitCallbackMut<Self>) {}
+    }
+
+    fn unary(token: Token<()>, operand: TreeTest) -> TreeTest {
+        TreeTest::Unary(token, Box::new(operand))
+    }
+
+    fn binary(token: Token<()>, left: TreeTest, right: TreeTest) -> TreeTest {
+        TreeTest::Binary(token, Box::new(left), Box::new(right))
+    }
+
+    fn arithmetic_table() -> OperatorTable<TokenKindTest, TreeTest> {
+        let mut table = OperatorTable::new();
+
+        table
+            .add_infix(TokenKindTest::Plus, 1, 2, binary)
+            .add_infix(TokenKindTest::Minus, 1, 2, binary)
+            .add_infix(TokenKindTest::Star, 3, 4, binary)
+            .add_infix(TokenKindTest::Slash, 3, 4, binary)
+            .add_prefix(TokenKindTest::Minus, 5, unary);
+
+        table
+    }
+
+    fn parser_pratt(
+        table: OperatorTable<TokenKindTest, TreeTest>,
+    ) -> ParserPratt<TokenKindTest, TreeTest> {
+        ParserPrattBuilder::<TokenKindTest, TreeTest>::default()
+            .operand(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::Number)
+                    .action(TreeTest::Number)
+                    .build()
+                    .unwrap(),
+            ))
+            .table(table)
+            .build()
+            .unwrap()
+    }
+
+    fn number(token: &Token<TokenKindTest>) -> TreeTest {
+        TreeTest::Number(token.to_kindless())
+    }
+
+    #[test]
+    fn test_parser_pratt_precedence() {
+        // -1 + 2 * 3
+        let tokens = tokens(&[
+            TokenKindTest::Minus,
+            TokenKindTest::Number,
+            TokenKindTest::Plus,
+            TokenKindTest::Number,
+            TokenKindTest::Star,
+            TokenKindTest::Number,
+        ]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_pratt(arithmetic_table()).parse(&mut token_reader),
+            Ok(binary(
+                tokens[2].to_kindless(),
+                unary(tokens[0].to_kindless(), number(&tokens[1])),
+                binary(
+                    tokens[4].to_kindless(),
+                    number(&tokens[3]),
+                    number(&tokens[5])
+                )
+            ))
+        );
+        assert!(token_reader.at_end());
+    }
+
+    #[test]
+    fn test_parser_pratt_left_associative() {
+        // 1 - 2 - 3
+        let tokens = tokens(&[
+            TokenKindTest::Number,
+            TokenKindTest::Minus,
+            TokenKindTest::Number,
+            TokenKindTest::Minus,
+            TokenKindTest::Number,
+        ]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_pratt(arithmetic_table()).parse(&mut token_reader),
+            Ok(binary(
+                tokens[3].to_kindless(),
+                binary(
+                    tokens[1].to_kindless(),
+                    number(&tokens[0]),
+                    number(&tokens[2])
+                ),
+                number(&tokens[4])
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parser_pratt_postfix() {
+        // 1 + 2!
+        let tokens = tokens(&[
+            TokenKindTest::Number,
+            TokenKindTest::Plus,
+            TokenKindTest::Number,
+            TokenKindTest::Bang,
+        ]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        let mut table = arithmetic_table();
+        table.add_postfix(TokenKindTest::Bang, 7, unary);
+
+        assert_eq!(
+            parser_pratt(table).parse(&mut token_reader),
+            Ok(binary(
+                tokens[1].to_kindless(),
+                number(&tokens[0]),
+                unary(tokens[3].to_kindless(), number(&tokens[2]))
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parser_pratt_missing_operand() {
+        // 1 +
+        let tokens = tokens(&[TokenKindTest::Number, TokenKindTest::Plus]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_pratt(arithmetic_table()).parse(&mut token_reader),
+            Err(ParsingError::UnexpectedEndOfSource {
+                expected_token_kinds: HashSet::from([TokenKindTest::Number]),
+            })
+        );
+        assert_eq!(token_reader.offset(), 0);
+    }
+
+    #[test]
+    fn test_parser_pratt_expected_tokens() {
+        assert_eq!(
+            parser_pratt(arithmetic_table()).expected_tokens(),
+            Ok(HashSet::from([TokenKindTest::Number, TokenKindTest::Minus]))
+        );
+    }
+
+    #[test]
+    fn test_parser_pratt_conflict() {
+        let mut table = arithmetic_table();
+        table.add_postfix(TokenKindTest::Plus, 7, unary);
+
+        assert_eq!(
+            ParserPrattBuilder::<TokenKindTest, TreeTest>::default()
+                .operand(Box::new(
+                    ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                        .token_kind(TokenKindTest::Number)
+                        .action(TreeTest::Number)
+                        .build()
+                        .unwrap(),
+                ))
+                .table(table)
+                .build()
+                .err(),
+            Some(ParsingError::OperatorConflict {
+                token_kind: TokenKindTest::Plus
+            })
+        );
+    }
+}
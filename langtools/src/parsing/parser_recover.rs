@@ -0,0 +1,259 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use super::{parse::Parse, parsing_error::ParsingError};
+use crate::{domain::token::TokenKind, lexing::token_reader::TokenReader, tree::ErrorTree};
+use std::{collections::HashSet, mem};
+
+// A recovery point for parsing with `parse_recovering`. If `child` fails, even after a commit point, its error is
+// recorded in the token reader as a message and an error node is returned in place of its tree. The tokens it failed on
+// are skipped up to the next token with one of the `sync` kinds, which is left for whatever comes next. At least one
+// token is skipped so that a repetition of recovering parsers always makes progress.
+//
+// The error node is at the first token that the child was given. If there are no tokens left then recovering wouldn't
+// make any progress, so the error is returned instead.
+pub struct ParserRecover<TTokenKind: TokenKind, TTree: ErrorTree> {
+    child: Box<dyn Parse<TTokenKind, TTree>>,
+    sync: HashSet<TTokenKind>,
+}
+
+pub struct ParserRecoverBuilder<TTokenKind: TokenKind, TTree: ErrorTree> {
+    child: Option<Box<dyn Parse<TTokenKind, TTree>>>,
+    sync: HashSet<TTokenKind>,
+}
+
+impl<TTokenKind: TokenKind, TTree: ErrorTree> ParserRecoverBuilder<TTokenKind, TTree> {
+    pub fn child(&mut self, value: Box<dyn Parse<TTokenKind, TTree>>) -> &mut Self {
+        self.child = Some(value);
+        self
+    }
+
+    pub fn sync(&mut self, value: TTokenKind) -> &mut Self {
+        self.sync.insert(value);
+        self
+    }
+
+    pub fn build(&mut self) -> Result<ParserRecover<TTokenKind, TTree>, ParsingError<TTokenKind>> {
+        Ok(ParserRecover {
+            child: self
+                .child
+                .take()
+                .ok_or(ParsingError::RequiredParserFieldMissing("child"))?,
+            sync: mem::take(&mut self.sync),
+        })
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: ErrorTree> Default for ParserRecoverBuilder<TTokenKind, TTree> {
+    fn default() -> Self {
+        Self {
+            child: None,
+            sync: HashSet::new(),
+        }
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: ErrorTree> Parse<TTokenKind, TTree>
+    for ParserRecover<TTokenKind, TTree>
+{
+    fn parse(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<TTree, ParsingError<TTokenKind>> {
+        token_reader.holding(|token_reader, offset| {
+            let err = match self.child.parse(token_reader) {
+                Ok(tree) => return Ok(tree),
+                Err(err) => err,
+            };
+
+            token_reader.seek(offset);
+
+            let location = match token_reader.eat_next() {
+                Some(token) => token.location.clone(),
+                None => return Err(err),
+            };
+
+            while token_reader
+                .peek_next()
+                .is_some_and(|token| !self.sync.contains(&token.kind))
+            {
+                token_reader.eat_next();
+            }
+
+            token_reader.record_recovered(offset, err.to_message(Some(location.clone())));
+
+            Ok(TTree::error_node(location))
+        })
+    }
+
+    fn expected_tokens_unsafe(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+        self.child.expected_tokens()
+    }
+
+    fn sub_parsers(&self) -> Vec<&dyn Parse<TTokenKind, TTree>> {
+        Vec::from([self.child.as_ref()])
+    }
+
+    fn can_match_empty(&self) -> bool {
+        self.child.can_match_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        domain::{source_location::SourceLocation, token::Token},
+        messaging::{message::MessageSource, message_context::MessageContext},
+        parsing::{
+            parse::parse_recovering, parser_kind_seq::ParserKindSeqBuilder,
+            parser_repetition::ParserRepetitionBuilder, testing::tokens,
+        },
+        tree::{visit::Visit, Tree},
+    };
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        A,
+        B,
+        C,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    enum TreeTest {
+        Statement(Token<()>),
+        Error(Token<()>),
+        Statements(Token<()>, Vec<TreeTest>),
+    }
+
+    impl Tree for TreeTest {
+        fn token(&self) -> &Token<()> {
+            match self {
+                TreeTest::Statement(token) => token,
+                TreeTest::Error(token) => token,
+                TreeTest::Statements(token, _) => token,
+            }
+        }
+    }
+
+    impl ErrorTree for TreeTest {
+        fn error_node(location: SourceLocation) -> Self {
+            TreeTest::Error(Token::new_from_location(location, String::new(), ()))
+        }
+    }
+
+    impl Visit for TreeTest {
+        fn visit(&self, _: crate::tree::visit::VisitCallback<Self>) {}
+
+        fn visit_mut(&self, _: crate::tree::visit::VisitCallbackMut<Self>) {}
+    }
+
+    // Statements are `A B`, and recovery skips to the next `A`
+    fn parser_statements() -> Box<dyn Parse<TokenKindTest, TreeTest>> {
+        Box::new(
+            ParserRepetitionBuilder::<TokenKindTest, TreeTest>::default()
+                .item(Box::new(
+                    ParserRecoverBuilder::<TokenKindTest, TreeTest>::default()
+                        .child(Box::new(
+                            ParserKindSeqBuilder::<TokenKindTest, TreeTest>::default()
+                                .kinds(TokenKindTest::A)
+                                .kinds(TokenKindTest::B)
+                                .action(|tokens| TreeTest::Statement(tokens[0].clone()))
+                                .build()
+                                .unwrap(),
+                        ))
+                        .sync(TokenKindTest::A)
+                        .build()
+                        .unwrap(),
+                ))
+                .action(|items, span| {
+                    TreeTest::Statements(
+                        Token::new_from_location(span.unwrap().start, String::new(), ()),
+                        items,
+                    )
+                })
+                .build()
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_parser_recover_partial_tree() {
+        let tokens = tokens(&[
+            TokenKindTest::A,
+            TokenKindTest::B,
+            TokenKindTest::A,
+            TokenKindTest::C,
+            TokenKindTest::C,
+            TokenKindTest::A,
+            TokenKindTest::B,
+        ]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+        let mut message_context = MessageContext::new();
+
+        let tree = parse_recovering(
+            parser_statements().as_ref(),
+            &mut token_reader,
+            &mut message_context,
+        );
+
+        assert_eq!(
+            tree,
+            Some(TreeTest::Statements(
+                Token::new_from_location(tokens[0].location.clone(), String::new(), ()),
+                Vec::from([
+                    TreeTest::Statement(tokens[0].to_kindless()),
+                    TreeTest::Error(Token::new_from_location(
+                        tokens[2].location.clone(),
+                        String::new(),
+                        ()
+                    )),
+                    TreeTest::Statement(tokens[5].to_kindless()),
+                ])
+            ))
+        );
+        assert!(token_reader.at_end());
+
+        assert_eq!(message_context.messages.len(), 1);
+        assert!(matches!(
+            &message_context.messages[0].source,
+            MessageSource::Location { location } if *location == tokens[3].location
+        ));
+    }
+
+    #[test]
+    fn test_parser_recover_backtracked() {
+        let tokens = tokens(&[TokenKindTest::A, TokenKindTest::C]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        // Seeking back before the error means the parse that recovered from it is being abandoned
+        assert!(parser_statements().parse(&mut token_reader).is_ok());
+        token_reader.seek(0);
+
+        assert!(token_reader.take_recovered().is_empty());
+    }
+}
@@ -0,0 +1,328 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use super::{parse::Parse, parsing_error::ParsingError};
+use crate::{
+    domain::{source_span::SourceSpan, token::TokenKind},
+    lexing::token_reader::TokenReader,
+    tree::Tree,
+};
+use std::collections::HashSet;
+
+// Called with the items parsed and the span they cover, the same as for `ParserRepetition`
+pub type ParserRepeatBoundedAction<TTree> = fn(Vec<TTree>, Option<SourceSpan>) -> TTree;
+
+// Parses `child` at least `min` times and at most `max` times. Once `max` items have been parsed it stops without
+// trying another, even if one would match, leaving those tokens for whatever comes next. Without a maximum it
+// continues until an item fails like `ParserRepetition`.
+//
+// If an item fails before `min` items have been parsed, the token reader is rewound to where the repetition started
+// and the item's error is returned. An item that succeeds without consuming anything ends the repetition and counts
+// for the rest of the minimum, since it would match the same way every time.
+pub struct ParserRepeatBounded<TTokenKind: TokenKind, TTree: Tree> {
+    child: Box<dyn Parse<TTokenKind, TTree>>,
+    min: usize,
+    max: Option<usize>,
+    action: ParserRepeatBoundedAction<TTree>,
+}
+
+pub struct ParserRepeatBoundedBuilder<TTokenKind: TokenKind, TTree: Tree> {
+    child: Option<Box<dyn Parse<TTokenKind, TTree>>>,
+    min: usize,
+    max: Option<usize>,
+    action: Option<ParserRepeatBoundedAction<TTree>>,
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> ParserRepeatBoundedBuilder<TTokenKind, TTree> {
+    pub fn child(&mut self, value: Box<dyn Parse<TTokenKind, TTree>>) -> &mut Self {
+        self.child = Some(value);
+        self
+    }
+
+    pub fn min(&mut self, value: usize) -> &mut Self {
+        self.min = value;
+        self
+    }
+
+    pub fn max(&mut self, value: Option<usize>) -> &mut Self {
+        self.max = value;
+        self
+    }
+
+    pub fn action(&mut self, value: ParserRepeatBoundedAction<TTree>) -> &mut Self {
+        self.action = Some(value);
+        self
+    }
+
+    pub fn build(
+        &mut self,
+    ) -> Result<ParserRepeatBounded<TTokenKind, TTree>, ParsingError<TTokenKind>> {
+        if let Some(max) = self.max {
+            if max < self.min {
+                return Err(ParsingError::InvalidRepetitionBounds { min: self.min, max });
+            }
+        }
+
+        Ok(ParserRepeatBounded {
+            child: self
+                .child
+                .take()
+                .ok_or(ParsingError::RequiredParserFieldMissing("child"))?,
+            min: self.min,
+            max: self.max,
+            action: self
+                .action
+                .ok_or(ParsingError::RequiredParserFieldMissing("action"))?,
+        })
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Default for ParserRepeatBoundedBuilder<TTokenKind, TTree> {
+    fn default() -> Self {
+        Self {
+            child: None,
+            min: 0,
+            max: None,
+            action: None,
+        }
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Parse<TTokenKind, TTree>
+    for ParserRepeatBounded<TTokenKind, TTree>
+{
+    fn parse(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<TTree, ParsingError<TTokenKind>> {
+        token_reader.holding(|token_reader, offset| {
+            let location = token_reader.location();
+
+            let mut items = Vec::new();
+
+            while self.max.is_none_or(|max| items.len() < max) {
+                let item_offset = token_reader.offset();
+
+                match self.child.parse(token_reader) {
+                    Ok(item) => {
+                        items.push(item);
+
+                        if token_reader.offset() == item_offset {
+                            break;
+                        }
+                    }
+                    Err(err) if err.is_committed() => return Err(err),
+                    Err(err) if items.len() < self.min => {
+                        token_reader.seek(offset);
+                        return Err(err);
+                    }
+                    Err(_) => {
+                        token_reader.seek(item_offset);
+                        break;
+                    }
+                }
+            }
+
+            let span = token_reader
+                .span_since(offset)
+                .or_else(|| location.map(|location| SourceSpan::new(location.clone(), location)));
+
+            Ok((self.action)(items, span))
+        })
+    }
+
+    fn expected_tokens_unsafe(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+        self.child.expected_tokens()
+    }
+
+    fn sub_parsers(&self) -> Vec<&dyn Parse<TTokenKind, TTree>> {
+        Vec::from([self.child.as_ref()])
+    }
+
+    fn can_match_empty(&self) -> bool {
+        self.min == 0 || self.child.can_match_empty()
+    }
+
+    fn repeated_parsers(&self) -> Vec<&dyn Parse<TTokenKind, TTree>> {
+        Vec::from([self.child.as_ref()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{
+        domain::{source_location::SourceLocation, token::Token},
+        parsing::{parser_token::ParserTokenBuilder, testing::tokens},
+        tree::visit::Visit,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        Digit,
+        Other,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    enum TreeTest {
+        Digit(Token<()>),
+        Digits(Token<()>, Vec<TreeTest>),
+    }
+
+    impl Tree for TreeTest {
+        fn token(&self) -> &Token<()> {
+            match self {
+                TreeTest::Digit(token) => token,
+                TreeTest::Digits(token, _) => token,
+            }
+        }
+    }
+
+    impl Visit for TreeTest {
+        fn visit(&self, _: crate::tree::visit::VisitCallback<Self>) {}
+
+        fn visit_mut(&self, _: crate::tree::visit::VisitCallbackMut<Self>) {}
+    }
+
+    fn parser_repeat_bounded(
+        min: usize,
+        max: Option<usize>,
+    ) -> ParserRepeatBounded<TokenKindTest, TreeTest> {
+        ParserRepeatBoundedBuilder::<TokenKindTest, TreeTest>::default()
+            .child(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::Digit)
+                    .action(TreeTest::Digit)
+                    .build()
+                    .unwrap(),
+            ))
+            .min(min)
+            .max(max)
+            .action(|items, span| {
+                let location = span.map_or_else(
+                    || SourceLocation::new(PathBuf::from("--"), 0, 1, 1),
+                    |span| span.start,
+                );
+
+                TreeTest::Digits(Token::new_from_location(location, String::new(), ()), items)
+            })
+            .build()
+            .unwrap()
+    }
+
+    fn item_count(
+        parser: &ParserRepeatBounded<TokenKindTest, TreeTest>,
+        token_reader: &mut TokenReader<TokenKindTest>,
+    ) -> Result<usize, ParsingError<TokenKindTest>> {
+        parser.parse(token_reader).map(|tree| match tree {
+            TreeTest::Digits(_, items) => items.len(),
+            TreeTest::Digit(_) => panic!("expected digits"),
+        })
+    }
+
+    #[test]
+    fn test_parser_repeat_bounded_exact() {
+        let tokens = tokens(&[TokenKindTest::Digit; 4]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            item_count(&parser_repeat_bounded(4, Some(4)), &mut token_reader),
+            Ok(4)
+        );
+        assert!(token_reader.at_end());
+    }
+
+    #[test]
+    fn test_parser_repeat_bounded_under_min() {
+        let tokens = tokens(&[
+            TokenKindTest::Digit,
+            TokenKindTest::Digit,
+            TokenKindTest::Other,
+        ]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            item_count(&parser_repeat_bounded(4, Some(4)), &mut token_reader),
+            Err(ParsingError::UnexpectedToken {
+                expected_token_kinds: HashSet::from([TokenKindTest::Digit]),
+                actual_token: Box::new(tokens[2].clone()),
+            })
+        );
+        assert_eq!(token_reader.offset(), 0);
+    }
+
+    #[test]
+    fn test_parser_repeat_bounded_over_max() {
+        let tokens = tokens(&[TokenKindTest::Digit; 5]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            item_count(&parser_repeat_bounded(1, Some(3)), &mut token_reader),
+            Ok(3)
+        );
+        assert_eq!(token_reader.offset(), 3);
+    }
+
+    #[test]
+    fn test_parser_repeat_bounded_no_tokens() {
+        let tokens = Vec::new();
+
+        for (min, max) in [(0, None), (0, Some(2)), (0, Some(0))] {
+            let mut tokens_iter = tokens.iter();
+            let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+            assert_eq!(
+                item_count(&parser_repeat_bounded(min, max), &mut token_reader),
+                Ok(0)
+            );
+        }
+
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            item_count(&parser_repeat_bounded(1, None), &mut token_reader),
+            Err(ParsingError::UnexpectedEndOfSource {
+                expected_token_kinds: HashSet::from([TokenKindTest::Digit]),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parser_repeat_bounded_invalid_bounds() {
+        assert!(matches!(
+            ParserRepeatBoundedBuilder::<TokenKindTest, TreeTest>::default()
+                .min(2)
+                .max(Some(1))
+                .build(),
+            Err(ParsingError::InvalidRepetitionBounds { min: 2, max: 1 })
+        ));
+    }
+}
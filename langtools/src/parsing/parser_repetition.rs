@@ -0,0 +1,277 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+use super::{parse::Parse, parsing_error::ParsingError};
+use crate::{
+    domain::{source_span::SourceSpan, token::TokenKind},
+    lexing::token_reader::TokenReader,
+    tree::Tree,
+};
+use std::collections::HashSet;
+
+// Called with the items parsed and the span they cover. With no items the span is empty at the position where the
+// first item would have started, or `None` if the token stream has no tokens at all since there is then no position.
+pub type ParserRepetitionAction<TTree> = fn(Vec<TTree>, Option<SourceSpan>) -> TTree;
+
+// Parses `item` zero or more times, stopping at the first item that fails to parse or that succeeds without consuming
+// anything. A failed item is rewound so that whatever comes after the repetition can try the same tokens, unless it
+// failed after a commit point, in which case the repetition fails too.
+pub struct ParserRepetition<TTokenKind: TokenKind, TTree: Tree> {
+    item: Box<dyn Parse<TTokenKind, TTree>>,
+    action: ParserRepetitionAction<TTree>,
+}
+
+pub struct ParserRepetitionBuilder<TTokenKind: TokenKind, TTree: Tree> {
+    item: Option<Box<dyn Parse<TTokenKind, TTree>>>,
+    action: Option<ParserRepetitionAction<TTree>>,
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> ParserRepetitionBuilder<TTokenKind, TTree> {
+    pub fn item(&mut self, value: Box<dyn Parse<TTokenKind, TTree>>) -> &mut Self {
+        self.item = Some(value);
+        self
+    }
+
+    pub fn action(&mut self, value: ParserRepetitionAction<TTree>) -> &mut Self {
+        self.action = Some(value);
+        self
+    }
+
+    pub fn build(
+        &mut self,
+    ) -> Result<ParserRepetition<TTokenKind, TTree>, ParsingError<TTokenKind>> {
+        Ok(ParserRepetition {
+            item: self
+                .item
+                .take()
+                .ok_or(ParsingError::RequiredParserFieldMissing("item"))?,
+            action: self
+                .action
+                .ok_or(ParsingError::RequiredParserFieldMissing("action"))?,
+        })
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Default for ParserRepetitionBuilder<TTokenKind, TTree> {
+    fn default() -> Self {
+        Self {
+            item: None,
+            action: None,
+        }
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Parse<TTokenKind, TTree>
+    for ParserRepetition<TTokenKind, TTree>
+{
+    fn parse(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<TTree, ParsingError<TTokenKind>> {
+        token_reader.holding(|token_reader, offset| {
+            let location = token_reader.location();
+
+            let mut items = Vec::new();
+
+            loop {
+                let item_offset = token_reader.offset();
+
+                match self.item.parse(token_reader) {
+                    Ok(item) if token_reader.offset() > item_offset => items.push(item),
+                    Ok(_) => break,
+                    Err(err) if err.is_committed() => return Err(err),
+                    Err(_) => {
+                        token_reader.seek(item_offset);
+                        break;
+                    }
+                }
+            }
+
+            let span = token_reader
+                .span_since(offset)
+                .or_else(|| location.map(|location| SourceSpan::new(location.clone(), location)));
+
+            Ok((self.action)(items, span))
+        })
+    }
+
+    fn expected_tokens_unsafe(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+        self.item.expected_tokens()
+    }
+
+    fn sub_parsers(&self) -> Vec<&dyn Parse<TTokenKind, TTree>> {
+        Vec::from([self.item.as_ref()])
+    }
+
+    fn can_match_empty(&self) -> bool {
+        true
+    }
+
+    fn repeated_parsers(&self) -> Vec<&dyn Parse<TTokenKind, TTree>> {
+        Vec::from([self.item.as_ref()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{
+        domain::{source_location::SourceLocation, token::Token},
+        parsing::{parser_token::ParserTokenBuilder, testing::tokens},
+        tree::visit::Visit,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        Item,
+        RightBracket,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    enum TreeTest {
+        Item(Token<()>),
+        List(Token<()>, Vec<TreeTest>, Option<SourceSpan>),
+    }
+
+    impl Tree for TreeTest {
+        fn token(&self) -> &Token<()> {
+            match self {
+                TreeTest::Item(token) => token,
+                TreeTest::List(token, _, _) => token,
+            }
+        }
+    }
+
+    impl Visit for TreeTest {
+        fn visit(&self, _: crate::tree::visit::VisitCallback<Self>) {}
+
+        fn visit_mut(&self, _: crate::tree::visit::VisitCallbackMut<Self>) {}
+    }
+
+    fn parser_repetition() -> ParserRepetition<TokenKindTest, TreeTest> {
+        ParserRepetitionBuilder::<TokenKindTest, TreeTest>::default()
+            .item(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::Item)
+                    .action(TreeTest::Item)
+                    .build()
+                    .unwrap(),
+            ))
+            .action(|items, span| {
+                let location = span.as_ref().map_or_else(
+                    || SourceLocation::new(PathBuf::from("--"), 0, 1, 1),
+                    |span| span.start.clone(),
+                );
+
+                TreeTest::List(
+                    Token::new_from_location(location, String::new(), ()),
+                    items,
+                    span,
+                )
+            })
+            .build()
+            .unwrap()
+    }
+
+    fn span_of(tree: Result<TreeTest, ParsingError<TokenKindTest>>) -> Option<SourceSpan> {
+        match tree {
+            Ok(TreeTest::List(_, _, span)) => span,
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_parser_repetition_several() {
+        let tokens = tokens(&[
+            TokenKindTest::Item,
+            TokenKindTest::Item,
+            TokenKindTest::Item,
+            TokenKindTest::RightBracket,
+        ]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            span_of(parser_repetition().parse(&mut token_reader)),
+            Some(SourceSpan::new(
+                SourceLocation::new(PathBuf::from("--"), 0, 1, 1),
+                SourceLocation::new(PathBuf::from("--"), 3, 1, 4)
+            ))
+        );
+        assert_eq!(token_reader.offset(), 3);
+    }
+
+    #[test]
+    fn test_parser_repetition_empty() {
+        let tokens = tokens(&[TokenKindTest::RightBracket]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        let start = SourceLocation::new(PathBuf::from("--"), 0, 1, 1);
+
+        assert_eq!(
+            parser_repetition().parse(&mut token_reader),
+            Ok(TreeTest::List(
+                Token::new_from_location(start.clone(), String::new(), ()),
+                Vec::new(),
+                Some(SourceSpan::new(start.clone(), start))
+            ))
+        );
+        assert_eq!(token_reader.offset(), 0);
+    }
+
+    #[test]
+    fn test_parser_repetition_empty_at_end() {
+        let tokens = tokens(&[TokenKindTest::RightBracket]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        token_reader.eat_next();
+
+        let end = SourceLocation::new(PathBuf::from("--"), 1, 1, 2);
+
+        assert_eq!(
+            span_of(parser_repetition().parse(&mut token_reader)),
+            Some(SourceSpan::new(end.clone(), end))
+        );
+    }
+
+    #[test]
+    fn test_parser_repetition_no_tokens() {
+        let tokens = Vec::new();
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_repetition().parse(&mut token_reader),
+            Ok(TreeTest::List(
+                Token::new(PathBuf::from("--"), 0, 1, 1, String::new(), ()),
+                Vec::new(),
+                None
+            ))
+        );
+    }
+}
@@ -22,21 +22,43 @@
 
 use super::{parse::Parse, parsing_error::ParsingError};
 use crate::{
-    domain::token::{Token, TokenKind},
+    domain::{
+        source_span::SourceSpan,
+        token::{Token, TokenKind},
+    },
     tree::Tree,
 };
 use std::{collections::HashSet, mem};
 
 pub type ParserSequentialAction<TTree> = fn(Token<()>, Vec<TTree>) -> TTree;
+pub type ParserSequentialSpanAction<TTree> = fn(Token<()>, Vec<TTree>, SourceSpan) -> TTree;
 
+enum ParserSequentialActionKind<TTree> {
+    Plain(ParserSequentialAction<TTree>),
+    Span(ParserSequentialSpanAction<TTree>),
+}
+
+impl<TTree> Clone for ParserSequentialActionKind<TTree> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<TTree> Copy for ParserSequentialActionKind<TTree> {}
+
+// With a commit point, a failure in any parser after it is wrapped in `ParsingError::Committed` so that enclosing
+// choices report it instead of backtracking into other alternatives. This is the cut operator from PEGs: once the
+// parsers before the commit point have matched, this is known to be the right alternative.
 pub struct ParserSequential<TTokenKind: TokenKind, TTree: Tree> {
     sequence: Vec<Box<dyn Parse<TTokenKind, TTree>>>,
-    action: ParserSequentialAction<TTree>,
+    action: ParserSequentialActionKind<TTree>,
+    commit_index: Option<usize>,
 }
 
 pub struct ParserSequentialBuilder<TTokenKind: TokenKind, TTree: Tree> {
     sequence: Vec<Box<dyn Parse<TTokenKind, TTree>>>,
-    action: Option<ParserSequentialAction<TTree>>,
+    action: Option<ParserSequentialActionKind<TTree>>,
+    commit_index: Option<usize>,
 }
 
 impl<TTokenKind: TokenKind, TTree: Tree> ParserSequentialBuilder<TTokenKind, TTree> {
@@ -45,8 +67,19 @@ impl<TTokenKind: TokenKind, TTree: Tree> ParserSequentialBuilder<TTokenKind, TTr
         self
     }
 
+    // Marks the commit point as being after the parsers added so far
+    pub fn commit(&mut self) -> &mut Self {
+        self.commit_index = Some(self.sequence.len());
+        self
+    }
+
     pub fn action(&mut self, value: ParserSequentialAction<TTree>) -> &mut Self {
-        self.action = Some(value);
+        self.action = Some(ParserSequentialActionKind::Plain(value));
+        self
+    }
+
+    pub fn action_with_span(&mut self, value: ParserSequentialSpanAction<TTree>) -> &mut Self {
+        self.action = Some(ParserSequentialActionKind::Span(value));
         self
     }
 
@@ -62,6 +95,7 @@ impl<TTokenKind: TokenKind, TTree: Tree> ParserSequentialBuilder<TTokenKind, TTr
             action: self
                 .action
                 .ok_or(ParsingError::RequiredParserFieldMissing("action"))?,
+            commit_index: self.commit_index.take(),
         })
     }
 }
@@ -71,6 +105,24 @@ impl<TTokenKind: TokenKind, TTree: Tree> Default for ParserSequentialBuilder<TTo
         Self {
             sequence: Vec::new(),
             action: None,
+            commit_index: None,
+        }
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> ParserSequential<TTokenKind, TTree> {
+    fn commit_error(
+        &self,
+        index: usize,
+        err: ParsingError<TTokenKind>,
+    ) -> ParsingError<TTokenKind> {
+        if self
+            .commit_index
+            .is_some_and(|commit_index| index >= commit_index)
+        {
+            err.into_committed()
+        } else {
+            err
         }
     }
 }
@@ -82,29 +134,53 @@ impl<TTokenKind: TokenKind, TTree: Tree> Parse<TTokenKind, TTree>
         &self,
         token_reader: &mut crate::lexing::token_reader::TokenReader<TTokenKind>,
     ) -> Result<TTree, ParsingError<TTokenKind>> {
-        let mut children = Vec::<TTree>::new();
+        token_reader.holding(|token_reader, offset| {
+            let mut children = Vec::<TTree>::new();
 
-        let mut token: Option<Token<()>> = None;
+            let mut token: Option<Token<()>> = None;
 
-        for child_parser in self.sequence.iter() {
-            match child_parser.parse(token_reader) {
-                Ok(child) => {
-                    if token.is_none() {
-                        token = Some(child.token().clone());
-                    }
+            for (index, child_parser) in self.sequence.iter().enumerate() {
+                match child_parser.parse(token_reader) {
+                    Ok(child) => {
+                        if token.is_none() {
+                            token = Some(child.token().clone());
+                        }
 
-                    children.push(child);
+                        children.push(child);
+                    }
+                    Err(err) => {
+                        return Err(self.commit_error(index, err));
+                    }
                 }
-                Err(err) => {
-                    return Err(err);
+            }
+
+            let token = token.ok_or(ParsingError::RequiredParserFieldMissing("sequence"))?;
+
+            match self.action {
+                ParserSequentialActionKind::Plain(action) => Ok(action(token, children)),
+                ParserSequentialActionKind::Span(action) => {
+                    // If no tokens were consumed, the span is empty at the location of the first child's token
+                    let span = token_reader.span_since(offset).unwrap_or_else(|| {
+                        SourceSpan::new(token.location.clone(), token.location.clone())
+                    });
+
+                    Ok(action(token, children, span))
                 }
             }
+        })
+    }
+
+    fn matches(
+        &self,
+        token_reader: &mut crate::lexing::token_reader::TokenReader<TTokenKind>,
+    ) -> Result<(), ParsingError<TTokenKind>> {
+        for (index, child_parser) in self.sequence.iter().enumerate() {
+            child_parser
+                .matches(token_reader)
+                .map_err(|err| self.commit_error(index, err))?;
         }
 
-        Ok((self.action)(
-            token.ok_or(ParsingError::RequiredParserFieldMissing("sequence"))?,
-            children,
-        ))
+        Ok(())
     }
 
     fn expected_tokens_unsafe(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
@@ -113,6 +189,14 @@ impl<TTokenKind: TokenKind, TTree: Tree> Parse<TTokenKind, TTree>
             .ok_or(ParsingError::RequiredParserFieldMissing("sequence"))?
             .expected_tokens()
     }
+
+    fn sub_parsers(&self) -> Vec<&dyn Parse<TTokenKind, TTree>> {
+        self.sequence.iter().map(|child| child.as_ref()).collect()
+    }
+
+    fn can_match_empty(&self) -> bool {
+        self.sequence.iter().all(|child| child.can_match_empty())
+    }
 }
 
 #[cfg(test)]
@@ -120,8 +204,10 @@ mod tests {
     use std::{collections::HashSet, path::PathBuf};
 
     use crate::{
-        domain::token::Token, lexing::token_reader::TokenReader,
-        parsing::parser_token::ParserTokenBuilder, tree::visit::Visit,
+        domain::{source_location::SourceLocation, token::Token},
+        lexing::token_reader::TokenReader,
+        parsing::parser_token::ParserTokenBuilder,
+        tree::visit::Visit,
     };
 
     use super::*;
@@ -139,6 +225,7 @@ mod tests {
         A(Token<()>),
         B(Token<()>),
         AB(Token<()>, Box<TreeTest>, Box<TreeTest>),
+        Spanned(Token<()>, SourceSpan),
     }
 
     impl Tree for TreeTest {
@@ -147,6 +234,7 @@ mod tests {
                 TreeTest::A(token) => token,
                 TreeTest::B(token) => token,
                 TreeTest::AB(token, _, _) => token,
+                TreeTest::Spanned(token, _) => token,
             }
         }
     }
@@ -167,14 +255,14 @@ mod tests {
             .sequence(Box::new(
                 ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
                     .token_kind(TokenKindTest::A)
-                    .action(|token| TreeTest::A(token))
+                    .action(TreeTest::A)
                     .build()
                     .unwrap(),
             ))
             .sequence(Box::new(
                 ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
                     .token_kind(TokenKindTest::B)
-                    .action(|token| TreeTest::B(token))
+                    .action(TreeTest::B)
                     .build()
                     .unwrap(),
             ))
@@ -225,14 +313,14 @@ mod tests {
             .sequence(Box::new(
                 ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
                     .token_kind(TokenKindTest::A)
-                    .action(|token| TreeTest::A(token))
+                    .action(TreeTest::A)
                     .build()
                     .unwrap(),
             ))
             .sequence(Box::new(
                 ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
                     .token_kind(TokenKindTest::B)
-                    .action(|token| TreeTest::B(token))
+                    .action(TreeTest::B)
                     .build()
                     .unwrap(),
             ))
@@ -252,14 +340,14 @@ mod tests {
             parser.parse(&mut token_reader),
             Err(ParsingError::UnexpectedToken {
                 expected_token_kinds: HashSet::from([TokenKindTest::A]),
-                actual_token: Token::new(
+                actual_token: Box::new(Token::new(
                     PathBuf::from("--"),
                     0,
                     1,
                     1,
                     String::from("b"),
                     TokenKindTest::B
-                )
+                ))
             })
         );
     }
@@ -291,14 +379,14 @@ mod tests {
             .sequence(Box::new(
                 ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
                     .token_kind(TokenKindTest::A)
-                    .action(|token| TreeTest::A(token))
+                    .action(TreeTest::A)
                     .build()
                     .unwrap(),
             ))
             .sequence(Box::new(
                 ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
                     .token_kind(TokenKindTest::B)
-                    .action(|token| TreeTest::B(token))
+                    .action(TreeTest::B)
                     .build()
                     .unwrap(),
             ))
@@ -318,14 +406,14 @@ mod tests {
             parser.parse(&mut token_reader),
             Err(ParsingError::UnexpectedToken {
                 expected_token_kinds: HashSet::from([TokenKindTest::B]),
-                actual_token: Token::new(
+                actual_token: Box::new(Token::new(
                     PathBuf::from("--"),
                     1,
                     2,
                     2,
                     String::from("a"),
                     TokenKindTest::A
-                )
+                ))
             })
         );
     }
@@ -357,14 +445,14 @@ mod tests {
             .sequence(Box::new(
                 ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
                     .token_kind(TokenKindTest::A)
-                    .action(|token| TreeTest::A(token))
+                    .action(TreeTest::A)
                     .build()
                     .unwrap(),
             ))
             .sequence(Box::new(
                 ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
                     .token_kind(TokenKindTest::B)
-                    .action(|token| TreeTest::B(token))
+                    .action(TreeTest::B)
                     .build()
                     .unwrap(),
             ))
@@ -403,4 +491,113 @@ mod tests {
             ))
         );
     }
+
+    #[test]
+    fn test_parser_sequential_success_span() {
+        let tokens = Vec::from([
+            Token::new(
+                PathBuf::from("--"),
+                0,
+                1,
+                1,
+                String::from("a"),
+                TokenKindTest::A,
+            ),
+            Token::new(
+                PathBuf::from("--"),
+                2,
+                2,
+                1,
+                String::from("bb"),
+                TokenKindTest::B,
+            ),
+        ]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        let parser = ParserSequentialBuilder::<TokenKindTest, TreeTest>::default()
+            .sequence(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::A)
+                    .action(TreeTest::A)
+                    .build()
+                    .unwrap(),
+            ))
+            .sequence(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::B)
+                    .action(TreeTest::B)
+                    .build()
+                    .unwrap(),
+            ))
+            .action_with_span(|token, _, span| TreeTest::Spanned(token, span))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            parser.parse(&mut token_reader),
+            Ok(TreeTest::Spanned(
+                Token::new(PathBuf::from("--"), 0, 1, 1, String::from("a"), ()),
+                SourceSpan::new(
+                    SourceLocation::new(PathBuf::from("--"), 0, 1, 1),
+                    SourceLocation::new(PathBuf::from("--"), 4, 2, 3)
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parser_sequential_success_span_max_buffered() {
+        let tokens = Vec::from([
+            Token::new(
+                PathBuf::from("--"),
+                0,
+                1,
+                1,
+                String::from("a"),
+                TokenKindTest::A,
+            ),
+            Token::new(
+                PathBuf::from("--"),
+                2,
+                2,
+                1,
+                String::from("bb"),
+                TokenKindTest::B,
+            ),
+        ]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+        token_reader.set_max_buffered(Some(1));
+
+        let parser = ParserSequentialBuilder::<TokenKindTest, TreeTest>::default()
+            .sequence(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::A)
+                    .action(TreeTest::A)
+                    .build()
+                    .unwrap(),
+            ))
+            .sequence(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::B)
+                    .action(TreeTest::B)
+                    .build()
+                    .unwrap(),
+            ))
+            .action_with_span(|token, _, span| TreeTest::Spanned(token, span))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            parser.parse(&mut token_reader),
+            Ok(TreeTest::Spanned(
+                Token::new(PathBuf::from("--"), 0, 1, 1, String::from("a"), ()),
+                SourceSpan::new(
+                    SourceLocation::new(PathBuf::from("--"), 0, 1, 1),
+                    SourceLocation::new(PathBuf::from("--"), 4, 2, 3)
+                )
+            ))
+        );
+    }
 }
@@ -20,7 +20,7 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use std::collections::HashSet;
+use std::{collections::HashSet, mem};
 
 use super::{parse::Parse, parsing_error::ParsingError};
 use crate::{
@@ -30,14 +30,31 @@ use crate::{
 
 pub type ParserTokenAction<TTree> = fn(Token<()>) -> TTree;
 
+// Called with the token's leading trivia moved out of it, for trees that keep trivia in their own nodes
+pub type ParserTokenActionWithTrivia<TTree> = fn(Token<()>, Vec<Token<()>>) -> TTree;
+
+enum ParserTokenActionKind<TTree> {
+    Plain(ParserTokenAction<TTree>),
+    WithTrivia(ParserTokenActionWithTrivia<TTree>),
+}
+
+// Derived impls would require `TTree: Copy`
+impl<TTree> Clone for ParserTokenActionKind<TTree> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<TTree> Copy for ParserTokenActionKind<TTree> {}
+
 pub struct ParserToken<TTokenKind: TokenKind, TTree: Tree> {
     token_kind: TTokenKind,
-    action: ParserTokenAction<TTree>,
+    action: ParserTokenActionKind<TTree>,
 }
 
 pub struct ParserTokenBuilder<TTokenKind: TokenKind, TTree: Tree> {
     token_kind: Option<TTokenKind>,
-    action: Option<ParserTokenAction<TTree>>,
+    action: Option<ParserTokenActionKind<TTree>>,
 }
 
 impl<TTokenKind: TokenKind, TTree: Tree> ParserTokenBuilder<TTokenKind, TTree> {
@@ -47,7 +64,13 @@ impl<TTokenKind: TokenKind, TTree: Tree> ParserTokenBuilder<TTokenKind, TTree> {
     }
 
     pub fn action(&mut self, value: ParserTokenAction<TTree>) -> &mut Self {
-        self.action = Some(value);
+        self.action = Some(ParserTokenActionKind::Plain(value));
+        self
+    }
+
+    // Replaces any action set with `action`
+    pub fn action_with_trivia(&mut self, value: ParserTokenActionWithTrivia<TTree>) -> &mut Self {
+        self.action = Some(ParserTokenActionKind::WithTrivia(value));
         self
     }
 
@@ -81,11 +104,19 @@ impl<TTokenKind: TokenKind, TTree: Tree> Parse<TTokenKind, TTree>
     ) -> Result<TTree, ParsingError<TTokenKind>> {
         if let Some(token) = token_reader.eat_next() {
             if token.kind == self.token_kind {
-                Ok((self.action)(token.to_kindless()))
+                let mut token = token.to_kindless();
+
+                Ok(match self.action {
+                    ParserTokenActionKind::Plain(action) => action(token),
+                    ParserTokenActionKind::WithTrivia(action) => {
+                        let leading_trivia = mem::take(&mut token.leading_trivia);
+                        action(token, leading_trivia)
+                    }
+                })
             } else {
                 Err(ParsingError::UnexpectedToken {
                     expected_token_kinds: self.expected_tokens()?,
-                    actual_token: token.clone(),
+                    actual_token: Box::new(token.clone()),
                 })
             }
         } else {
@@ -176,7 +207,7 @@ mod tests {
             parser.parse(&mut token_reader),
             Err(ParsingError::UnexpectedToken {
                 expected_token_kinds: HashSet::from([TokenKindTest::B]),
-                actual_token: tokens[0].clone(),
+                actual_token: Box::new(tokens[0].clone()),
             })
         );
     }
@@ -207,4 +238,58 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn test_parser_token_action_with_trivia() {
+        #[derive(Debug, PartialEq, Eq, Clone)]
+        struct TreeTestTrivia {
+            token: Token<()>,
+            leading_trivia: Vec<Token<()>>,
+        }
+
+        impl Tree for TreeTestTrivia {
+            fn token(&self) -> &Token<()> {
+                &self.token
+            }
+        }
+
+        impl Visit for TreeTestTrivia {
+            fn visit(&self, _: crate::tree::visit::VisitCallback<Self>) {}
+
+            fn visit_mut(&self, _: crate::tree::visit::VisitCallbackMut<Self>) {}
+        }
+
+        let comment = Token::new(PathBuf::from("--"), 0, 1, 1, String::from("// a\n"), ());
+
+        let mut token = Token::new(
+            PathBuf::from("--"),
+            5,
+            2,
+            1,
+            String::from("a"),
+            TokenKindTest::A,
+        );
+        token.leading_trivia.push(comment.clone());
+
+        let tokens = Vec::from([token]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        let parser = ParserTokenBuilder::<TokenKindTest, TreeTestTrivia>::default()
+            .token_kind(TokenKindTest::A)
+            .action_with_trivia(|token, leading_trivia| TreeTestTrivia {
+                token,
+                leading_trivia,
+            })
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            parser.parse(&mut token_reader),
+            Ok(TreeTestTrivia {
+                token: Token::new(PathBuf::from("--"), 5, 2, 1, String::from("a"), ()),
+                leading_trivia: Vec::from([comment]),
+            })
+        );
+    }
 }
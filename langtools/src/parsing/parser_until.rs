@@ -0,0 +1,298 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use super::{parse::Parse, parsing_error::ParsingError};
+use crate::{
+    domain::token::{Token, TokenKind},
+    lexing::token_reader::TokenReader,
+    tree::Tree,
+};
+use std::collections::HashSet;
+
+// Called with the terminator token, which isn't consumed, and the items parsed before it
+pub type ParserUntilAction<TTree> = fn(Token<()>, Vec<TTree>) -> TTree;
+
+// Parses `item` repeatedly until the next token is of `terminator_kind`, which is left for the caller to consume. The
+// terminator is what stops the loop, so an item that fails to parse is reported as an error instead of quietly ending
+// the repetition. Reaching the end of the source first is an error, as is an item that succeeds without consuming
+// anything since it would repeat forever. On failure the reader seeks back to where it started.
+pub struct ParserUntil<TTokenKind: TokenKind, TTree: Tree> {
+    item: Box<dyn Parse<TTokenKind, TTree>>,
+    terminator_kind: TTokenKind,
+    action: ParserUntilAction<TTree>,
+}
+
+pub struct ParserUntilBuilder<TTokenKind: TokenKind, TTree: Tree> {
+    item: Option<Box<dyn Parse<TTokenKind, TTree>>>,
+    terminator_kind: Option<TTokenKind>,
+    action: Option<ParserUntilAction<TTree>>,
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> ParserUntilBuilder<TTokenKind, TTree> {
+    pub fn item(&mut self, value: Box<dyn Parse<TTokenKind, TTree>>) -> &mut Self {
+        self.item = Some(value);
+        self
+    }
+
+    pub fn terminator_kind(&mut self, value: TTokenKind) -> &mut Self {
+        self.terminator_kind = Some(value);
+        self
+    }
+
+    pub fn action(&mut self, value: ParserUntilAction<TTree>) -> &mut Self {
+        self.action = Some(value);
+        self
+    }
+
+    pub fn build(&mut self) -> Result<ParserUntil<TTokenKind, TTree>, ParsingError<TTokenKind>> {
+        Ok(ParserUntil {
+            item: self
+                .item
+                .take()
+                .ok_or(ParsingError::RequiredParserFieldMissing("item"))?,
+            terminator_kind: self
+                .terminator_kind
+                .ok_or(ParsingError::RequiredParserFieldMissing("terminator_kind"))?,
+            action: self
+                .action
+                .ok_or(ParsingError::RequiredParserFieldMissing("action"))?,
+        })
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Default for ParserUntilBuilder<TTokenKind, TTree> {
+    fn default() -> Self {
+        Self {
+            item: None,
+            terminator_kind: None,
+            action: None,
+        }
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> ParserUntil<TTokenKind, TTree> {
+    fn parse_items(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<TTree, ParsingError<TTokenKind>> {
+        let mut items = Vec::new();
+
+        loop {
+            let item_offset = token_reader.offset();
+
+            match token_reader.peek_next() {
+                Some(token) if token.kind == self.terminator_kind => {
+                    return Ok((self.action)(token.to_kindless(), items));
+                }
+                Some(_) => {}
+                None => {
+                    return Err(ParsingError::UnexpectedEndOfSource {
+                        expected_token_kinds: self.expected_tokens()?,
+                    })
+                }
+            }
+
+            items.push(self.item.parse(token_reader)?);
+
+            if token_reader.offset() == item_offset {
+                if let Some(token) = token_reader.peek_next() {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected_token_kinds: self.expected_tokens()?,
+                        actual_token: Box::new(token.clone()),
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Parse<TTokenKind, TTree>
+    for ParserUntil<TTokenKind, TTree>
+{
+    fn parse(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<TTree, ParsingError<TTokenKind>> {
+        token_reader.holding(|token_reader, offset| {
+            let result = self.parse_items(token_reader);
+
+            if result.is_err() {
+                token_reader.seek(offset);
+            }
+
+            result
+        })
+    }
+
+    fn expected_tokens_unsafe(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+        let mut result = self.item.expected_tokens()?;
+        result.insert(self.terminator_kind);
+        Ok(result)
+    }
+
+    fn sub_parsers(&self) -> Vec<&dyn Parse<TTokenKind, TTree>> {
+        Vec::from([self.item.as_ref()])
+    }
+
+    fn can_match_empty(&self) -> bool {
+        // The terminator isn't consumed, so an immediate terminator matches nothing
+        true
+    }
+
+    fn repeated_parsers(&self) -> Vec<&dyn Parse<TTokenKind, TTree>> {
+        Vec::from([self.item.as_ref()])
+    }
+
+    fn own_token_kinds(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+        Ok(HashSet::from([self.terminator_kind]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parsing::{parser_token::ParserTokenBuilder, testing::tokens},
+        tree::visit::Visit,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        Statement,
+        RightBrace,
+        Comma,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    enum TreeTest {
+        Statement(Token<()>),
+        Block(Token<()>, Vec<TreeTest>),
+    }
+
+    impl Tree for TreeTest {
+        fn token(&self) -> &Token<()> {
+            match self {
+                TreeTest::Statement(token) => token,
+                TreeTest::Block(token, _) => token,
+            }
+        }
+    }
+
+    impl Visit for TreeTest {
+        fn visit(&self, _: crate::tree::visit::VisitCallback<Self>) {}
+
+        fn visit_mut(&self, _: crate::tree::visit::VisitCallbackMut<Self>) {}
+    }
+
+    fn parser_until() -> ParserUntil<TokenKindTest, TreeTest> {
+        ParserUntilBuilder::<TokenKindTest, TreeTest>::default()
+            .item(Box::new(
+                ParserTokenBuilder::<TokenKindTest, TreeTest>::default()
+                    .token_kind(TokenKindTest::Statement)
+                    .action(TreeTest::Statement)
+                    .build()
+                    .unwrap(),
+            ))
+            .terminator_kind(TokenKindTest::RightBrace)
+            .action(TreeTest::Block)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parser_until_empty() {
+        let tokens = tokens(&[TokenKindTest::RightBrace]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_until().parse(&mut token_reader),
+            Ok(TreeTest::Block(tokens[0].to_kindless(), Vec::new()))
+        );
+        assert_eq!(token_reader.offset(), 0);
+    }
+
+    #[test]
+    fn test_parser_until_several() {
+        let tokens = tokens(&[
+            TokenKindTest::Statement,
+            TokenKindTest::Statement,
+            TokenKindTest::Statement,
+            TokenKindTest::RightBrace,
+        ]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_until().parse(&mut token_reader),
+            Ok(TreeTest::Block(
+                tokens[3].to_kindless(),
+                tokens[..3]
+                    .iter()
+                    .map(|token| TreeTest::Statement(token.to_kindless()))
+                    .collect()
+            ))
+        );
+        assert_eq!(token_reader.offset(), 3);
+    }
+
+    #[test]
+    fn test_parser_until_missing_terminator() {
+        let tokens = tokens(&[TokenKindTest::Statement, TokenKindTest::Statement]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_until().parse(&mut token_reader),
+            Err(ParsingError::UnexpectedEndOfSource {
+                expected_token_kinds: HashSet::from([
+                    TokenKindTest::Statement,
+                    TokenKindTest::RightBrace
+                ]),
+            })
+        );
+        assert_eq!(token_reader.offset(), 0);
+    }
+
+    #[test]
+    fn test_parser_until_bad_item() {
+        let tokens = tokens(&[
+            TokenKindTest::Statement,
+            TokenKindTest::Comma,
+            TokenKindTest::RightBrace,
+        ]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_until().parse(&mut token_reader),
+            Err(ParsingError::UnexpectedToken {
+                expected_token_kinds: HashSet::from([TokenKindTest::Statement]),
+                actual_token: Box::new(tokens[1].clone()),
+            })
+        );
+        assert_eq!(token_reader.offset(), 0);
+    }
+}
@@ -0,0 +1,293 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+use super::{parse::Parse, parsing_error::ParsingError};
+use crate::{
+    domain::token::{Token, TokenKind},
+    lexing::token_reader::TokenReader,
+    tree::Tree,
+};
+use std::{collections::HashSet, mem};
+
+// Parses the child, but reports `expected` as its expected tokens instead of the set derived from the child. This is
+// for curating error messages when a complex subtree would otherwise list every token it could start with. Errors are
+// only rewritten when the child fails on its first token, since an error further in is about something more specific
+// than the start of the subtree. A committed error is rewritten the same way and stays committed.
+pub struct ParserWithExpected<TTokenKind: TokenKind, TTree: Tree> {
+    child: Box<dyn Parse<TTokenKind, TTree>>,
+    expected: HashSet<TTokenKind>,
+}
+
+pub struct ParserWithExpectedBuilder<TTokenKind: TokenKind, TTree: Tree> {
+    child: Option<Box<dyn Parse<TTokenKind, TTree>>>,
+    expected: HashSet<TTokenKind>,
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> ParserWithExpectedBuilder<TTokenKind, TTree> {
+    pub fn child(&mut self, value: Box<dyn Parse<TTokenKind, TTree>>) -> &mut Self {
+        self.child = Some(value);
+        self
+    }
+
+    pub fn expected(&mut self, value: TTokenKind) -> &mut Self {
+        self.expected.insert(value);
+        self
+    }
+
+    pub fn build(
+        &mut self,
+    ) -> Result<ParserWithExpected<TTokenKind, TTree>, ParsingError<TTokenKind>> {
+        if self.expected.is_empty() {
+            return Err(ParsingError::RequiredParserFieldMissing("expected"));
+        }
+
+        Ok(ParserWithExpected {
+            child: self
+                .child
+                .take()
+                .ok_or(ParsingError::RequiredParserFieldMissing("child"))?,
+            expected: mem::take(&mut self.expected),
+        })
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Default for ParserWithExpectedBuilder<TTokenKind, TTree> {
+    fn default() -> Self {
+        Self {
+            child: None,
+            expected: HashSet::new(),
+        }
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> ParserWithExpected<TTokenKind, TTree> {
+    fn override_expected(
+        &self,
+        first_token: Option<Token<TTokenKind>>,
+        error: ParsingError<TTokenKind>,
+    ) -> ParsingError<TTokenKind> {
+        match error {
+            ParsingError::Committed(error) => {
+                self.override_expected(first_token, *error).into_committed()
+            }
+            ParsingError::UnexpectedToken { actual_token, .. }
+                if first_token.as_ref() == Some(actual_token.as_ref()) =>
+            {
+                ParsingError::UnexpectedToken {
+                    expected_token_kinds: self.expected.clone(),
+                    actual_token,
+                }
+            }
+            ParsingError::UnexpectedEndOfSource { .. } if first_token.is_none() => {
+                ParsingError::UnexpectedEndOfSource {
+                    expected_token_kinds: self.expected.clone(),
+                }
+            }
+            error => error,
+        }
+    }
+}
+
+impl<TTokenKind: TokenKind, TTree: Tree> Parse<TTokenKind, TTree>
+    for ParserWithExpected<TTokenKind, TTree>
+{
+    fn parse(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<TTree, ParsingError<TTokenKind>> {
+        let first_token = token_reader.peek_next().cloned();
+
+        self.child
+            .parse(token_reader)
+            .map_err(|error| self.override_expected(first_token, error))
+    }
+
+    fn matches(
+        &self,
+        token_reader: &mut TokenReader<TTokenKind>,
+    ) -> Result<(), ParsingError<TTokenKind>> {
+        let first_token = token_reader.peek_next().cloned();
+
+        self.child
+            .matches(token_reader)
+            .map_err(|error| self.override_expected(first_token, error))
+    }
+
+    fn expected_tokens_unsafe(&self) -> Result<HashSet<TTokenKind>, ParsingError<TTokenKind>> {
+        Ok(self.expected.clone())
+    }
+
+    fn sub_parsers(&self) -> Vec<&dyn Parse<TTokenKind, TTree>> {
+        Vec::from([self.child.as_ref()])
+    }
+
+    fn can_match_empty(&self) -> bool {
+        self.child.can_match_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parsing::{
+            parser_sequential::ParserSequentialBuilder,
+            testing::{parser_token, tokens},
+        },
+        tree::visit::Visit,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        Expression,
+        A,
+        B,
+        C,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    struct TreeTest {
+        token: Token<()>,
+    }
+
+    impl Tree for TreeTest {
+        fn token(&self) -> &Token<()> {
+            &self.token
+        }
+    }
+
+    impl Visit for TreeTest {
+        fn visit(&self, _: crate::tree::visit::VisitCallback<Self>) {}
+
+        fn visit_mut(&self, _: crate::tree::visit::VisitCallbackMut<Self>) {}
+    }
+
+    // Stands in for a subtree whose derived expected set isn't what should be shown to users
+    fn parser_with_expected() -> ParserWithExpected<TokenKindTest, TreeTest> {
+        ParserWithExpectedBuilder::<TokenKindTest, TreeTest>::default()
+            .child(Box::new(
+                ParserSequentialBuilder::<TokenKindTest, TreeTest>::default()
+                    .sequence(parser_token(TokenKindTest::A, |token| TreeTest { token }))
+                    .sequence(parser_token(TokenKindTest::B, |token| TreeTest { token }))
+                    .action(|token, _| TreeTest { token })
+                    .build()
+                    .unwrap(),
+            ))
+            .expected(TokenKindTest::Expression)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parser_with_expected_success() {
+        let tokens = tokens(&[TokenKindTest::A, TokenKindTest::B]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_with_expected().parse(&mut token_reader),
+            Ok(TreeTest {
+                token: tokens[0].to_kindless()
+            })
+        );
+        assert_eq!(
+            parser_with_expected().expected_tokens(),
+            Ok(HashSet::from([TokenKindTest::Expression]))
+        );
+    }
+
+    #[test]
+    fn test_parser_with_expected_first_token() {
+        let tokens = tokens(&[TokenKindTest::C]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_with_expected().parse(&mut token_reader),
+            Err(ParsingError::UnexpectedToken {
+                expected_token_kinds: HashSet::from([TokenKindTest::Expression]),
+                actual_token: Box::new(tokens[0].clone()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parser_with_expected_end_of_source() {
+        let tokens = Vec::new();
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_with_expected().matches(&mut token_reader),
+            Err(ParsingError::UnexpectedEndOfSource {
+                expected_token_kinds: HashSet::from([TokenKindTest::Expression]),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parser_with_expected_committed() {
+        let tokens = tokens(&[TokenKindTest::C]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        let parser = ParserWithExpectedBuilder::<TokenKindTest, TreeTest>::default()
+            .child(Box::new(
+                ParserSequentialBuilder::<TokenKindTest, TreeTest>::default()
+                    .commit()
+                    .sequence(parser_token(TokenKindTest::A, |token| TreeTest { token }))
+                    .action(|token, _| TreeTest { token })
+                    .build()
+                    .unwrap(),
+            ))
+            .expected(TokenKindTest::Expression)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            parser.parse(&mut token_reader),
+            Err(ParsingError::Committed(Box::new(
+                ParsingError::UnexpectedToken {
+                    expected_token_kinds: HashSet::from([TokenKindTest::Expression]),
+                    actual_token: Box::new(tokens[0].clone()),
+                }
+            )))
+        );
+    }
+
+    #[test]
+    fn test_parser_with_expected_later_token() {
+        let tokens = tokens(&[TokenKindTest::A, TokenKindTest::C]);
+        let mut tokens_iter = tokens.iter();
+        let mut token_reader = TokenReader::<TokenKindTest>::new(&mut tokens_iter);
+
+        assert_eq!(
+            parser_with_expected().parse(&mut token_reader),
+            Err(ParsingError::UnexpectedToken {
+                expected_token_kinds: HashSet::from([TokenKindTest::B]),
+                actual_token: Box::new(tokens[1].clone()),
+            })
+        );
+    }
+}
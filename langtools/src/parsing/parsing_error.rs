@@ -20,22 +20,125 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use std::fmt::{self, Display, Formatter};
+use std::fmt::{self, Debug, Display, Formatter};
 use std::{collections::HashSet, error::Error};
 
-use crate::domain::token::{Token, TokenKind};
+use crate::{
+    domain::{
+        source_location::SourceLocation,
+        token::{format_expected, sorted_kinds, Token, TokenKind},
+    },
+    messaging::{
+        message::{Message, Severity},
+        render::{render_location, render_snippet, RenderOptions},
+    },
+    sourcing::source_map::SourceMap,
+};
 
-#[derive(Debug, Clone)]
+// `Debug` is implemented by hand so that expected token kinds are listed in a stable order
+#[derive(Clone)]
 pub enum ParsingError<TTokenKind: TokenKind> {
     UnexpectedEndOfSource {
         expected_token_kinds: HashSet<TTokenKind>,
     },
     UnexpectedToken {
         expected_token_kinds: HashSet<TTokenKind>,
-        actual_token: Token<TTokenKind>,
+        actual_token: Box<Token<TTokenKind>>,
     },
     RequiredParserFieldMissing(&'static str),
     NoExpectedTokensProvided,
+    AmbiguousParse {
+        alternatives: usize,
+    },
+    AnchorMoved {
+        anchor: usize,
+        offset: usize,
+    },
+    ShadowedChoice {
+        choice: usize,
+        token_kind: TTokenKind,
+    },
+    OperatorConflict {
+        token_kind: TTokenKind,
+    },
+    // A domain specific error, usually made by rewriting a generic one with `ParserMapErr`. The token is where it is
+    // reported, which is usually the token of the error it replaces from `ParsingError::token`.
+    Custom {
+        message: String,
+        token: Option<Box<Token<TTokenKind>>>,
+    },
+    // An error after a commit point in a sequence. Choices and other parsers that backtrack pass it on instead of trying
+    // something else, so it reaches the top of the parse and is reported as the wrapped error.
+    Committed(Box<ParsingError<TTokenKind>>),
+    UnproductiveRepetition {
+        expected_token_kinds: HashSet<TTokenKind>,
+    },
+    ParseLimitExceeded {
+        limit: usize,
+    },
+    InvalidRepetitionBounds {
+        min: usize,
+        max: usize,
+    },
+}
+
+impl<TTokenKind: TokenKind> ParsingError<TTokenKind> {
+    pub fn is_committed(&self) -> bool {
+        matches!(self, ParsingError::Committed(_))
+    }
+
+    pub fn into_committed(self) -> Self {
+        if self.is_committed() {
+            self
+        } else {
+            ParsingError::Committed(Box::new(self))
+        }
+    }
+
+    // The token the error is reported at: the unexpected token, or the token given to a custom error
+    pub fn token(&self) -> Option<&Token<TTokenKind>> {
+        match self {
+            ParsingError::Committed(error) => error.token(),
+            ParsingError::UnexpectedToken { actual_token, .. } => Some(actual_token),
+            ParsingError::Custom { token, .. } => token.as_deref(),
+            _ => None,
+        }
+    }
+
+    // Makes an error message at the error's token, or at `location` for errors that don't have a token, or with no
+    // location if there isn't one either
+    pub fn to_message(&self, location: Option<SourceLocation>) -> Message {
+        match (self.token(), location) {
+            (Some(token), _) => {
+                Message::new_location(token.location.clone(), Severity::Error, self.to_string())
+            }
+            (None, Some(location)) => {
+                Message::new_location(location, Severity::Error, self.to_string())
+            }
+            (None, None) => Message::new_global(Severity::Error, self.to_string()),
+        }
+    }
+
+    pub fn render(&self, source_map: &SourceMap) -> String {
+        match self.token() {
+            Some(token) => {
+                let mut result = format!("{}: error: {self}", render_location(&token.location));
+
+                if let Some(snippet) = render_snippet(
+                    &token.location,
+                    token.text.chars().count(),
+                    source_map,
+                    &RenderOptions::default(),
+                ) {
+                    result.push('\n');
+                    result.push_str(&snippet);
+                }
+
+                result
+            }
+            None => format!("error: {self}"),
+        }
+    }
 }
 
 impl<TTokenKind: TokenKind> PartialEq for ParsingError<TTokenKind> {
@@ -71,6 +174,77 @@ impl<TTokenKind: TokenKind> PartialEq for ParsingError<TTokenKind> {
                 Self::RequiredParserFieldMissing(name_other),
             ) => name_self == name_other,
             (Self::NoExpectedTokensProvided, Self::NoExpectedTokensProvided) => true,
+            (
+                Self::AmbiguousParse {
+                    alternatives: alternatives_self,
+                },
+                Self::AmbiguousParse {
+                    alternatives: alternatives_other,
+                },
+            ) => alternatives_self == alternatives_other,
+            (
+                Self::AnchorMoved {
+                    anchor: anchor_self,
+                    offset: offset_self,
+                },
+                Self::AnchorMoved {
+                    anchor: anchor_other,
+                    offset: offset_other,
+                },
+            ) => anchor_self == anchor_other && offset_self == offset_other,
+            (
+                Self::ShadowedChoice {
+                    choice: choice_self,
+                    token_kind: token_kind_self,
+                },
+                Self::ShadowedChoice {
+                    choice: choice_other,
+                    token_kind: token_kind_other,
+                },
+            ) => choice_self == choice_other && token_kind_self == token_kind_other,
+            (
+                Self::OperatorConflict {
+                    token_kind: token_kind_self,
+                },
+                Self::OperatorConflict {
+                    token_kind: token_kind_other,
+                },
+            ) => token_kind_self == token_kind_other,
+            (
+                Self::Custom {
+                    message: message_self,
+                    token: token_self,
+                },
+                Self::Custom {
+                    message: message_other,
+                    token: token_other,
+                },
+            ) => message_self == message_other && token_self == token_other,
+            (Self::Committed(error_self), Self::Committed(error_other)) => {
+                error_self == error_other
+            }
+            (
+                Self::UnproductiveRepetition {
+                    expected_token_kinds: expected_token_kinds_self,
+                },
+                Self::UnproductiveRepetition {
+                    expected_token_kinds: expected_token_kinds_other,
+                },
+            ) => expected_token_kinds_self == expected_token_kinds_other,
+            (
+                Self::ParseLimitExceeded { limit: limit_self },
+                Self::ParseLimitExceeded { limit: limit_other },
+            ) => limit_self == limit_other,
+            (
+                Self::InvalidRepetitionBounds {
+                    min: min_self,
+                    max: max_self,
+                },
+                Self::InvalidRepetitionBounds {
+                    min: min_other,
+                    max: max_other,
+                },
+            ) => min_self == min_other && max_self == max_other,
             _ => false,
         }
     }
@@ -78,6 +252,80 @@ impl<TTokenKind: TokenKind> PartialEq for ParsingError<TTokenKind> {
 
 impl<TTokenKind: TokenKind> Error for ParsingError<TTokenKind> {}
 
+// Formats an expected set like `HashSet`'s `Debug`, but sorted with `sorted_kinds`
+struct SortedKinds<'kinds, TTokenKind: TokenKind>(&'kinds HashSet<TTokenKind>);
+
+impl<'kinds, TTokenKind: TokenKind> Debug for SortedKinds<'kinds, TTokenKind> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_set().entries(sorted_kinds(self.0)).finish()
+    }
+}
+
+impl<TTokenKind: TokenKind> Debug for ParsingError<TTokenKind> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ParsingError::UnexpectedEndOfSource {
+                expected_token_kinds,
+            } => f
+                .debug_struct("UnexpectedEndOfSource")
+                .field("expected_token_kinds", &SortedKinds(expected_token_kinds))
+                .finish(),
+            ParsingError::UnexpectedToken {
+                expected_token_kinds,
+                actual_token,
+            } => f
+                .debug_struct("UnexpectedToken")
+                .field("expected_token_kinds", &SortedKinds(expected_token_kinds))
+                .field("actual_token", actual_token)
+                .finish(),
+            ParsingError::RequiredParserFieldMissing(name) => f
+                .debug_tuple("RequiredParserFieldMissing")
+                .field(name)
+                .finish(),
+            ParsingError::NoExpectedTokensProvided => write!(f, "NoExpectedTokensProvided"),
+            ParsingError::AmbiguousParse { alternatives } => f
+                .debug_struct("AmbiguousParse")
+                .field("alternatives", alternatives)
+                .finish(),
+            ParsingError::AnchorMoved { anchor, offset } => f
+                .debug_struct("AnchorMoved")
+                .field("anchor", anchor)
+                .field("offset", offset)
+                .finish(),
+            ParsingError::ShadowedChoice { choice, token_kind } => f
+                .debug_struct("ShadowedChoice")
+                .field("choice", choice)
+                .field("token_kind", token_kind)
+                .finish(),
+            ParsingError::OperatorConflict { token_kind } => f
+                .debug_struct("OperatorConflict")
+                .field("token_kind", token_kind)
+                .finish(),
+            ParsingError::Custom { message, token } => f
+                .debug_struct("Custom")
+                .field("message", message)
+                .field("token", token)
+                .finish(),
+            ParsingError::Committed(error) => f.debug_tuple("Committed").field(error).finish(),
+            ParsingError::UnproductiveRepetition {
+                expected_token_kinds,
+            } => f
+                .debug_struct("UnproductiveRepetition")
+                .field("expected_token_kinds", &SortedKinds(expected_token_kinds))
+                .finish(),
+            ParsingError::ParseLimitExceeded { limit } => f
+                .debug_struct("ParseLimitExceeded")
+                .field("limit", limit)
+                .finish(),
+            ParsingError::InvalidRepetitionBounds { min, max } => f
+                .debug_struct("InvalidRepetitionBounds")
+                .field("min", min)
+                .field("max", max)
+                .finish(),
+        }
+    }
+}
+
 impl<TTokenKind: TokenKind> Display for ParsingError<TTokenKind> {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match self {
@@ -85,18 +333,156 @@ impl<TTokenKind: TokenKind> Display for ParsingError<TTokenKind> {
                 expected_token_kinds,
             } => write!(
                 f,
-                "unexpected end of source, expected {expected_token_kinds:?}"
+                "unexpected end of source, {}",
+                format_expected(expected_token_kinds)
             ),
             ParsingError::UnexpectedToken {
                 expected_token_kinds,
                 actual_token,
-            } => write!(f, "expected {expected_token_kinds:?}, not {actual_token:?}"),
+            } => write!(
+                f,
+                "{}, not {} \"{}\"",
+                format_expected(expected_token_kinds),
+                actual_token.kind.display_name(),
+                actual_token.text.escape_debug()
+            ),
             ParsingError::RequiredParserFieldMissing(name) => {
                 write!(f, "required parser field {name:?} missing")
             }
             ParsingError::NoExpectedTokensProvided => {
                 write!(f, "no expected tokens provided")
             }
+            ParsingError::AmbiguousParse { alternatives } => {
+                write!(f, "ambiguous parse, {alternatives} alternatives matched")
+            }
+            ParsingError::AnchorMoved { anchor, offset } => {
+                write!(f, "expected no tokens to be consumed from offset {anchor}, but parsing reached offset {offset}")
+            }
+            ParsingError::ShadowedChoice { choice, token_kind } => write!(
+                f,
+                "choice {choice} is shadowed by an earlier choice that also expects only {}",
+                token_kind.display_name()
+            ),
+            ParsingError::OperatorConflict { token_kind } => write!(
+                f,
+                "{} is registered as both an infix and a postfix operator",
+                token_kind.display_name()
+            ),
+            ParsingError::Custom { message, .. } => write!(f, "{message}"),
+            ParsingError::Committed(error) => write!(f, "{error}"),
+            ParsingError::UnproductiveRepetition {
+                expected_token_kinds,
+            } => write!(
+                f,
+                "a repetition repeats a parser that can match without consuming tokens ({})",
+                format_expected(expected_token_kinds)
+            ),
+            ParsingError::ParseLimitExceeded { limit } => {
+                write!(f, "parsing gave up after consuming {limit} tokens")
+            }
+            ParsingError::InvalidRepetitionBounds { min, max } => write!(
+                f,
+                "repetition has a minimum of {min} that is more than its maximum of {max}"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::domain::source_info::SourceInfo;
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        A,
+        B,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    #[test]
+    fn test_parsing_error_render_unexpected_token() {
+        let mut source_map = SourceMap::new();
+        source_map.add(&mut SourceInfo::new(PathBuf::from("--")), "a\na bb a\n");
+
+        let error = ParsingError::UnexpectedToken {
+            expected_token_kinds: HashSet::from([TokenKindTest::A]),
+            actual_token: Box::new(Token::new(
+                PathBuf::from("--"),
+                4,
+                2,
+                3,
+                String::from("bb"),
+                TokenKindTest::B,
+            )),
+        };
+
+        assert_eq!(
+            error.render(&source_map),
+            format!("--:2:3: error: {error}\na bb a\n  ^^")
+        );
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindNamed {
+        Plus,
+        Minus,
+        Number,
+    }
+
+    impl TokenKind for TokenKindNamed {
+        fn display_name(&self) -> String {
+            String::from(match self {
+                TokenKindNamed::Plus => "`+`",
+                TokenKindNamed::Minus => "`-`",
+                TokenKindNamed::Number => "a number",
+            })
         }
     }
+
+    #[test]
+    fn test_parsing_error_display_end_of_source() {
+        let error = ParsingError::UnexpectedEndOfSource {
+            expected_token_kinds: HashSet::from([
+                TokenKindNamed::Number,
+                TokenKindNamed::Minus,
+                TokenKindNamed::Plus,
+            ]),
+        };
+
+        assert_eq!(
+            error.to_string(),
+            "unexpected end of source, expected `+`, `-`, or a number"
+        );
+    }
+
+    #[test]
+    fn test_parsing_error_display_unexpected_token() {
+        let error = ParsingError::UnexpectedToken {
+            expected_token_kinds: HashSet::from([TokenKindNamed::Number]),
+            actual_token: Box::new(Token::new(
+                PathBuf::from("--"),
+                0,
+                1,
+                1,
+                String::from("-\n"),
+                TokenKindNamed::Minus,
+            )),
+        };
+
+        assert_eq!(error.to_string(), "expected a number, not `-` \"-\\n\"");
+    }
+
+    #[test]
+    fn test_parsing_error_render_no_token() {
+        let error = ParsingError::<TokenKindTest>::UnexpectedEndOfSource {
+            expected_token_kinds: HashSet::from([TokenKindTest::A]),
+        };
+
+        assert_eq!(error.render(&SourceMap::new()), format!("error: {error}"));
+    }
 }
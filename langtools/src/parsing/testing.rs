@@ -0,0 +1,62 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use super::{
+    parse::Parse,
+    parser_token::{ParserTokenAction, ParserTokenBuilder},
+};
+use crate::{
+    domain::token::{Token, TokenKind},
+    tree::Tree,
+};
+use std::path::PathBuf;
+
+// One token of each kind in `kinds`, all on the first line with one character each and no space between them
+pub fn tokens<TTokenKind: TokenKind>(kinds: &[TTokenKind]) -> Vec<Token<TTokenKind>> {
+    kinds
+        .iter()
+        .enumerate()
+        .map(|(index, kind)| {
+            Token::new(
+                PathBuf::from("--"),
+                index,
+                1,
+                index as u32 + 1,
+                String::from("x"),
+                *kind,
+            )
+        })
+        .collect()
+}
+
+pub fn parser_token<TTokenKind: TokenKind + 'static, TTree: Tree + 'static>(
+    token_kind: TTokenKind,
+    action: ParserTokenAction<TTree>,
+) -> Box<dyn Parse<TTokenKind, TTree>> {
+    Box::new(
+        ParserTokenBuilder::<TTokenKind, TTree>::default()
+            .token_kind(token_kind)
+            .action(action)
+            .build()
+            .unwrap(),
+    )
+}
@@ -20,8 +20,15 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+pub mod read_bytes;
 pub mod read_source;
+pub mod source_bytes_raw;
+pub mod source_bytes_utf8;
+pub mod source_element;
+pub mod source_lines;
+pub mod source_map;
 pub mod source_reader;
+pub mod source_replay;
 pub mod source_string;
 pub mod sourcing_error;
 
@@ -29,6 +36,6 @@ use self::source_string::SourceString;
 use crate::domain::source_info::SourceInfo;
 use std::path::PathBuf;
 
-pub fn source_new_string(data: &str) -> SourceString {
+pub fn source_new_string(data: &str) -> SourceString<'_> {
     SourceString::new(SourceInfo::new(PathBuf::from("--")), data)
 }
@@ -20,13 +20,33 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use super::sourcing_error::Result;
+use super::sourcing_error::{Result, SourcingError};
 use crate::domain::source_info::SourceInfo;
 
-pub trait ReadSource {
+// Sources read `char`s by default. See `SourceElement` for the other element types a source can be read as.
+pub trait ReadSource<TElement = char> {
     fn info(&self) -> &SourceInfo;
     fn offset(&self) -> usize;
-    fn has_more(&mut self) -> bool;
-    fn peek_next(&mut self) -> Result<char>;
-    fn eat_next(&mut self) -> Result<char>;
+    // Takes a shared borrow so that callers can check for remaining characters without exclusive access. Sources that
+    // need to look ahead to answer this should buffer the next character when it is peeked or eaten rather than
+    // requiring `&mut self` here.
+    fn has_more(&self) -> bool;
+    fn peek_next(&mut self) -> Result<TElement>;
+    fn eat_next(&mut self) -> Result<TElement>;
+
+    // Sources that can be rewound return a mark of their current position that can later be restored. Marks are only
+    // meaningful to the source that created them.
+    fn mark(&self) -> Result<SourceMark> {
+        Err(SourcingError::MarkingNotSupported)
+    }
+
+    fn restore(&mut self, _mark: &SourceMark) -> Result<()> {
+        Err(SourcingError::MarkingNotSupported)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceMark {
+    pub index: usize,
+    pub offset: usize,
 }
@@ -0,0 +1,127 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::domain::source_info::SourceInfo;
+
+use super::read_source::{ReadSource, SourceMark};
+use super::sourcing_error::{Result, SourcingError};
+
+pub struct SourceBytesRaw<'bytes> {
+    info: SourceInfo,
+    data: &'bytes [u8],
+    offset: usize,
+}
+
+impl<'bytes> SourceBytesRaw<'bytes> {
+    pub fn new(info: SourceInfo, data: &'bytes [u8]) -> Self {
+        Self {
+            info,
+            data,
+            offset: 0,
+        }
+    }
+}
+
+impl<'bytes> ReadSource<u8> for SourceBytesRaw<'bytes> {
+    fn info(&self) -> &SourceInfo {
+        &self.info
+    }
+
+    fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn has_more(&self) -> bool {
+        self.offset < self.data.len()
+    }
+
+    fn peek_next(&mut self) -> Result<u8> {
+        self.data
+            .get(self.offset)
+            .copied()
+            .ok_or(SourcingError::NoMoreChars)
+    }
+
+    fn eat_next(&mut self) -> Result<u8> {
+        let result = self.peek_next()?;
+
+        self.offset += 1;
+
+        Ok(result)
+    }
+
+    fn mark(&self) -> Result<SourceMark> {
+        Ok(SourceMark {
+            index: self.offset,
+            offset: self.offset,
+        })
+    }
+
+    fn restore(&mut self, mark: &SourceMark) -> Result<()> {
+        assert!(
+            mark.offset <= self.data.len(),
+            "mark is not from this source"
+        );
+
+        self.offset = mark.offset;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn test_source_bytes_raw_empty() {
+        let mut source = SourceBytesRaw::new(SourceInfo::new(PathBuf::from("--")), b"");
+
+        assert_eq!(source.info().path, PathBuf::from("--"));
+        assert_eq!(source.offset(), 0);
+        assert!(!source.has_more());
+        assert_eq!(source.peek_next(), Err(SourcingError::NoMoreChars));
+        assert_eq!(source.eat_next(), Err(SourcingError::NoMoreChars));
+    }
+
+    #[test]
+    fn test_source_bytes_raw_two() {
+        let mut source = SourceBytesRaw::new(SourceInfo::new(PathBuf::from("--")), &[0xff, b'\r']);
+
+        assert_eq!(source.offset(), 0);
+        assert!(source.has_more());
+        assert_eq!(source.peek_next(), Ok(0xff));
+        assert_eq!(source.eat_next(), Ok(0xff));
+
+        assert_eq!(source.offset(), 1);
+        assert!(source.has_more());
+        assert_eq!(source.peek_next(), Ok(b'\r'));
+        assert_eq!(source.eat_next(), Ok(b'\r'));
+
+        assert_eq!(source.offset(), 2);
+        assert!(!source.has_more());
+        assert_eq!(source.peek_next(), Err(SourcingError::NoMoreChars));
+        assert_eq!(source.eat_next(), Err(SourcingError::NoMoreChars));
+    }
+}
@@ -0,0 +1,242 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+use std::str;
+
+use crate::domain::source_info::SourceInfo;
+
+use super::read_source::{ReadSource, SourceMark};
+use super::sourcing_error::{Result, SourcingError};
+
+// What to do with bytes that aren't valid UTF-8. Each maximal invalid sequence is handled as one unit, the same way as
+// `String::from_utf8_lossy`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvalidUtf8Policy {
+    // Read U+FFFD in place of the invalid bytes
+    Replace,
+    // Leave the invalid bytes out as if they weren't there
+    Skip,
+    // Fail with `SourcingError::InvalidUtf8` when the invalid bytes are reached
+    Abort,
+}
+
+// Decodes bytes as UTF-8 characters. Offsets count characters like `SourceString`, so skipped bytes don't count toward
+// them.
+pub struct SourceBytesUtf8<'bytes> {
+    info: SourceInfo,
+    data: &'bytes [u8],
+    policy: InvalidUtf8Policy,
+    index: usize,
+    offset: usize,
+}
+
+enum Decoded {
+    Char(char),
+    Invalid,
+}
+
+impl<'bytes> SourceBytesUtf8<'bytes> {
+    pub fn new(info: SourceInfo, data: &'bytes [u8], policy: InvalidUtf8Policy) -> Self {
+        let mut result = Self {
+            info,
+            data,
+            policy,
+            index: 0,
+            offset: 0,
+        };
+
+        result.skip_invalid();
+
+        result
+    }
+
+    // Decodes the next character, returning it with the number of bytes it takes up
+    fn decode_next(&self) -> Option<(Decoded, usize)> {
+        let rest = &self.data[self.index..];
+        let chunk = &rest[..rest.len().min(4)];
+
+        let valid = match str::from_utf8(chunk) {
+            Ok(valid) => valid,
+            Err(err) if err.valid_up_to() > 0 => {
+                str::from_utf8(&chunk[..err.valid_up_to()]).expect("prefix should be valid")
+            }
+            Err(err) if !chunk.is_empty() => {
+                return Some((Decoded::Invalid, err.error_len().unwrap_or(chunk.len())))
+            }
+            Err(_) => return None,
+        };
+
+        valid
+            .chars()
+            .next()
+            .map(|value| (Decoded::Char(value), value.len_utf8()))
+    }
+
+    // With `InvalidUtf8Policy::Skip`, moves past any invalid bytes so that `has_more` doesn't see bytes that won't be
+    // read
+    fn skip_invalid(&mut self) {
+        if self.policy != InvalidUtf8Policy::Skip {
+            return;
+        }
+
+        while let Some((Decoded::Invalid, len)) = self.decode_next() {
+            self.index += len;
+        }
+    }
+
+    fn peek_next_with_len(&self) -> Result<(char, usize)> {
+        match self.decode_next() {
+            Some((Decoded::Char(value), len)) => Ok((value, len)),
+            Some((Decoded::Invalid, len)) => match self.policy {
+                InvalidUtf8Policy::Replace => Ok((char::REPLACEMENT_CHARACTER, len)),
+                InvalidUtf8Policy::Skip => unreachable!("invalid bytes should have been skipped"),
+                InvalidUtf8Policy::Abort => Err(SourcingError::InvalidUtf8 {
+                    byte_offset: self.index,
+                }),
+            },
+            None => Err(SourcingError::NoMoreChars),
+        }
+    }
+}
+
+impl<'bytes> ReadSource for SourceBytesUtf8<'bytes> {
+    fn info(&self) -> &SourceInfo {
+        &self.info
+    }
+
+    fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn has_more(&self) -> bool {
+        self.index < self.data.len()
+    }
+
+    fn peek_next(&mut self) -> Result<char> {
+        self.peek_next_with_len().map(|(value, _)| value)
+    }
+
+    fn eat_next(&mut self) -> Result<char> {
+        let (result, len) = self.peek_next_with_len()?;
+
+        self.index += len;
+        self.offset += 1;
+        self.skip_invalid();
+
+        Ok(result)
+    }
+
+    fn mark(&self) -> Result<SourceMark> {
+        Ok(SourceMark {
+            index: self.index,
+            offset: self.offset,
+        })
+    }
+
+    fn restore(&mut self, mark: &SourceMark) -> Result<()> {
+        assert!(
+            mark.index <= self.data.len(),
+            "mark is not from this source"
+        );
+
+        self.index = mark.index;
+        self.offset = mark.offset;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn read_all(source: &mut SourceBytesUtf8) -> Result<String> {
+        let mut result = String::new();
+
+        while source.has_more() {
+            result.push(source.eat_next()?);
+        }
+
+        Ok(result)
+    }
+
+    #[test]
+    fn test_source_bytes_utf8_valid() {
+        let mut source = SourceBytesUtf8::new(
+            SourceInfo::new(PathBuf::from("--")),
+            "aé€😀".as_bytes(),
+            InvalidUtf8Policy::Abort,
+        );
+
+        assert_eq!(read_all(&mut source), Ok(String::from("aé€😀")));
+        assert_eq!(source.offset(), 4);
+        assert_eq!(source.peek_next(), Err(SourcingError::NoMoreChars));
+    }
+
+    #[test]
+    fn test_source_bytes_utf8_replace() {
+        let mut source = SourceBytesUtf8::new(
+            SourceInfo::new(PathBuf::from("--")),
+            b"a\xffb\xe2\x82",
+            InvalidUtf8Policy::Replace,
+        );
+
+        assert_eq!(
+            read_all(&mut source),
+            Ok(String::from("a\u{fffd}b\u{fffd}"))
+        );
+        assert_eq!(source.offset(), 4);
+    }
+
+    #[test]
+    fn test_source_bytes_utf8_skip() {
+        let mut source = SourceBytesUtf8::new(
+            SourceInfo::new(PathBuf::from("--")),
+            b"\xffa\xffb\xe2\x82",
+            InvalidUtf8Policy::Skip,
+        );
+
+        assert_eq!(read_all(&mut source), Ok(String::from("ab")));
+        assert_eq!(source.offset(), 2);
+    }
+
+    #[test]
+    fn test_source_bytes_utf8_abort() {
+        let mut source = SourceBytesUtf8::new(
+            SourceInfo::new(PathBuf::from("--")),
+            b"a\xffb",
+            InvalidUtf8Policy::Abort,
+        );
+
+        assert_eq!(source.eat_next(), Ok('a'));
+        assert_eq!(
+            source.peek_next(),
+            Err(SourcingError::InvalidUtf8 { byte_offset: 1 })
+        );
+        assert_eq!(
+            source.eat_next(),
+            Err(SourcingError::InvalidUtf8 { byte_offset: 1 })
+        );
+        assert_eq!(source.offset(), 1);
+    }
+}
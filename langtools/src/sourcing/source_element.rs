@@ -0,0 +1,81 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::{fmt::Debug, ops::Deref};
+
+// An element that a source can be read as. Text sources read `char`s and fold `\r\n`, `\r\r` and `\r` into `\n`.
+// Byte sources read `u8`s as-is since folding carriage returns would corrupt binary data, but `\n` still advances the
+// line in locations so that textual islands get useful line numbers.
+pub trait SourceElement: Copy + Eq {
+    type Buffer: Clone + Debug + Default + PartialEq + Deref<Target = Self::Slice>;
+    type Slice: ?Sized;
+
+    const LINE_FEED: Self;
+    const CARRIAGE_RETURN: Self;
+    const FOLD_NEWLINES: bool;
+
+    fn push_to_buffer(buffer: &mut Self::Buffer, value: Self);
+    fn clear_buffer(buffer: &mut Self::Buffer);
+    fn is_buffer_empty(buffer: &Self::Buffer) -> bool;
+}
+
+impl SourceElement for char {
+    type Buffer = String;
+    type Slice = str;
+
+    const LINE_FEED: Self = '\n';
+    const CARRIAGE_RETURN: Self = '\r';
+    const FOLD_NEWLINES: bool = true;
+
+    fn push_to_buffer(buffer: &mut Self::Buffer, value: Self) {
+        buffer.push(value);
+    }
+
+    fn clear_buffer(buffer: &mut Self::Buffer) {
+        buffer.clear();
+    }
+
+    fn is_buffer_empty(buffer: &Self::Buffer) -> bool {
+        buffer.is_empty()
+    }
+}
+
+impl SourceElement for u8 {
+    type Buffer = Vec<u8>;
+    type Slice = [u8];
+
+    const LINE_FEED: Self = b'\n';
+    const CARRIAGE_RETURN: Self = b'\r';
+    const FOLD_NEWLINES: bool = false;
+
+    fn push_to_buffer(buffer: &mut Self::Buffer, value: Self) {
+        buffer.push(value);
+    }
+
+    fn clear_buffer(buffer: &mut Self::Buffer) {
+        buffer.clear();
+    }
+
+    fn is_buffer_empty(buffer: &Self::Buffer) -> bool {
+        buffer.is_empty()
+    }
+}
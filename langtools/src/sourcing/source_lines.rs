@@ -0,0 +1,248 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::io::BufRead;
+
+use crate::domain::source_info::SourceInfo;
+
+use super::read_source::ReadSource;
+use super::sourcing_error::{Result, SourcingError};
+
+// Reads characters from a `BufRead` one line at a time so that I/O is amortized over whole lines. Line endings are kept
+// in the character stream, and a last line without a trailing newline is read as-is. Positions can't be marked since
+// lines that have been read are not kept.
+pub struct SourceLines<TReader: BufRead> {
+    info: SourceInfo,
+    reader: TReader,
+    line: String,
+    index: usize,
+    offset: usize,
+    // Byte offset of the start of `line` within the whole input, used to report where invalid UTF-8 is
+    line_byte_offset: usize,
+    // An error hit while reading the next line, returned once the characters before it have been read
+    pending_error: Option<SourcingError>,
+}
+
+impl<TReader: BufRead> SourceLines<TReader> {
+    pub fn new(info: SourceInfo, reader: TReader) -> Self {
+        let mut result = Self {
+            info,
+            reader,
+            line: String::new(),
+            index: 0,
+            offset: 0,
+            line_byte_offset: 0,
+            pending_error: None,
+        };
+
+        result.read_line();
+
+        result
+    }
+
+    // Replaces `line` with the next line of input once the current one has been read, so that `has_more` can be
+    // answered without reading
+    fn read_line(&mut self) {
+        if self.index < self.line.len() || self.pending_error.is_some() {
+            return;
+        }
+
+        self.line_byte_offset += self.line.len();
+        self.line.clear();
+        self.index = 0;
+
+        let mut bytes = Vec::new();
+
+        if let Err(err) = self.reader.read_until(b'\n', &mut bytes) {
+            self.pending_error = Some(SourcingError::Io { kind: err.kind() });
+        }
+
+        match String::from_utf8(bytes) {
+            Ok(line) => self.line = line,
+            Err(err) => {
+                let valid_up_to = err.utf8_error().valid_up_to();
+                let mut bytes = err.into_bytes();
+
+                bytes.truncate(valid_up_to);
+
+                self.line = String::from_utf8(bytes).expect("prefix should be valid");
+                self.pending_error = Some(SourcingError::InvalidUtf8 {
+                    byte_offset: self.line_byte_offset + valid_up_to,
+                });
+            }
+        }
+    }
+}
+
+impl<TReader: BufRead> ReadSource for SourceLines<TReader> {
+    fn info(&self) -> &SourceInfo {
+        &self.info
+    }
+
+    fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn has_more(&self) -> bool {
+        self.index < self.line.len() || self.pending_error.is_some()
+    }
+
+    fn peek_next(&mut self) -> Result<char> {
+        match self.line[self.index..].chars().next() {
+            Some(value) => Ok(value),
+            None => Err(self
+                .pending_error
+                .clone()
+                .unwrap_or(SourcingError::NoMoreChars)),
+        }
+    }
+
+    fn eat_next(&mut self) -> Result<char> {
+        let result = self.peek_next()?;
+
+        self.index += result.len_utf8();
+        self.offset += 1;
+        self.read_line();
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::path::PathBuf;
+
+    use crate::{
+        domain::token::{Token, TokenKind},
+        lexing::lexer::Lexer,
+        messaging::message_context::MessageContext,
+        sourcing::{source_reader::SourceReader, source_string::SourceString},
+    };
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+    enum TokenKindTest {
+        A,
+        AB,
+        B,
+    }
+
+    impl TokenKind for TokenKindTest {}
+
+    fn read_all(source: &mut dyn ReadSource) -> Result<String> {
+        let mut result = String::new();
+
+        while source.has_more() {
+            result.push(source.eat_next()?);
+        }
+
+        Ok(result)
+    }
+
+    fn lex_all(source: &mut dyn ReadSource) -> Vec<Token<TokenKindTest>> {
+        let mut lexer = Lexer::<TokenKindTest>::new();
+
+        assert_eq!(
+            lexer.add_trigger("a", |_, _| Some(TokenKindTest::A)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("ab", |_, _| Some(TokenKindTest::AB)),
+            Ok(())
+        );
+        assert_eq!(
+            lexer.add_trigger("b", |_, _| Some(TokenKindTest::B)),
+            Ok(())
+        );
+
+        for whitespace in [" ", "\n", "\r\n"] {
+            assert_eq!(lexer.add_trigger(whitespace, |_, _| None), Ok(()));
+        }
+
+        let mut source_reader = SourceReader::new(source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+
+        let mut message_context = MessageContext::new();
+
+        let result = lexer
+            .lex(&mut source_reader, &mut message_context)
+            .collect();
+
+        assert!(message_context.messages.is_empty());
+
+        result
+    }
+
+    #[test]
+    fn test_source_lines_empty() {
+        let mut source = SourceLines::new(SourceInfo::new(PathBuf::from("--")), Cursor::new(""));
+
+        assert_eq!(source.offset(), 0);
+        assert!(!source.has_more());
+        assert_eq!(source.peek_next(), Err(SourcingError::NoMoreChars));
+        assert_eq!(source.eat_next(), Err(SourcingError::NoMoreChars));
+    }
+
+    #[test]
+    fn test_source_lines_newlines() {
+        for data in ["a\nbé\r\n\nc", "a\nbé\r\n\nc\n", "\n\n"] {
+            let mut source =
+                SourceLines::new(SourceInfo::new(PathBuf::from("--")), Cursor::new(data));
+
+            assert_eq!(read_all(&mut source), Ok(String::from(data)));
+            assert_eq!(source.offset(), data.chars().count());
+            assert_eq!(source.peek_next(), Err(SourcingError::NoMoreChars));
+        }
+    }
+
+    #[test]
+    fn test_source_lines_invalid_utf8() {
+        let mut source = SourceLines::new(
+            SourceInfo::new(PathBuf::from("--")),
+            Cursor::new(b"ab\nc\xffd\n"),
+        );
+
+        assert_eq!(
+            read_all(&mut source),
+            Err(SourcingError::InvalidUtf8 { byte_offset: 4 })
+        );
+        assert_eq!(source.offset(), 4);
+        assert!(source.has_more());
+    }
+
+    #[test]
+    fn test_source_lines_same_tokens_as_source_string() {
+        for data in ["ab a\nb  ab\n\nba\n", "ab a\nb  ab\n\nba", "a\r\nb\r\n"] {
+            let mut source_string = SourceString::new(SourceInfo::new(PathBuf::from("--")), data);
+            let mut source_lines =
+                SourceLines::new(SourceInfo::new(PathBuf::from("--")), Cursor::new(data));
+
+            let expected = lex_all(&mut source_string);
+
+            assert!(!expected.is_empty());
+            assert_eq!(lex_all(&mut source_lines), expected);
+        }
+    }
+}
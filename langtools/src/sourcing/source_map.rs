@@ -0,0 +1,237 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::{collections::HashMap, mem, path::PathBuf};
+
+use crate::{
+    domain::{
+        line_index::LineIndex,
+        source_info::{SourceId, SourceInfo},
+        source_location::{Line, SourceLocation, COLUMN_INITIAL, LINE_INITIAL},
+    },
+    messaging::{
+        message::{Message, Severity},
+        message_context::MessageContext,
+    },
+};
+
+pub struct SourceMap {
+    sources: HashMap<PathBuf, SourceMapEntry>,
+}
+
+struct SourceMapEntry {
+    id: SourceId,
+    lines: Vec<String>,
+    line_index: LineIndex,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self {
+            sources: HashMap::new(),
+        }
+    }
+
+    // Assigns `info` its id, so add it before creating the source it is read from for tokens to carry the id. Adding a
+    // source with the same path again replaces its data but keeps its id.
+    pub fn add(&mut self, info: &mut SourceInfo, data: &str) {
+        let id = match self.sources.get(&info.path) {
+            Some(entry) => entry.id,
+            None => self.sources.len() as SourceId,
+        };
+
+        info.set_id(id);
+
+        self.sources.insert(
+            info.path.clone(),
+            SourceMapEntry {
+                id,
+                lines: split_source_lines(data),
+                line_index: LineIndex::new(data),
+            },
+        );
+    }
+
+    pub fn contains(&self, info: &SourceInfo) -> bool {
+        self.sources.contains_key(&info.path)
+    }
+
+    pub fn try_get_line(&self, info: &SourceInfo, line: Line) -> Option<&str> {
+        let index = line.checked_sub(LINE_INITIAL)? as usize;
+
+        self.sources
+            .get(&info.path)?
+            .lines
+            .get(index)
+            .map(|line| line.as_str())
+    }
+
+    // Emits a single warning if some lines of the source are indented with tabs and others with spaces. A line's style
+    // is that of its first character, and lines that are blank or aren't indented don't count. The warning points at
+    // the first line whose style differs from the first indented line.
+    pub fn check_mixed_indentation(&self, info: &SourceInfo, message_context: &mut MessageContext) {
+        let Some(entry) = self.sources.get(&info.path) else {
+            return;
+        };
+
+        let mut first_style = None;
+
+        for (index, line) in entry.lines.iter().enumerate() {
+            let style = match line.chars().next() {
+                Some(value @ (' ' | '\t')) if !line.trim().is_empty() => value,
+                _ => continue,
+            };
+
+            match first_style {
+                None => first_style = Some(style),
+                Some(first_style) if first_style != style => {
+                    let line = index as Line + LINE_INITIAL;
+                    let offset = entry
+                        .line_index
+                        .try_get_line_start(line)
+                        .expect("lines of a source should be in its line index");
+
+                    message_context.emit(Message::new_location(
+                        SourceLocation::new_from_info(info.clone(), offset, line, COLUMN_INITIAL),
+                        Severity::Warning,
+                        String::from("file mixes tabs and spaces for indentation"),
+                    ));
+
+                    return;
+                }
+                Some(_) => {}
+            }
+        }
+    }
+}
+
+impl Default for SourceMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Lines are split the same way that SourceReader folds newlines so that line numbers from locations line up
+fn split_source_lines(data: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut current = String::new();
+    let mut iter = data.chars().peekable();
+
+    while let Some(value) = iter.next() {
+        match value {
+            '\r' => {
+                if let Some('\r') | Some('\n') = iter.peek() {
+                    iter.next();
+                }
+
+                result.push(mem::take(&mut current));
+            }
+            '\n' => {
+                result.push(mem::take(&mut current));
+            }
+            _ => current.push(value),
+        }
+    }
+
+    result.push(current);
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::messaging::message::MessageSource;
+
+    use super::*;
+
+    #[test]
+    fn test_source_map_try_get_line() {
+        let mut info = SourceInfo::new(PathBuf::from("--"));
+        let mut source_map = SourceMap::new();
+        source_map.add(&mut info, "ab\ncd\r\nef\rgh");
+
+        assert!(source_map.contains(&info));
+        assert_eq!(source_map.try_get_line(&info, 0), None);
+        assert_eq!(source_map.try_get_line(&info, 1), Some("ab"));
+        assert_eq!(source_map.try_get_line(&info, 2), Some("cd"));
+        assert_eq!(source_map.try_get_line(&info, 3), Some("ef"));
+        assert_eq!(source_map.try_get_line(&info, 4), Some("gh"));
+        assert_eq!(source_map.try_get_line(&info, 5), None);
+        assert_eq!(
+            source_map.try_get_line(&SourceInfo::new(PathBuf::from("other")), 1),
+            None
+        );
+    }
+
+    #[test]
+    fn test_source_map_add_id() {
+        let mut a = SourceInfo::new(PathBuf::from("a.txt"));
+        let mut b = SourceInfo::new(PathBuf::from("b.txt"));
+        let mut source_map = SourceMap::new();
+        source_map.add(&mut a, "x");
+        source_map.add(&mut b, "y");
+
+        assert_eq!(a.id(), Some(0));
+        assert_eq!(b.id(), Some(1));
+        assert_eq!(SourceInfo::new(PathBuf::from("c.txt")).id(), None);
+
+        let mut a_again = SourceInfo::new(PathBuf::from("a.txt"));
+        source_map.add(&mut a_again, "z");
+
+        assert_eq!(a_again.id(), Some(0));
+        assert_eq!(a_again, SourceInfo::new(PathBuf::from("a.txt")));
+    }
+
+    #[test]
+    fn test_source_map_check_mixed_indentation() {
+        let mut info = SourceInfo::new(PathBuf::from("--"));
+        let mut source_map = SourceMap::new();
+        source_map.add(&mut info, "a\n\tb\n\n  \n\tc\r\n  d\n\te\n");
+
+        let mut message_context = MessageContext::new();
+        source_map.check_mixed_indentation(&info, &mut message_context);
+
+        assert_eq!(message_context.messages.len(), 1);
+        assert_eq!(message_context.messages[0].severity, Severity::Warning);
+        assert_eq!(
+            message_context.messages[0].description,
+            "file mixes tabs and spaces for indentation"
+        );
+        assert!(matches!(
+            &message_context.messages[0].source,
+            MessageSource::Location { location }
+                if *location == SourceLocation::new(PathBuf::from("--"), 13, 6, 1)
+        ));
+    }
+
+    #[test]
+    fn test_source_map_check_mixed_indentation_consistent() {
+        let mut info = SourceInfo::new(PathBuf::from("--"));
+        let mut source_map = SourceMap::new();
+        source_map.add(&mut info, "a\n  b\n    c\n \t\nd");
+
+        let mut message_context = MessageContext::new();
+        source_map.check_mixed_indentation(&info, &mut message_context);
+
+        assert!(message_context.messages.is_empty());
+    }
+}
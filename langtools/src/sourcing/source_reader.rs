@@ -21,7 +21,8 @@
 // SOFTWARE.
 
 use super::{
-    read_source::ReadSource,
+    read_source::{ReadSource, SourceMark},
+    source_element::SourceElement,
     sourcing_error::{self, SourcingError},
 };
 use crate::domain::{
@@ -29,15 +30,24 @@ use crate::domain::{
     source_location::{SourceLocation, COLUMN_INITIAL, LINE_INITIAL, OFFSET_INITIAL},
 };
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct SourceReaderMark<TElement: SourceElement = char> {
+    source: SourceMark,
+    location: SourceLocation,
+    line: TElement::Buffer,
+}
+
 #[readonly::make]
-pub struct SourceReader<'source> {
-    source: &'source mut dyn ReadSource,
+pub struct SourceReader<'source, TElement: SourceElement = char> {
+    source: &'source mut dyn ReadSource<TElement>,
     pub location: SourceLocation,
-    buffer: Option<String>,
+    buffer: Option<TElement::Buffer>,
+    // Everything eaten since the last newline, regardless of buffering
+    line: TElement::Buffer,
 }
 
-impl<'source> SourceReader<'source> {
-    pub fn new(source: &'source mut dyn ReadSource) -> Self {
+impl<'source, TElement: SourceElement> SourceReader<'source, TElement> {
+    pub fn new(source: &'source mut dyn ReadSource<TElement>) -> Self {
         let info = source.info().clone();
 
         Self::new_with_location(
@@ -47,13 +57,14 @@ impl<'source> SourceReader<'source> {
     }
 
     pub fn new_with_location(
-        source: &'source mut dyn ReadSource,
+        source: &'source mut dyn ReadSource<TElement>,
         location: SourceLocation,
     ) -> Self {
         Self {
             source,
             location,
             buffer: None,
+            line: TElement::Buffer::default(),
         }
     }
 
@@ -65,7 +76,7 @@ impl<'source> SourceReader<'source> {
         if self.buffer.is_some() {
             Err(SourcingError::BufferingAlreadyEnabled)
         } else {
-            self.buffer = Some(String::new());
+            self.buffer = Some(TElement::Buffer::default());
             Ok(())
         }
     }
@@ -82,35 +93,72 @@ impl<'source> SourceReader<'source> {
     pub fn is_buffer_empty(&self) -> bool {
         self.buffer
             .as_ref()
-            .map(|buffer| buffer.is_empty())
+            .map(TElement::is_buffer_empty)
             .unwrap_or(true)
     }
 
-    pub fn clear_buffer(&mut self) -> sourcing_error::Result<()> {
+    pub fn peek_buffer(&self) -> sourcing_error::Result<&TElement::Slice> {
         self.buffer
-            .as_mut()
-            .ok_or(SourcingError::BufferingNeedsToBeEnabled)?
-            .clear();
+            .as_deref()
+            .ok_or(SourcingError::BufferingNeedsToBeEnabled)
+    }
+
+    pub fn clear_buffer(&mut self) -> sourcing_error::Result<()> {
+        TElement::clear_buffer(
+            self.buffer
+                .as_mut()
+                .ok_or(SourcingError::BufferingNeedsToBeEnabled)?,
+        );
 
         Ok(())
     }
 
-    pub fn pop_buffer(&mut self) -> sourcing_error::Result<String> {
+    pub fn pop_buffer(&mut self) -> sourcing_error::Result<TElement::Buffer> {
         self.buffer
-            .replace(String::new())
+            .replace(TElement::Buffer::default())
             .ok_or(SourcingError::BufferingNeedsToBeEnabled)
     }
 
-    fn eat_next_helper_fold_newlines(&mut self) -> sourcing_error::Result<char> {
+    // The text eaten on the current line so far, not counting the newline that ended the previous line. A reader created
+    // partway through a line with `new_with_location` only has what it has eaten itself.
+    pub fn current_line_so_far(&self) -> &TElement::Slice {
+        &self.line
+    }
+
+    pub fn mark_position(&self) -> sourcing_error::Result<SourceReaderMark<TElement>> {
+        Ok(SourceReaderMark {
+            source: self.source.mark()?,
+            location: self.location.clone(),
+            line: self.line.clone(),
+        })
+    }
+
+    // Restoring a position clears the buffer, since anything buffered after the mark would no longer match the source
+    pub fn restore_position(
+        &mut self,
+        mark: &SourceReaderMark<TElement>,
+    ) -> sourcing_error::Result<()> {
+        self.source.restore(&mark.source)?;
+        self.location = mark.location.clone();
+        self.line = mark.line.clone();
+
+        if let Some(ref mut buffer) = self.buffer {
+            TElement::clear_buffer(buffer);
+        }
+
+        Ok(())
+    }
+
+    fn eat_next_helper_fold_newlines(&mut self) -> sourcing_error::Result<TElement> {
         let result = self.source.eat_next()?;
 
-        if result == '\r' {
+        if TElement::FOLD_NEWLINES && result == TElement::CARRIAGE_RETURN {
             match self.source.peek_next() {
-                Ok('\r') | Ok('\n') => {
+                Ok(next) if next == TElement::CARRIAGE_RETURN || next == TElement::LINE_FEED => {
                     self.source.eat_next()?;
-                    Ok('\n')
+                    Ok(TElement::LINE_FEED)
                 }
-                Ok(_) => Ok('\n'),
+                Ok(_) => Ok(TElement::LINE_FEED),
                 error => error,
             }
         } else {
@@ -118,16 +166,18 @@ impl<'source> SourceReader<'source> {
         }
     }
 
-    fn eat_next_helper_update_location(&mut self) -> sourcing_error::Result<char> {
+    fn eat_next_helper_update_location(&mut self) -> sourcing_error::Result<TElement> {
         let result = self.eat_next_helper_fold_newlines()?;
 
-        assert!(result != '\r');
+        assert!(!TElement::FOLD_NEWLINES || result != TElement::CARRIAGE_RETURN);
 
-        if result == '\n' {
+        if result == TElement::LINE_FEED {
             self.location.line += 1;
             self.location.column = COLUMN_INITIAL;
+            TElement::clear_buffer(&mut self.line);
         } else {
             self.location.column += 1;
+            TElement::push_to_buffer(&mut self.line, result);
         }
 
         self.location.offset = self.source.offset();
@@ -135,18 +185,18 @@ impl<'source> SourceReader<'source> {
         Ok(result)
     }
 
-    fn eat_next_helper_push_buffer(&mut self) -> sourcing_error::Result<char> {
+    fn eat_next_helper_push_buffer(&mut self) -> sourcing_error::Result<TElement> {
         let result = self.eat_next_helper_update_location()?;
 
         if let Some(ref mut buffer) = self.buffer {
-            buffer.push(result);
+            TElement::push_to_buffer(buffer, result);
         }
 
         Ok(result)
     }
 }
 
-impl<'source> ReadSource for SourceReader<'source> {
+impl<'source, TElement: SourceElement> ReadSource<TElement> for SourceReader<'source, TElement> {
     fn info(&self) -> &SourceInfo {
         self.source.info()
     }
@@ -155,19 +205,21 @@ impl<'source> ReadSource for SourceReader<'source> {
         self.source.offset()
     }
 
-    fn has_more(&mut self) -> bool {
+    fn has_more(&self) -> bool {
         self.source.has_more()
     }
 
-    fn peek_next(&mut self) -> sourcing_error::Result<char> {
+    fn peek_next(&mut self) -> sourcing_error::Result<TElement> {
         let result = self.source.peek_next();
         match result {
-            Ok('\r') => Ok('\n'),
+            Ok(value) if TElement::FOLD_NEWLINES && value == TElement::CARRIAGE_RETURN => {
+                Ok(TElement::LINE_FEED)
+            }
             _ => result,
         }
     }
 
-    fn eat_next(&mut self) -> sourcing_error::Result<char> {
+    fn eat_next(&mut self) -> sourcing_error::Result<TElement> {
         self.eat_next_helper_push_buffer()
     }
 }
@@ -177,9 +229,51 @@ mod tests {
     use std::path::PathBuf;
 
     use super::{
-        super::{source_string::SourceString, sourcing_error::SourcingError},
+        super::{
+            source_bytes_raw::SourceBytesRaw, source_string::SourceString,
+            sourcing_error::SourcingError,
+        },
         *,
     };
+    use crate::lexing::{dfsa::DFSA, dfsa_executor::DFSAExecutor};
+
+    #[test]
+    fn test_source_reader_current_line_so_far() {
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "ab\r\n#cd");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.current_line_so_far(), "");
+        assert_eq!(source_reader.eat_next(), Ok('a'));
+        assert_eq!(source_reader.current_line_so_far(), "a");
+        assert_eq!(source_reader.eat_next(), Ok('b'));
+        assert_eq!(source_reader.eat_next(), Ok('\n'));
+        assert_eq!(source_reader.current_line_so_far(), "");
+        assert_eq!(source_reader.eat_next(), Ok('#'));
+
+        let mark = source_reader.mark_position().unwrap();
+
+        assert_eq!(source_reader.eat_next(), Ok('c'));
+        assert_eq!(source_reader.current_line_so_far(), "#c");
+        assert_eq!(source_reader.restore_position(&mark), Ok(()));
+        assert_eq!(source_reader.current_line_so_far(), "#");
+    }
+
+    #[test]
+    fn test_source_reader_peek_buffer() {
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "ab");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(
+            source_reader.peek_buffer(),
+            Err(SourcingError::BufferingNeedsToBeEnabled)
+        );
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+        assert_eq!(source_reader.eat_next(), Ok('a'));
+        assert_eq!(source_reader.peek_buffer(), Ok("a"));
+        assert_eq!(source_reader.eat_next(), Ok('b'));
+        assert_eq!(source_reader.peek_buffer(), Ok("ab"));
+    }
 
     #[test]
     fn test_source_reader_empty() {
@@ -806,4 +900,97 @@ mod tests {
 
         assert_eq!(source_reader.pop_buffer(), Ok(String::from("bc")));
     }
+
+    #[test]
+    fn test_source_reader_bytes_no_newline_folding() {
+        let mut source = SourceBytesRaw::new(SourceInfo::new(PathBuf::from("--")), b"\r\n\x00");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.peek_next(), Ok(b'\r'));
+        assert_eq!(source_reader.eat_next(), Ok(b'\r'));
+        assert_eq!(
+            source_reader.location,
+            SourceLocation::new(PathBuf::from("--"), 1, 1, 2)
+        );
+        assert_eq!(source_reader.eat_next(), Ok(b'\n'));
+        assert_eq!(
+            source_reader.location,
+            SourceLocation::new(PathBuf::from("--"), 2, 2, 1)
+        );
+        assert_eq!(source_reader.eat_next(), Ok(0x00));
+        assert!(!source_reader.has_more());
+    }
+
+    #[test]
+    fn test_source_reader_bytes_lex_pattern() {
+        let mut dfsa = DFSA::<u8, &str>::new();
+        let start = dfsa.add_state();
+        let magic_0 = dfsa.add_state();
+        let magic = dfsa.add_state_with_action("magic");
+        let ok_0 = dfsa.add_state();
+        let ok = dfsa.add_state_with_action("ok");
+        dfsa.add_transition(start, 0xca, magic_0).unwrap();
+        dfsa.add_transition(magic_0, 0xfe, magic).unwrap();
+        dfsa.add_transition(start, b'O', ok_0).unwrap();
+        dfsa.add_transition(ok_0, b'K', ok).unwrap();
+        dfsa.set_start_id(start).unwrap();
+
+        let mut source = SourceBytesRaw::new(
+            SourceInfo::new(PathBuf::from("--")),
+            &[0xca, 0xfe, b'O', b'K', 0xca, 0xfe],
+        );
+        let mut source_reader = SourceReader::new(&mut source);
+        source_reader.enable_buffering().unwrap();
+
+        let mut dfsa_executor = DFSAExecutor::new(&dfsa).unwrap();
+        let mut lexed = Vec::new();
+
+        while source_reader.has_more() {
+            dfsa_executor.reset();
+
+            while let Ok(value) = source_reader.peek_next() {
+                if dfsa_executor.step(value).is_err() {
+                    break;
+                }
+
+                source_reader.eat_next().unwrap();
+            }
+
+            lexed.push((
+                dfsa_executor.current_action().copied(),
+                source_reader.pop_buffer().unwrap(),
+            ));
+        }
+
+        assert_eq!(
+            lexed,
+            Vec::from([
+                (Some("magic"), Vec::from([0xca, 0xfe])),
+                (Some("ok"), Vec::from([b'O', b'K'])),
+                (Some("magic"), Vec::from([0xca, 0xfe])),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_source_reader_mark_restore() {
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "a\nb");
+        let mut source_reader = SourceReader::new(&mut source);
+
+        assert_eq!(source_reader.enable_buffering(), Ok(()));
+        assert_eq!(source_reader.eat_next(), Ok('a'));
+
+        let mark = source_reader.mark_position().unwrap();
+
+        assert_eq!(source_reader.eat_next(), Ok('\n'));
+        assert_eq!(source_reader.eat_next(), Ok('b'));
+        assert_eq!(source_reader.restore_position(&mark), Ok(()));
+        assert_eq!(
+            source_reader.location,
+            SourceLocation::new(PathBuf::from("--"), 1, 1, 2)
+        );
+        assert!(source_reader.is_buffer_empty());
+        assert_eq!(source_reader.eat_next(), Ok('\n'));
+        assert_eq!(source_reader.peek_buffer(), Ok("\n"));
+    }
 }
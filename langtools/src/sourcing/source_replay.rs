@@ -0,0 +1,151 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+use crate::domain::{
+    source_info::SourceInfo,
+    source_location::{Offset, SourceLocation},
+};
+
+use super::read_source::{ReadSource, SourceMark};
+use super::sourcing_error::{Result, SourcingError};
+
+// Replays recorded characters along with the locations they were recorded at, so that tests can control locations
+// exactly. The offset is the recorded offset of the next character, or one past the offset of the last character once
+// they have all been read. `SourceReader` keeps its own location and recomputes it from the characters it reads, so
+// read from a replay directly when the recorded lines and columns matter.
+pub struct SourceReplay {
+    info: SourceInfo,
+    data: Vec<(char, SourceLocation)>,
+    index: usize,
+}
+
+impl SourceReplay {
+    pub fn new(info: SourceInfo, data: Vec<(char, SourceLocation)>) -> Self {
+        Self {
+            info,
+            data,
+            index: 0,
+        }
+    }
+
+    // The recorded location of the next character
+    pub fn location(&self) -> Option<&SourceLocation> {
+        self.data.get(self.index).map(|(_, location)| location)
+    }
+
+    fn offset_at(&self, index: usize) -> Offset {
+        match self.data.get(index) {
+            Some((_, location)) => location.offset,
+            None => self
+                .data
+                .last()
+                .map_or(0, |(_, location)| location.offset + 1),
+        }
+    }
+}
+
+impl ReadSource for SourceReplay {
+    fn info(&self) -> &SourceInfo {
+        &self.info
+    }
+
+    fn offset(&self) -> usize {
+        self.offset_at(self.index)
+    }
+
+    fn has_more(&self) -> bool {
+        self.index < self.data.len()
+    }
+
+    fn peek_next(&mut self) -> Result<char> {
+        self.data
+            .get(self.index)
+            .map(|(value, _)| *value)
+            .ok_or(SourcingError::NoMoreChars)
+    }
+
+    fn eat_next(&mut self) -> Result<char> {
+        let result = self.peek_next()?;
+
+        self.index += 1;
+
+        Ok(result)
+    }
+
+    fn mark(&self) -> Result<SourceMark> {
+        Ok(SourceMark {
+            index: self.index,
+            offset: self.offset(),
+        })
+    }
+
+    fn restore(&mut self, mark: &SourceMark) -> Result<()> {
+        assert!(
+            mark.index <= self.data.len(),
+            "mark is not from this source"
+        );
+
+        self.index = mark.index;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn test_source_replay() {
+        let info = SourceInfo::new(PathBuf::from("--"));
+
+        // Locations that no real source would produce, to check that they come from the recording
+        let mut source = SourceReplay::new(
+            info.clone(),
+            Vec::from([
+                ('a', SourceLocation::new_from_info(info.clone(), 10, 5, 7)),
+                ('b', SourceLocation::new_from_info(info.clone(), 20, 9, 1)),
+            ]),
+        );
+
+        assert_eq!(source.offset(), 10);
+        assert_eq!(source.location().map(|location| location.line), Some(5));
+        assert_eq!(source.eat_next(), Ok('a'));
+
+        let mark = source.mark().unwrap();
+
+        assert_eq!(source.offset(), 20);
+        assert_eq!(
+            source.location(),
+            Some(&SourceLocation::new_from_info(info, 20, 9, 1))
+        );
+        assert_eq!(source.eat_next(), Ok('b'));
+        assert_eq!(source.offset(), 21);
+        assert!(!source.has_more());
+        assert_eq!(source.location(), None);
+        assert_eq!(source.peek_next(), Err(SourcingError::NoMoreChars));
+
+        assert_eq!(source.restore(&mark), Ok(()));
+        assert_eq!(source.peek_next(), Ok('b'));
+    }
+}
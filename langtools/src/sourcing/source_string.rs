@@ -21,15 +21,16 @@
 // SOFTWARE.
 
 use crate::domain::source_info::SourceInfo;
-use std::iter::Peekable;
-use std::str::Chars;
 
-use super::read_source::ReadSource;
+use super::read_source::{ReadSource, SourceMark};
 use super::sourcing_error::{Result, SourcingError};
 
+const BOM: char = '\u{feff}';
+
 pub struct SourceString<'string> {
     info: SourceInfo,
-    iter: Peekable<Chars<'string>>,
+    data: &'string str,
+    index: usize,
     offset: usize,
 }
 
@@ -37,7 +38,26 @@ impl<'string> SourceString<'string> {
     pub fn new(info: SourceInfo, data: &'string str) -> Self {
         Self {
             info,
-            iter: data.chars().peekable(),
+            data,
+            index: 0,
+            offset: 0,
+        }
+    }
+
+    // Like `new`, but a leading byte order mark is skipped and recorded in `SourceInfo::had_bom` instead of being read
+    // as a character. Offsets start after it so the first character is still at offset 0.
+    pub fn new_recording_bom(mut info: SourceInfo, data: &'string str) -> Self {
+        let index = if data.starts_with(BOM) {
+            info.had_bom = true;
+            BOM.len_utf8()
+        } else {
+            0
+        };
+
+        Self {
+            info,
+            data,
+            index,
             offset: 0,
         }
     }
@@ -52,17 +72,43 @@ impl<'string> ReadSource for SourceString<'string> {
         self.offset
     }
 
-    fn has_more(&mut self) -> bool {
-        self.iter.peek().is_some()
+    fn has_more(&self) -> bool {
+        self.index < self.data.len()
     }
 
     fn peek_next(&mut self) -> Result<char> {
-        self.iter.peek().ok_or(SourcingError::NoMoreChars).copied()
+        self.data[self.index..]
+            .chars()
+            .next()
+            .ok_or(SourcingError::NoMoreChars)
     }
 
     fn eat_next(&mut self) -> Result<char> {
+        let result = self.peek_next()?;
+
+        self.index += result.len_utf8();
         self.offset += 1;
-        self.iter.next().ok_or(SourcingError::NoMoreChars)
+
+        Ok(result)
+    }
+
+    fn mark(&self) -> Result<SourceMark> {
+        Ok(SourceMark {
+            index: self.index,
+            offset: self.offset,
+        })
+    }
+
+    fn restore(&mut self, mark: &SourceMark) -> Result<()> {
+        assert!(
+            self.data.is_char_boundary(mark.index),
+            "mark is not from this source"
+        );
+
+        self.index = mark.index;
+        self.offset = mark.offset;
+
+        Ok(())
     }
 }
 
@@ -122,4 +168,48 @@ mod tests {
         assert_eq!(source.peek_next(), Err(SourcingError::NoMoreChars));
         assert_eq!(source.eat_next(), Err(SourcingError::NoMoreChars));
     }
+
+    #[test]
+    fn test_source_string_has_more_shared() {
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "é");
+
+        let source_shared = &source;
+        assert!(source_shared.has_more());
+
+        assert_eq!(source.eat_next(), Ok('é'));
+
+        let source_shared = &source;
+        assert!(!source_shared.has_more());
+        assert_eq!(source_shared.offset(), 1);
+    }
+
+    #[test]
+    fn test_source_string_mark_restore() {
+        let mut source = SourceString::new(SourceInfo::new(PathBuf::from("--")), "éa");
+
+        assert_eq!(source.eat_next(), Ok('é'));
+
+        let mark = source.mark().unwrap();
+
+        assert_eq!(source.eat_next(), Ok('a'));
+        assert!(!source.has_more());
+        assert_eq!(source.restore(&mark), Ok(()));
+        assert_eq!(source.offset(), 1);
+        assert_eq!(source.eat_next(), Ok('a'));
+    }
+
+    #[test]
+    fn test_source_string_recording_bom() {
+        let mut source =
+            SourceString::new_recording_bom(SourceInfo::new(PathBuf::from("--")), "\u{feff}a");
+
+        assert!(source.info().had_bom);
+        assert_eq!(source.offset(), 0);
+        assert_eq!(source.eat_next(), Ok('a'));
+        assert!(!source.has_more());
+
+        let source = SourceString::new_recording_bom(SourceInfo::new(PathBuf::from("--")), "a");
+
+        assert!(!source.info().had_bom);
+    }
 }
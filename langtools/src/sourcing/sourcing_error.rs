@@ -22,16 +22,20 @@
 
 use std::error::Error;
 use std::fmt::{self, Display, Formatter};
+use std::io;
 use std::result;
 
 pub type Result<TValue> = result::Result<TValue, SourcingError>;
 
-#[derive(Debug, PartialEq, PartialOrd)]
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub enum SourcingError {
     NoMoreChars,
     BufferingAlreadyEnabled,
     BufferingAlreadyDisabled,
     BufferingNeedsToBeEnabled,
+    MarkingNotSupported,
+    InvalidUtf8 { byte_offset: usize },
+    Io { kind: io::ErrorKind },
 }
 
 impl Error for SourcingError {}
@@ -45,6 +49,13 @@ impl Display for SourcingError {
             SourcingError::BufferingNeedsToBeEnabled => {
                 write!(f, "buffering must be enabled for it to be accessed")
             }
+            SourcingError::MarkingNotSupported => {
+                write!(f, "source does not support marking and restoring positions")
+            }
+            SourcingError::InvalidUtf8 { byte_offset } => {
+                write!(f, "invalid UTF-8 at byte {byte_offset}")
+            }
+            SourcingError::Io { kind } => write!(f, "I/O error while reading source: {kind}"),
         }
     }
 }
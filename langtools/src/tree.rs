@@ -22,10 +22,126 @@
 
 use core::fmt::Debug;
 
-use crate::domain::token::Token;
+use crate::domain::{source_location::SourceLocation, token::Token};
 
 pub mod visit;
 
 pub trait Tree: Debug + Clone + PartialEq + Eq + visit::Visit {
     fn token(&self) -> &Token<()>;
+
+    // Trees with children should override this so that the traversal helpers below can walk into them
+    fn children(&self) -> Vec<&Self> {
+        Vec::new()
+    }
+
+    // Searches this tree and its descendants in pre-order
+    fn find_first<F: Fn(&Token<()>) -> bool>(&self, predicate: F) -> Option<&Self> {
+        let mut stack = Vec::from([self]);
+
+        while let Some(tree) = stack.pop() {
+            if predicate(tree.token()) {
+                return Some(tree);
+            }
+
+            stack.extend(tree.children().into_iter().rev());
+        }
+
+        None
+    }
+
+    // Token kinds are erased once tokens are stored in a tree, so matching is done with a predicate over the token
+    // rather than by kind
+    fn contains_token<F: Fn(&Token<()>) -> bool>(&self, predicate: F) -> bool {
+        self.find_first(predicate).is_some()
+    }
+}
+
+// Trees that have a node to stand in for input that failed to parse, so that error recovery can return a partial tree
+pub trait ErrorTree: Tree {
+    fn error_node(location: SourceLocation) -> Self;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{visit::Visit, *};
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    enum TreeTest {
+        Leaf(Token<()>),
+        Node(Token<()>, Vec<TreeTest>),
+    }
+
+    impl Tree for TreeTest {
+        fn token(&self) -> &Token<()> {
+            match self {
+                TreeTest::Leaf(token) => token,
+                TreeTest::Node(token, _) => token,
+            }
+        }
+
+        fn children(&self) -> Vec<&Self> {
+            match self {
+                TreeTest::Leaf(_) => Vec::new(),
+                TreeTest::Node(_, children) => children.iter().collect(),
+            }
+        }
+    }
+
+    impl Visit for TreeTest {
+        fn visit(&self, _: visit::VisitCallback<Self>) {}
+
+        fn visit_mut(&self, _: visit::VisitCallbackMut<Self>) {}
+    }
+
+    fn token(offset: usize, text: &str) -> Token<()> {
+        Token::new(
+            PathBuf::from("--"),
+            offset,
+            1,
+            offset as u32 + 1,
+            String::from(text),
+            (),
+        )
+    }
+
+    fn tree() -> TreeTest {
+        TreeTest::Node(
+            token(0, "("),
+            Vec::from([
+                TreeTest::Leaf(token(1, "a")),
+                TreeTest::Node(
+                    token(2, "("),
+                    Vec::from([TreeTest::Node(
+                        token(3, "("),
+                        Vec::from([TreeTest::Leaf(token(4, "b"))]),
+                    )]),
+                ),
+                TreeTest::Leaf(token(5, "b")),
+            ]),
+        )
+    }
+
+    #[test]
+    fn test_tree_find_first_nested() {
+        assert_eq!(
+            tree().find_first(|token| token.text == "b"),
+            Some(&TreeTest::Leaf(token(4, "b")))
+        );
+    }
+
+    #[test]
+    fn test_tree_find_first_self() {
+        assert_eq!(
+            tree().find_first(|token| token.text == "(").cloned(),
+            Some(tree())
+        );
+    }
+
+    #[test]
+    fn test_tree_contains_token() {
+        assert!(tree().contains_token(|token| token.text == "a"));
+        assert!(!tree().contains_token(|token| token.text == "c"));
+    }
 }
@@ -0,0 +1,200 @@
+// MIT License
+//
+// Copyright (c) 2022 Sophie Katz
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use langtools::{
+    domain::token::{Token, TokenKind},
+    lexing::lexer::Lexer,
+    messaging::message::{MessageSource, Severity},
+    parsing::{
+        parse::Parse, parse_str, parser_sequential::ParserSequentialBuilder,
+        parser_token::ParserTokenBuilder,
+    },
+    sourcing::read_source::ReadSource,
+    tree::{visit::Visit, Tree},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+enum TokenKindExpression {
+    Integer,
+    Plus,
+}
+
+impl TokenKind for TokenKindExpression {}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum TreeExpression {
+    Integer(Token<()>),
+    Plus(Token<()>),
+    Add(Token<()>, Box<TreeExpression>, Box<TreeExpression>),
+}
+
+impl Tree for TreeExpression {
+    fn token(&self) -> &Token<()> {
+        match self {
+            TreeExpression::Integer(token) => token,
+            TreeExpression::Plus(token) => token,
+            TreeExpression::Add(token, _, _) => token,
+        }
+    }
+}
+
+impl Visit for TreeExpression {
+    fn visit(&self, _: langtools::tree::visit::VisitCallback<Self>) {}
+
+    fn visit_mut(&self, _: langtools::tree::visit::VisitCallbackMut<Self>) {}
+}
+
+fn lex_integer(source: &mut dyn ReadSource, _: &str) -> Option<TokenKindExpression> {
+    while source.peek_next().is_ok_and(|value| value.is_ascii_digit()) {
+        let _ = source.eat_next();
+    }
+
+    Some(TokenKindExpression::Integer)
+}
+
+fn create_lexer() -> Lexer<TokenKindExpression> {
+    let mut lexer = Lexer::new();
+
+    for digit in '0'..='9' {
+        lexer.add_trigger(&digit.to_string(), lex_integer).unwrap();
+    }
+
+    lexer
+        .add_trigger("+", |_, _| Some(TokenKindExpression::Plus))
+        .unwrap();
+    lexer.add_trigger(" ", |_, _| None).unwrap();
+
+    lexer
+}
+
+fn create_parser() -> Box<dyn Parse<TokenKindExpression, TreeExpression>> {
+    create_parser_with_commit(false)
+}
+
+// With `commit`, failing after the left operand is a committed error
+fn create_parser_with_commit(commit: bool) -> Box<dyn Parse<TokenKindExpression, TreeExpression>> {
+    let mut builder = ParserSequentialBuilder::<TokenKindExpression, TreeExpression>::default();
+
+    builder.sequence(Box::new(
+        ParserTokenBuilder::default()
+            .token_kind(TokenKindExpression::Integer)
+            .action(TreeExpression::Integer)
+            .build()
+            .unwrap(),
+    ));
+
+    if commit {
+        builder.commit();
+    }
+
+    Box::new(
+        builder
+            .sequence(Box::new(
+                ParserTokenBuilder::default()
+                    .token_kind(TokenKindExpression::Plus)
+                    .action(TreeExpression::Plus)
+                    .build()
+                    .unwrap(),
+            ))
+            .sequence(Box::new(
+                ParserTokenBuilder::default()
+                    .token_kind(TokenKindExpression::Integer)
+                    .action(TreeExpression::Integer)
+                    .build()
+                    .unwrap(),
+            ))
+            .action(|token, mut children| {
+                let right = children.pop().unwrap();
+                children.pop();
+                let left = children.pop().unwrap();
+
+                TreeExpression::Add(token, Box::new(left), Box::new(right))
+            })
+            .build()
+            .unwrap(),
+    )
+}
+
+fn integer_text(tree: &TreeExpression) -> &str {
+    match tree {
+        TreeExpression::Integer(token) => &token.text,
+        _ => panic!("expected an integer"),
+    }
+}
+
+#[test]
+fn test_parse_str_expression() {
+    let lexer = create_lexer();
+    let parser = create_parser();
+
+    let (tree, message_context) = parse_str("12 + 345", &lexer, parser.as_ref());
+
+    assert!(message_context.messages.is_empty());
+
+    match tree {
+        Some(TreeExpression::Add(_, left, right)) => {
+            assert_eq!(integer_text(&left), "12");
+            assert_eq!(integer_text(&right), "345");
+        }
+        tree => panic!("expected an addition, not {tree:?}"),
+    }
+}
+
+#[test]
+fn test_parse_str_expression_error() {
+    let lexer = create_lexer();
+    let parser = create_parser();
+
+    let (tree, message_context) = parse_str("12 + + 3", &lexer, parser.as_ref());
+
+    assert_eq!(tree, None);
+    assert_eq!(message_context.count_with_severity(Severity::Error), 1);
+}
+
+#[test]
+fn test_parse_str_expression_trailing_tokens() {
+    let lexer = create_lexer();
+    let parser = create_parser();
+
+    let (tree, message_context) = parse_str("1 + 2 3", &lexer, parser.as_ref());
+
+    assert_eq!(tree, None);
+    assert_eq!(
+        message_context.messages[0].description,
+        "expected end of source"
+    );
+}
+
+#[test]
+fn test_parse_str_expression_committed_error() {
+    let lexer = create_lexer();
+    let parser = create_parser_with_commit(true);
+
+    let (tree, message_context) = parse_str("12 3", &lexer, parser.as_ref());
+
+    assert_eq!(tree, None);
+    assert_eq!(message_context.messages.len(), 1);
+    assert!(matches!(
+        &message_context.messages[0].source,
+        MessageSource::Location { location } if location.offset == 3
+    ));
+}
@@ -20,8 +20,9 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use langtools::{language_declaration::LanguageDeclaration, sourcing::source_new_string};
+use langtools::sourcing::source_new_string;
 
+#[allow(dead_code)]
 fn create_toy_lang() {
     // let mut toy_lang = LanguageDeclaration::default();
 
@@ -30,7 +31,7 @@ fn create_toy_lang() {
 
 #[test]
 fn test_toy() {
-    let mut source = source_new_string(
+    let _source = source_new_string(
         "
 
     function main() {